@@ -1,23 +1,73 @@
 use std::env;
 
 use anyhow::Context;
+use chrono::Utc;
+use tokio::sync::broadcast;
+use tracing::warn;
 
 use blazebooru_core::{config::BlazeBooruConfig, BlazeBooruCore};
 
-use crate::{auth::BlazeBooruAuth, server::BlazeBooruServer};
+use crate::{
+    auth::{access_token_lifetime, BlazeBooruAuth, Keys},
+    oidc::OidcClient,
+    rate_limit::{CommentRateLimiter, LoginRateLimiter},
+    server::BlazeBooruServer,
+};
+
+/// Number of events buffered for each client before it starts missing them
+const EVENT_CHANNEL_CAPACITY: usize = 256;
 
 pub async fn server(config: BlazeBooruConfig, core: BlazeBooruCore, serve_files: bool) -> Result<(), anyhow::Error> {
     let jwt_secret = env::var("BLAZEBOORU_JWT_SECRET")
         .ok()
-        .or_else(|| config.jwt_secret.clone())
-        .context("BLAZEBOORU_JWT_SECRET is not set")?;
+        .or_else(|| config.jwt_secret.clone());
+
+    let keys = Keys::load(jwt_secret.as_deref().map(str::as_bytes), &config.jwt_keys)
+        .context("Error loading JWT keys. Either BLAZEBOORU_JWT_SECRET or jwt-keys must be set")?;
+
+    let auth = BlazeBooruAuth::new(keys);
+
+    // Restore recently revoked sessions, so that their
+    // access tokens remain rejected across restarts.
+    let revoked_sessions = core
+        .get_revoked_sessions(Utc::now() - access_token_lifetime())
+        .await
+        .context("Error getting revoked sessions")?;
+
+    auth.revoke_sessions(revoked_sessions);
+
+    let login_limiter = LoginRateLimiter::new(&config);
+    let comment_limiter = CommentRateLimiter::new(&config);
+    let (events, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
 
-    let auth = BlazeBooruAuth::new(jwt_secret.as_bytes());
+    let oidc = if let Some(oidc_config) = &config.oidc {
+        let mut oidc_config = oidc_config.clone();
+
+        if let Ok(client_secret) = env::var("BLAZEBOORU_OIDC_CLIENT_SECRET") {
+            oidc_config.client_secret = Some(client_secret);
+        }
+
+        let oidc = OidcClient::new(oidc_config);
+
+        // Logins are unavailable until the provider can be reached,
+        // but that should not prevent the rest of the server from starting.
+        if let Err(err) = oidc.discover().await {
+            warn!("Error discovering OIDC provider, retrying on first login: {err:#}");
+        }
+
+        Some(oidc)
+    } else {
+        None
+    };
 
     let server = BlazeBooruServer {
         config,
         auth,
         core,
+        login_limiter,
+        comment_limiter,
+        events,
+        oidc,
         serve_files,
     };
 
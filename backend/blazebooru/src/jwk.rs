@@ -0,0 +1,233 @@
+use anyhow::{anyhow, Context};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, KeyAlgorithm, OctetKeyPairParameters, OctetKeyPairType,
+    PublicKeyUse, RSAKeyParameters, RSAKeyType,
+};
+use jsonwebtoken::Algorithm;
+
+const DER_INTEGER: u8 = 0x02;
+const DER_BIT_STRING: u8 = 0x03;
+const DER_OBJECT_IDENTIFIER: u8 = 0x06;
+const DER_SEQUENCE: u8 = 0x30;
+
+/// Encoded object identifier of Ed25519 keys (1.3.101.112)
+const OID_ED25519: &[u8] = &[0x2b, 0x65, 0x70];
+/// Encoded object identifier of RSA keys (1.2.840.113549.1.1.1)
+const OID_RSA_ENCRYPTION: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01];
+
+const ED25519_PUBLIC_KEY_SIZE: usize = 32;
+
+/// Create a JWK from a PEM encoded public key (SubjectPublicKeyInfo).
+/// Only the key material is read from the PEM, so the algorithm must be specified.
+pub fn public_key_jwk(pem: &[u8], kid: &str, algorithm: Algorithm) -> Result<Jwk, anyhow::Error> {
+    let pem = pem::parse(pem).context("Error parsing public key PEM")?;
+
+    if pem.tag() != "PUBLIC KEY" {
+        return Err(anyhow!("Expected a PUBLIC KEY PEM, found {}", pem.tag()));
+    }
+
+    let (key_oid, public_key) = spki_public_key(pem.contents()).context("Invalid public key")?;
+
+    let expected_oid = match algorithm {
+        Algorithm::EdDSA => OID_ED25519,
+        Algorithm::RS256 => OID_RSA_ENCRYPTION,
+        _ => return Err(anyhow!("Unsupported key algorithm: {algorithm:?}")),
+    };
+
+    if key_oid != expected_oid {
+        return Err(anyhow!("Public key is not a {algorithm:?} key"));
+    }
+
+    let (key_algorithm, algorithm_parameters) = match algorithm {
+        Algorithm::EdDSA => {
+            if public_key.len() != ED25519_PUBLIC_KEY_SIZE {
+                return Err(anyhow!("Invalid Ed25519 public key"));
+            }
+
+            (
+                KeyAlgorithm::EdDSA,
+                AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                    key_type: OctetKeyPairType::OctetKeyPair,
+                    curve: EllipticCurve::Ed25519,
+                    x: URL_SAFE_NO_PAD.encode(public_key),
+                }),
+            )
+        }
+        Algorithm::RS256 => {
+            let (n, e) = rsa_public_key_components(public_key).context("Invalid RSA public key")?;
+
+            (
+                KeyAlgorithm::RS256,
+                AlgorithmParameters::RSA(RSAKeyParameters {
+                    key_type: RSAKeyType::RSA,
+                    n: URL_SAFE_NO_PAD.encode(n),
+                    e: URL_SAFE_NO_PAD.encode(e),
+                }),
+            )
+        }
+        _ => unreachable!(),
+    };
+
+    Ok(Jwk {
+        common: CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            key_algorithm: Some(key_algorithm),
+            key_id: Some(kid.to_string()),
+            ..Default::default()
+        },
+        algorithm: algorithm_parameters,
+    })
+}
+
+/// Get the algorithm object identifier and the public key from a DER encoded SubjectPublicKeyInfo.
+fn spki_public_key(der: &[u8]) -> Option<(&[u8], &[u8])> {
+    let (DER_SEQUENCE, spki, _) = read_der(der)? else {
+        return None;
+    };
+
+    let (DER_SEQUENCE, algorithm_identifier, rest) = read_der(spki)? else {
+        return None;
+    };
+
+    // Any parameters following the object identifier are ignored
+    let (DER_OBJECT_IDENTIFIER, oid, _) = read_der(algorithm_identifier)? else {
+        return None;
+    };
+
+    let (DER_BIT_STRING, public_key, _) = read_der(rest)? else {
+        return None;
+    };
+
+    // The first byte of a bit string is the number of unused bits, which is always 0 for keys
+    match public_key.split_first()? {
+        (0, public_key) => Some((oid, public_key)),
+        _ => None,
+    }
+}
+
+/// Get the modulus and exponent from a DER encoded RSAPublicKey.
+fn rsa_public_key_components(der: &[u8]) -> Option<(&[u8], &[u8])> {
+    let (DER_SEQUENCE, rsa_public_key, _) = read_der(der)? else {
+        return None;
+    };
+
+    let (DER_INTEGER, n, rest) = read_der(rsa_public_key)? else {
+        return None;
+    };
+
+    let (DER_INTEGER, e, _) = read_der(rest)? else {
+        return None;
+    };
+
+    Some((strip_leading_zeros(n), strip_leading_zeros(e)))
+}
+
+/// Read a DER element.
+/// Returns its tag, its contents and the remaining input.
+fn read_der(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = input.split_first()?;
+    let (&len, rest) = rest.split_first()?;
+
+    let (len, rest) = if len & 0x80 == 0 {
+        (len as usize, rest)
+    } else {
+        // Long form, where the lower bits are the number of length bytes
+        let len_size = (len & 0x7f) as usize;
+        if len_size == 0 || len_size > 4 || rest.len() < len_size {
+            return None;
+        }
+
+        let (len_bytes, rest) = rest.split_at(len_size);
+        let len = len_bytes.iter().fold(0usize, |len, &b| (len << 8) | b as usize);
+
+        (len, rest)
+    };
+
+    if rest.len() < len {
+        return None;
+    }
+
+    let (contents, rest) = rest.split_at(len);
+
+    Some((tag, contents, rest))
+}
+
+/// DER integers are signed, so positive ones can have a leading zero byte.
+fn strip_leading_zeros(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|&b| b != 0).unwrap_or(bytes.len());
+
+    &bytes[start..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ED25519_KEY: [u8; 32] = [7; 32];
+
+    /// Create a PEM encoded SubjectPublicKeyInfo with the specified algorithm and parameters.
+    fn spki_pem(algorithm_identifier: &[u8], public_key: &[u8]) -> Vec<u8> {
+        let mut bit_string = vec![DER_BIT_STRING, public_key.len() as u8 + 1, 0];
+        bit_string.extend_from_slice(public_key);
+
+        let mut contents = vec![DER_SEQUENCE, algorithm_identifier.len() as u8];
+        contents.extend_from_slice(algorithm_identifier);
+        contents.extend_from_slice(&bit_string);
+
+        let mut der = vec![DER_SEQUENCE, contents.len() as u8];
+        der.extend_from_slice(&contents);
+
+        pem::encode(&pem::Pem::new("PUBLIC KEY", der)).into_bytes()
+    }
+
+    fn oid(oid: &[u8]) -> Vec<u8> {
+        let mut der = vec![DER_OBJECT_IDENTIFIER, oid.len() as u8];
+        der.extend_from_slice(oid);
+        der
+    }
+
+    #[test]
+    fn ed25519_key_is_converted() {
+        let pem = spki_pem(&oid(OID_ED25519), &ED25519_KEY);
+
+        let jwk = public_key_jwk(&pem, "k1", Algorithm::EdDSA).unwrap();
+
+        assert_eq!(jwk.common.key_id.as_deref(), Some("k1"));
+        assert!(matches!(
+            jwk.algorithm,
+            AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters { ref x, .. }) if *x == URL_SAFE_NO_PAD.encode(ED25519_KEY)
+        ));
+    }
+
+    #[test]
+    fn key_with_other_algorithm_is_rejected() {
+        // X25519 keys have the same size as Ed25519 keys
+        let x25519 = spki_pem(&oid(&[0x2b, 0x65, 0x6e]), &ED25519_KEY);
+        assert!(public_key_jwk(&x25519, "k1", Algorithm::EdDSA).is_err());
+
+        let mut rsa_identifier = oid(OID_RSA_ENCRYPTION);
+        rsa_identifier.extend_from_slice(&[0x05, 0x00]);
+        let rsa = spki_pem(&rsa_identifier, &ED25519_KEY);
+        assert!(public_key_jwk(&rsa, "k1", Algorithm::EdDSA).is_err());
+    }
+
+    #[test]
+    fn rsa_key_is_converted() {
+        let mut rsa_identifier = oid(OID_RSA_ENCRYPTION);
+        rsa_identifier.extend_from_slice(&[0x05, 0x00]);
+
+        // Modulus with a leading zero byte, since its high bit is set
+        let rsa_public_key = [DER_SEQUENCE, 8, DER_INTEGER, 3, 0x00, 0xc1, 0x23, DER_INTEGER, 1, 0x03];
+        let pem = spki_pem(&rsa_identifier, &rsa_public_key);
+
+        let jwk = public_key_jwk(&pem, "k1", Algorithm::RS256).unwrap();
+
+        let AlgorithmParameters::RSA(RSAKeyParameters { n, e, .. }) = jwk.algorithm else {
+            panic!("Expected an RSA key");
+        };
+
+        assert_eq!(n, URL_SAFE_NO_PAD.encode([0xc1, 0x23]));
+        assert_eq!(e, URL_SAFE_NO_PAD.encode([0x03]));
+    }
+}
@@ -9,6 +9,9 @@ use blazebooru_core::{config::BlazeBooruConfig, BlazeBooruCore};
 mod auth;
 mod command;
 mod deserialize;
+mod jwk;
+mod oidc;
+mod rate_limit;
 mod server;
 
 #[derive(Debug, Parser)]
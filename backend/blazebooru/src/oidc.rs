@@ -0,0 +1,465 @@
+use std::str::FromStr;
+use std::sync::RwLock;
+
+use anyhow::{anyhow, Context};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::sync::OnceCell;
+use uuid::Uuid;
+
+use blazebooru_core::config::OidcConfig;
+
+/// Algorithm the provider signs ID tokens with if its keys do not specify one
+const DEFAULT_ID_TOKEN_ALGORITHM: Algorithm = Algorithm::RS256;
+
+/// OpenID Connect client, using the authorization code flow with PKCE.
+pub struct OidcClient {
+    pub config: OidcConfig,
+    http: reqwest::Client,
+
+    /// Endpoints of the provider.
+    /// Discovered on first use, and retried until discovery succeeds.
+    metadata: OnceCell<ProviderMetadata>,
+
+    /// Signing keys of the provider.
+    /// Refreshed if a token is signed with an unknown key.
+    jwks: RwLock<JwkSet>,
+}
+
+/// An identity verified by the provider
+#[derive(Debug)]
+pub struct OidcIdentity {
+    pub issuer: String,
+    pub subject: String,
+    pub preferred_username: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+}
+
+/// A login that has been started at the provider.
+/// Kept by the browser that started it, so that a login
+/// can only be completed by the browser that started it.
+/// Uses different field names than other token claims,
+/// so that one can never be mistaken for another.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PendingLogin {
+    oidc_state: String,
+    oidc_nonce: String,
+    oidc_code_verifier: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct IdTokenClaims {
+    iss: String,
+    sub: String,
+    nonce: Option<String>,
+    preferred_username: Option<String>,
+}
+
+impl OidcClient {
+    /// Create a client.
+    /// The provider is not contacted until it is first needed.
+    pub fn new(config: OidcConfig) -> Self {
+        Self {
+            config,
+            http: reqwest::Client::new(),
+            metadata: OnceCell::new(),
+            jwks: RwLock::new(JwkSet { keys: Vec::new() }),
+        }
+    }
+
+    /// Discover the endpoints and signing keys of the provider,
+    /// if this has not already been done.
+    pub async fn discover(&self) -> Result<(), anyhow::Error> {
+        self.metadata().await?;
+
+        Ok(())
+    }
+
+    /// Start a login, returning the URL to send the user to,
+    /// and the login to keep until the provider redirects back.
+    pub async fn authorize_url(&self) -> Result<(Url, PendingLogin), anyhow::Error> {
+        let metadata = self.metadata().await?;
+
+        let state = Uuid::new_v4().simple().to_string();
+        let nonce = Uuid::new_v4().simple().to_string();
+        let code_verifier = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+
+        let code_challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()));
+
+        let url = Url::parse_with_params(
+            &metadata.authorization_endpoint,
+            &[
+                ("response_type", "code"),
+                ("client_id", &self.config.client_id),
+                ("redirect_uri", &self.config.redirect_url),
+                ("scope", &self.config.scopes),
+                ("state", &state),
+                ("nonce", &nonce),
+                ("code_challenge", &code_challenge),
+                ("code_challenge_method", "S256"),
+            ],
+        )
+        .context("Error creating OIDC authorization URL")?;
+
+        let pending_login = PendingLogin {
+            oidc_state: state,
+            oidc_nonce: nonce,
+            oidc_code_verifier: code_verifier,
+        };
+
+        Ok((url, pending_login))
+    }
+
+    /// Complete a login using the code the provider redirected back with.
+    /// Returns None if the state does not belong to the pending login.
+    pub async fn exchange_code(
+        &self,
+        code: &str,
+        state: &str,
+        pending_login: &PendingLogin,
+    ) -> Result<Option<OidcIdentity>, anyhow::Error> {
+        if state != pending_login.oidc_state {
+            return Ok(None);
+        }
+
+        let metadata = self.metadata().await?;
+
+        let mut request = self.http.post(&metadata.token_endpoint).form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", &self.config.redirect_url),
+            ("client_id", &self.config.client_id),
+            ("code_verifier", &pending_login.oidc_code_verifier),
+        ]);
+
+        if let Some(client_secret) = &self.config.client_secret {
+            request = request.basic_auth(&self.config.client_id, Some(client_secret));
+        }
+
+        let token_response: TokenResponse = request
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .context("Error exchanging OIDC authorization code")?
+            .json()
+            .await
+            .context("Error parsing OIDC token response")?;
+
+        let claims = self.verify_id_token(metadata, &token_response.id_token).await?;
+
+        if claims.nonce.as_deref() != Some(&pending_login.oidc_nonce) {
+            return Err(anyhow!("OIDC ID token nonce does not match"));
+        }
+
+        Ok(Some(OidcIdentity {
+            issuer: claims.iss,
+            subject: claims.sub,
+            preferred_username: claims.preferred_username,
+        }))
+    }
+
+    async fn metadata(&self) -> Result<&ProviderMetadata, anyhow::Error> {
+        self.metadata
+            .get_or_try_init(|| async {
+                let discovery_url = format!(
+                    "{}/.well-known/openid-configuration",
+                    self.config.issuer_url.trim_end_matches('/')
+                );
+
+                let metadata: ProviderMetadata = self
+                    .http
+                    .get(&discovery_url)
+                    .send()
+                    .await
+                    .and_then(|r| r.error_for_status())
+                    .context("Error getting OIDC provider metadata")?
+                    .json()
+                    .await
+                    .context("Error parsing OIDC provider metadata")?;
+
+                let jwks = fetch_jwks(&self.http, &metadata.jwks_uri).await?;
+                *self.jwks.write().unwrap() = jwks;
+
+                Ok(metadata)
+            })
+            .await
+    }
+
+    async fn verify_id_token(
+        &self,
+        metadata: &ProviderMetadata,
+        id_token: &str,
+    ) -> Result<IdTokenClaims, anyhow::Error> {
+        let header = jsonwebtoken::decode_header(id_token).context("Error decoding OIDC ID token header")?;
+        let kid = header.kid.context("OIDC ID token has no key ID")?;
+
+        let mut decoding_key = self.find_decoding_key(&kid)?;
+
+        // The provider may have rotated its keys
+        if decoding_key.is_none() {
+            let jwks = fetch_jwks(&self.http, &metadata.jwks_uri).await?;
+            *self.jwks.write().unwrap() = jwks;
+
+            decoding_key = self.find_decoding_key(&kid)?;
+        }
+
+        let (algorithm, decoding_key) = decoding_key.context("OIDC ID token is signed with an unknown key")?;
+
+        // The algorithm is taken from the key rather than the untrusted token header
+        if header.alg != algorithm {
+            return Err(anyhow!("OIDC ID token algorithm does not match its key"));
+        }
+
+        let mut validation = Validation::new(algorithm);
+        validation.set_issuer(&[&metadata.issuer]);
+        validation.set_audience(&[&self.config.client_id]);
+
+        let token_data = jsonwebtoken::decode::<IdTokenClaims>(id_token, &decoding_key, &validation)
+            .context("Error verifying OIDC ID token")?;
+
+        Ok(token_data.claims)
+    }
+
+    /// Find a key of the provider, along with the algorithm it signs with.
+    fn find_decoding_key(&self, kid: &str) -> Result<Option<(Algorithm, DecodingKey)>, anyhow::Error> {
+        let jwks = self.jwks.read().unwrap();
+
+        let Some(jwk) = jwks.find(kid) else {
+            return Ok(None);
+        };
+
+        let algorithm = match jwk.common.key_algorithm {
+            Some(key_algorithm) => {
+                Algorithm::from_str(&key_algorithm.to_string()).context("OIDC provider key is not a signing key")?
+            }
+            None => DEFAULT_ID_TOKEN_ALGORITHM,
+        };
+
+        let decoding_key = DecodingKey::from_jwk(jwk).context("Error reading OIDC provider key")?;
+
+        Ok(Some((algorithm, decoding_key)))
+    }
+}
+
+async fn fetch_jwks(http: &reqwest::Client, jwks_uri: &str) -> Result<JwkSet, anyhow::Error> {
+    let jwks = http
+        .get(jwks_uri)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .context("Error getting OIDC provider keys")?
+        .json()
+        .await
+        .context("Error parsing OIDC provider keys")?;
+
+    Ok(jwks)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use std::sync::Mutex;
+
+    use axum::{extract::State, http::StatusCode, routing::get, routing::post, Form, Json, Router};
+    use chrono::{Duration, Utc};
+    use jsonwebtoken::{EncodingKey, Header};
+    use serde_json::json;
+
+    use super::*;
+
+    const CLIENT_ID: &str = "blazebooru";
+    const SECRET: &[u8] = b"stub-issuer-secret";
+
+    /// What the stub issuer remembers from the authorization request
+    #[derive(Default)]
+    struct StubState {
+        issuer: String,
+        nonce: Mutex<Option<String>>,
+        code_challenge: Mutex<Option<String>>,
+        /// Algorithm to claim in the ID token header, instead of the one of the key
+        header_alg: Mutex<Option<jsonwebtoken::Algorithm>>,
+    }
+
+    #[derive(Deserialize)]
+    struct TokenRequest {
+        code: String,
+        code_verifier: String,
+    }
+
+    /// Start a provider on a random local port, returning its issuer URL.
+    async fn start_stub_issuer() -> Arc<StubState> {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let issuer = format!("http://{}", listener.local_addr().unwrap());
+
+        let state = Arc::new(StubState {
+            issuer,
+            ..Default::default()
+        });
+
+        let router = Router::new()
+            .route("/.well-known/openid-configuration", get(stub_metadata))
+            .route("/jwks", get(stub_jwks))
+            .route("/token", post(stub_token))
+            .with_state(state.clone());
+
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        state
+    }
+
+    async fn stub_metadata(State(state): State<Arc<StubState>>) -> Json<serde_json::Value> {
+        Json(json!({
+            "issuer": state.issuer,
+            "authorization_endpoint": format!("{}/authorize", state.issuer),
+            "token_endpoint": format!("{}/token", state.issuer),
+            "jwks_uri": format!("{}/jwks", state.issuer),
+        }))
+    }
+
+    async fn stub_jwks() -> Json<serde_json::Value> {
+        Json(json!({
+            "keys": [{ "kty": "oct", "kid": "stub", "alg": "HS256", "k": URL_SAFE_NO_PAD.encode(SECRET) }]
+        }))
+    }
+
+    async fn stub_token(
+        State(state): State<Arc<StubState>>,
+        Form(req): Form<TokenRequest>,
+    ) -> Result<Json<serde_json::Value>, StatusCode> {
+        let code_challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(req.code_verifier.as_bytes()));
+
+        if req.code != "stub-code" || Some(code_challenge) != *state.code_challenge.lock().unwrap() {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+
+        let claims = json!({
+            "iss": state.issuer,
+            "sub": "alice-sub",
+            "aud": CLIENT_ID,
+            "exp": (Utc::now() + Duration::minutes(5)).timestamp(),
+            "nonce": *state.nonce.lock().unwrap(),
+            "preferred_username": "alice",
+        });
+
+        let alg = state
+            .header_alg
+            .lock()
+            .unwrap()
+            .unwrap_or(jsonwebtoken::Algorithm::HS256);
+
+        let header = Header {
+            kid: Some("stub".to_owned()),
+            ..Header::new(alg)
+        };
+
+        let id_token = jsonwebtoken::encode(&header, &claims, &EncodingKey::from_secret(SECRET)).unwrap();
+
+        Ok(Json(json!({ "id_token": id_token })))
+    }
+
+    fn client(issuer_url: &str) -> OidcClient {
+        OidcClient::new(OidcConfig {
+            issuer_url: issuer_url.to_owned(),
+            client_id: CLIENT_ID.to_owned(),
+            client_secret: None,
+            redirect_url: "http://localhost/oidc".to_owned(),
+            scopes: "openid".to_owned(),
+            allow_registration: true,
+        })
+    }
+
+    fn query_param(url: &Url, name: &str) -> String {
+        url.query_pairs().find(|(k, _)| k == name).unwrap().1.into_owned()
+    }
+
+    /// Start a login, and let the stub issuer know what it was started with.
+    async fn start_login(oidc: &OidcClient, stub: &StubState) -> (String, PendingLogin) {
+        let (url, pending_login) = oidc.authorize_url().await.unwrap();
+
+        *stub.nonce.lock().unwrap() = Some(query_param(&url, "nonce"));
+        *stub.code_challenge.lock().unwrap() = Some(query_param(&url, "code_challenge"));
+
+        (query_param(&url, "state"), pending_login)
+    }
+
+    #[tokio::test]
+    async fn login_completes_against_provider() {
+        let stub = start_stub_issuer().await;
+        let oidc = client(&stub.issuer);
+
+        let (state, pending_login) = start_login(&oidc, &stub).await;
+
+        let identity = oidc
+            .exchange_code("stub-code", &state, &pending_login)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(identity.issuer, stub.issuer);
+        assert_eq!(identity.subject, "alice-sub");
+        assert_eq!(identity.preferred_username.as_deref(), Some("alice"));
+    }
+
+    #[tokio::test]
+    async fn login_rejects_state_of_another_login() {
+        let stub = start_stub_issuer().await;
+        let oidc = client(&stub.issuer);
+
+        // A login started by someone else cannot be completed in this browser
+        let (_, pending_login) = oidc.authorize_url().await.unwrap();
+        let (other_state, _) = start_login(&oidc, &stub).await;
+
+        let identity = oidc.exchange_code("stub-code", &other_state, &pending_login).await;
+
+        assert!(identity.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn login_rejects_wrong_nonce() {
+        let stub = start_stub_issuer().await;
+        let oidc = client(&stub.issuer);
+
+        let (state, pending_login) = start_login(&oidc, &stub).await;
+
+        *stub.nonce.lock().unwrap() = Some("replayed".to_owned());
+
+        assert!(oidc.exchange_code("stub-code", &state, &pending_login).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn login_rejects_algorithm_not_matching_key() {
+        let stub = start_stub_issuer().await;
+        let oidc = client(&stub.issuer);
+
+        let (state, pending_login) = start_login(&oidc, &stub).await;
+
+        *stub.header_alg.lock().unwrap() = Some(jsonwebtoken::Algorithm::HS384);
+
+        assert!(oidc.exchange_code("stub-code", &state, &pending_login).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn discovery_is_retried() {
+        // Nothing is listening on port 1
+        let oidc = client("http://127.0.0.1:1");
+
+        assert!(oidc.discover().await.is_err());
+        assert!(oidc.authorize_url().await.is_err());
+    }
+}
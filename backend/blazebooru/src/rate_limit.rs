@@ -0,0 +1,430 @@
+use std::collections::{BTreeSet, HashMap};
+use std::net::IpAddr;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Duration, Utc};
+
+use blazebooru_core::config::BlazeBooruConfig;
+
+/// Maximum number of keys to track attempts for.
+/// User names are chosen by whoever is attempting to log in,
+/// so the number of keys has to be bounded.
+const MAX_TRACKED_KEYS: usize = 100_000;
+
+/// The delay will have hit the maximum long before this many doublings
+const MAX_BACKOFF_EXPONENT: u32 = 30;
+
+/// Limits login and registration attempts per IP and per user name.
+///
+/// Once the free attempts have been used up, each further attempt
+/// doubles the time that has to pass before the next one is allowed.
+/// User names (and TOTP codes) that keep failing are locked out entirely for a while.
+pub struct LoginRateLimiter {
+    free_attempts: u32,
+    backoff_base: Duration,
+    backoff_max: Duration,
+    lockout_threshold: u32,
+    lockout_duration: Duration,
+    max_keys: usize,
+
+    attempts: Mutex<TrackedAttempts>,
+}
+
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum RateLimitKey {
+    Ip(IpAddr),
+    UserName(String),
+    /// TOTP code attempts for a user
+    Totp(i32),
+}
+
+#[derive(Debug)]
+struct Attempts {
+    count: u32,
+    last_attempt: DateTime<Utc>,
+}
+
+/// Attempts for each key, along with the order in which to forget them.
+#[derive(Default)]
+struct TrackedAttempts {
+    attempts: HashMap<RateLimitKey, Attempts>,
+    /// Keys that are not locked out, by the time of their last attempt
+    unlocked: BTreeSet<(DateTime<Utc>, RateLimitKey)>,
+    /// Keys that are locked out, by the time of their last attempt
+    locked: BTreeSet<(DateTime<Utc>, RateLimitKey)>,
+}
+
+impl LoginRateLimiter {
+    pub fn new(config: &BlazeBooruConfig) -> Self {
+        Self {
+            free_attempts: config.login_free_attempts,
+            backoff_base: Duration::seconds(config.login_backoff_base_seconds.into()),
+            backoff_max: Duration::seconds(config.login_backoff_max_seconds.into()),
+            lockout_threshold: config.login_lockout_threshold,
+            lockout_duration: Duration::seconds(config.login_lockout_seconds.into()),
+            max_keys: MAX_TRACKED_KEYS,
+            attempts: Mutex::new(TrackedAttempts::default()),
+        }
+    }
+
+    /// Record an attempt for all keys, if none of them are currently limited.
+    /// Returns how long to wait before trying again if any of them are.
+    ///
+    /// Attempts are recorded before they are carried out, so that
+    /// concurrent requests can not get around the limit.
+    pub fn attempt(&self, keys: &[RateLimitKey]) -> Result<(), Duration> {
+        let now = Utc::now();
+        let mut attempts = self.attempts.lock().unwrap();
+
+        let retry_after = keys
+            .iter()
+            .filter_map(|key| {
+                let a = attempts.attempts.get(key)?;
+                let allowed_at = self.allowed_at(key, a)?;
+
+                (allowed_at > now).then(|| allowed_at - now)
+            })
+            .max();
+
+        if let Some(retry_after) = retry_after {
+            return Err(retry_after);
+        }
+
+        for key in keys {
+            let mut a = match self.remove(&mut attempts, key) {
+                Some(a) => a,
+                None => {
+                    if attempts.attempts.len() >= self.max_keys {
+                        self.make_room(&mut attempts, now);
+                    }
+
+                    Attempts {
+                        count: 0,
+                        last_attempt: now,
+                    }
+                }
+            };
+
+            // Start over if the previous attempts are old enough
+            if now - a.last_attempt > self.lockout_duration {
+                a.count = 0;
+            }
+
+            a.count += 1;
+            a.last_attempt = now;
+
+            self.insert(&mut attempts, key.clone(), a);
+        }
+
+        Ok(())
+    }
+
+    /// Record that an attempt succeeded.
+    /// Failures are forgotten for the user, and the attempt
+    /// no longer counts against the IP.
+    pub fn succeeded(&self, keys: &[RateLimitKey]) {
+        let mut attempts = self.attempts.lock().unwrap();
+
+        for key in keys {
+            let Some(mut a) = self.remove(&mut attempts, key) else {
+                continue;
+            };
+
+            if let RateLimitKey::Ip(_) = key {
+                a.count = a.count.saturating_sub(1);
+                self.insert(&mut attempts, key.clone(), a);
+            }
+        }
+    }
+
+    /// Forget attempts that no longer affect anything.
+    pub fn prune(&self) {
+        let before = Utc::now() - self.lockout_duration.max(self.backoff_max);
+
+        let mut attempts = self.attempts.lock().unwrap();
+        let TrackedAttempts {
+            attempts,
+            unlocked,
+            locked,
+        } = &mut *attempts;
+
+        for keys in [unlocked, locked] {
+            while keys.first().is_some_and(|(last_attempt, _)| *last_attempt < before) {
+                if let Some((_, key)) = keys.pop_first() {
+                    attempts.remove(&key);
+                }
+            }
+        }
+    }
+
+    /// Make room for a new key.
+    /// Keys that are locked out are only forgotten once their lockout has ended,
+    /// unless all the keys are locked out.
+    fn make_room(&self, attempts: &mut TrackedAttempts, now: DateTime<Utc>) {
+        let lockout_ended = attempts
+            .locked
+            .first()
+            .is_some_and(|(last_attempt, _)| *last_attempt + self.lockout_duration <= now);
+
+        let oldest = if lockout_ended {
+            attempts.locked.pop_first()
+        } else {
+            attempts.unlocked.pop_first().or_else(|| attempts.locked.pop_first())
+        };
+
+        if let Some((_, key)) = oldest {
+            attempts.attempts.remove(&key);
+        }
+    }
+
+    fn insert(&self, attempts: &mut TrackedAttempts, key: RateLimitKey, a: Attempts) {
+        let keys = if self.is_locked_out(&key, &a) {
+            &mut attempts.locked
+        } else {
+            &mut attempts.unlocked
+        };
+
+        keys.insert((a.last_attempt, key.clone()));
+        attempts.attempts.insert(key, a);
+    }
+
+    fn remove(&self, attempts: &mut TrackedAttempts, key: &RateLimitKey) -> Option<Attempts> {
+        let a = attempts.attempts.remove(key)?;
+
+        let keys = if self.is_locked_out(key, &a) {
+            &mut attempts.locked
+        } else {
+            &mut attempts.unlocked
+        };
+
+        keys.remove(&(a.last_attempt, key.clone()));
+
+        Some(a)
+    }
+
+    /// User names and TOTP codes are locked out after too many attempts, while IPs only back off.
+    fn is_locked_out(&self, key: &RateLimitKey, a: &Attempts) -> bool {
+        !matches!(key, RateLimitKey::Ip(_)) && a.count >= self.lockout_threshold
+    }
+
+    /// Get the time at which the next attempt is allowed,
+    /// if the key is being limited.
+    fn allowed_at(&self, key: &RateLimitKey, a: &Attempts) -> Option<DateTime<Utc>> {
+        if self.is_locked_out(key, a) {
+            return Some(a.last_attempt + self.lockout_duration);
+        }
+
+        if a.count < self.free_attempts {
+            return None;
+        }
+
+        let exponent = (a.count - self.free_attempts).min(MAX_BACKOFF_EXPONENT);
+        let delay = 2i32
+            .checked_pow(exponent)
+            .and_then(|factor| self.backoff_base.checked_mul(factor))
+            .map_or(self.backoff_max, |delay| delay.min(self.backoff_max));
+
+        Some(a.last_attempt + delay)
+    }
+}
+
+/// Limits the number of comments that can be posted from an IP within a period.
+pub struct CommentRateLimiter {
+    limit: usize,
+    period: Duration,
+
+    comments: Mutex<HashMap<IpAddr, Vec<DateTime<Utc>>>>,
+}
+
+impl CommentRateLimiter {
+    pub fn new(config: &BlazeBooruConfig) -> Self {
+        Self {
+            limit: config.comment_rate_limit as usize,
+            period: Duration::seconds(config.comment_rate_limit_seconds.into()),
+            comments: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Record a comment from the IP, if it has not reached the limit.
+    /// Returns how long to wait before trying again if it has.
+    pub fn attempt(&self, ip: IpAddr) -> Result<(), Duration> {
+        let now = Utc::now();
+        let after = now - self.period;
+
+        let mut comments = self.comments.lock().unwrap();
+        let times = comments.entry(ip).or_default();
+
+        times.retain(|t| *t > after);
+
+        if times.len() >= self.limit {
+            // Another comment is allowed once the oldest one is outside the period
+            let retry_after = times.first().map_or(self.period, |t| *t - after);
+
+            return Err(retry_after);
+        }
+
+        times.push(now);
+
+        Ok(())
+    }
+
+    /// Forget comments that are outside the period.
+    pub fn prune(&self) {
+        let after = Utc::now() - self.period;
+
+        let mut comments = self.comments.lock().unwrap();
+        comments.retain(|_, times| {
+            times.retain(|t| *t > after);
+            !times.is_empty()
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn login_limiter() -> LoginRateLimiter {
+        LoginRateLimiter {
+            free_attempts: 2,
+            backoff_base: Duration::seconds(1),
+            backoff_max: Duration::seconds(60),
+            lockout_threshold: 5,
+            lockout_duration: Duration::seconds(600),
+            max_keys: 3,
+            attempts: Mutex::new(TrackedAttempts::default()),
+        }
+    }
+
+    fn delay(limiter: &LoginRateLimiter, key: &RateLimitKey, count: u32) -> Option<Duration> {
+        let last_attempt = Utc::now();
+        let a = Attempts { count, last_attempt };
+
+        limiter.allowed_at(key, &a).map(|allowed_at| allowed_at - last_attempt)
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_maximum() {
+        let limiter = login_limiter();
+        let key = RateLimitKey::Ip(IpAddr::from([127, 0, 0, 1]));
+
+        assert_eq!(delay(&limiter, &key, 1), None);
+        assert_eq!(delay(&limiter, &key, 2), Some(Duration::seconds(1)));
+        assert_eq!(delay(&limiter, &key, 3), Some(Duration::seconds(2)));
+        assert_eq!(delay(&limiter, &key, 5), Some(Duration::seconds(8)));
+        assert_eq!(delay(&limiter, &key, 100), Some(Duration::seconds(60)));
+    }
+
+    #[test]
+    fn backoff_does_not_overflow() {
+        let mut limiter = login_limiter();
+        limiter.backoff_base = Duration::days(365 * 1000);
+        limiter.backoff_max = Duration::days(365 * 10_000);
+
+        let key = RateLimitKey::Ip(IpAddr::from([127, 0, 0, 1]));
+
+        assert_eq!(delay(&limiter, &key, u32::MAX), Some(limiter.backoff_max));
+    }
+
+    #[test]
+    fn user_names_are_locked_out_after_threshold() {
+        let limiter = login_limiter();
+        let key = RateLimitKey::UserName("user".to_string());
+
+        assert_eq!(delay(&limiter, &key, 4), Some(Duration::seconds(4)));
+        assert_eq!(delay(&limiter, &key, 5), Some(limiter.lockout_duration));
+    }
+
+    #[test]
+    fn attempts_are_limited_after_free_attempts() {
+        let limiter = login_limiter();
+        let keys = [RateLimitKey::UserName("user".to_string())];
+
+        assert!(limiter.attempt(&keys).is_ok());
+        assert!(limiter.attempt(&keys).is_ok());
+        assert!(limiter.attempt(&keys).is_err());
+
+        limiter.succeeded(&keys);
+        assert!(limiter.attempt(&keys).is_ok());
+    }
+
+    #[test]
+    fn tracked_keys_are_bounded() {
+        let limiter = login_limiter();
+
+        for i in 0..10 {
+            let keys = [RateLimitKey::UserName(format!("user{i}"))];
+            limiter.attempt(&keys).unwrap();
+        }
+
+        let attempts = limiter.attempts.lock().unwrap();
+        assert_eq!(attempts.attempts.len(), limiter.max_keys);
+        assert_eq!(attempts.unlocked.len(), limiter.max_keys);
+        assert!(attempts
+            .attempts
+            .contains_key(&RateLimitKey::UserName("user9".to_string())));
+    }
+
+    #[test]
+    fn locked_out_keys_are_not_forgotten_to_make_room() {
+        let limiter = login_limiter();
+        let victim = RateLimitKey::UserName("victim".to_string());
+
+        {
+            let mut attempts = limiter.attempts.lock().unwrap();
+            let a = Attempts {
+                count: limiter.lockout_threshold,
+                last_attempt: Utc::now(),
+            };
+            limiter.insert(&mut attempts, victim.clone(), a);
+        }
+
+        for i in 0..10 {
+            let keys = [RateLimitKey::UserName(format!("user{i}"))];
+            limiter.attempt(&keys).unwrap();
+        }
+
+        assert!(limiter.attempt(&[victim]).is_err());
+    }
+
+    #[test]
+    fn old_attempts_are_pruned() {
+        let limiter = login_limiter();
+        let key = RateLimitKey::Ip(IpAddr::from([127, 0, 0, 1]));
+
+        {
+            let mut attempts = limiter.attempts.lock().unwrap();
+            let a = Attempts {
+                count: 1,
+                last_attempt: Utc::now() - limiter.lockout_duration - Duration::seconds(1),
+            };
+            limiter.insert(&mut attempts, key.clone(), a);
+        }
+
+        let other_key = RateLimitKey::Ip(IpAddr::from([127, 0, 0, 2]));
+        limiter.attempt(&[other_key]).unwrap();
+        limiter.prune();
+
+        let attempts = limiter.attempts.lock().unwrap();
+        assert!(!attempts.attempts.contains_key(&key));
+        assert_eq!(attempts.attempts.len(), 1);
+        assert_eq!(attempts.unlocked.len(), 1);
+    }
+
+    #[test]
+    fn comments_are_limited_per_ip() {
+        let limiter = CommentRateLimiter {
+            limit: 2,
+            period: Duration::seconds(60),
+            comments: Mutex::new(HashMap::new()),
+        };
+
+        let ip = IpAddr::from([127, 0, 0, 1]);
+        let other_ip = IpAddr::from([127, 0, 0, 2]);
+
+        assert!(limiter.attempt(ip).is_ok());
+        assert!(limiter.attempt(ip).is_ok());
+        assert!(limiter.attempt(ip).is_err());
+        assert!(limiter.attempt(other_ip).is_ok());
+    }
+}
@@ -0,0 +1,49 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::extract::Query;
+use axum::extract::State;
+use axum::routing::get;
+use axum::Json;
+use axum::Router;
+use serde::Deserialize;
+
+use blazebooru_models::view as vm;
+
+use crate::server::api::Authorized;
+use crate::server::ApiError;
+use crate::server::BlazeBooruServer;
+
+#[derive(Deserialize)]
+struct PaginatedQuery {
+    #[serde(rename = "sid")]
+    start_id: Option<i32>,
+    limit: i32,
+}
+
+pub fn router() -> Router<Arc<BlazeBooruServer>> {
+    Router::new().route("/", get(get_audit_log))
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn get_audit_log(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+    Query(filter): Query<vm::AuditLogFilter>,
+    Query(PaginatedQuery { start_id, limit }): Query<PaginatedQuery>,
+) -> Result<Json<Vec<vm::AuditLogEntry>>, ApiError> {
+    // Moderation is not available to API keys
+    auth.require_user()?;
+
+    if !server.core.is_user_admin(auth.claims.user_id).await? {
+        return Err(ApiError::Forbidden);
+    }
+
+    let entries = server
+        .core
+        .get_audit_log(filter, start_id, limit)
+        .await
+        .context("Error getting audit log")?;
+
+    Ok(Json(entries))
+}
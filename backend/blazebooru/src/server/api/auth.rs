@@ -1,18 +1,30 @@
+use std::net::IpAddr;
 use std::sync::Arc;
 
+use anyhow::Context;
 use axum::extract::State;
-use axum::routing::post;
+use axum::http::header;
+use axum::response::Redirect;
+use axum::routing::{get, post};
 use axum::{Json, Router};
 use axum_client_ip::SecureClientIp;
+use axum_extra::headers::{Cookie, UserAgent};
+use axum_extra::TypedHeader;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use blazebooru_models::local as lm;
 
-use crate::auth::{AuthClaims, JwtClaims, SessionClaims};
+use crate::auth::{oidc_login_lifetime, AuthClaims, JwtClaims, SessionClaims, TotpChallengeClaims};
+use crate::oidc::PendingLogin;
+use crate::rate_limit::RateLimitKey;
 use crate::server::api::Authorized;
 use crate::server::{ApiError, BlazeBooruServer};
 
+/// Cookie holding the signed pending OIDC login of the browser that started it
+const OIDC_LOGIN_COOKIE: &str = "blazebooru_oidc_login";
+const OIDC_LOGIN_COOKIE_PATH: &str = "/api/auth/oidc";
+
 #[derive(Debug, Deserialize)]
 struct LoginRequest {
     name: String,
@@ -26,6 +38,33 @@ pub struct LoginResponse {
     pub refresh_token: Uuid,
 }
 
+#[derive(Debug, Deserialize)]
+struct LoginTotpRequest {
+    challenge_token: String,
+    code: String,
+}
+
+/// Returned by login instead of tokens for users with TOTP enabled.
+/// The challenge token must be completed with a TOTP code to log in.
+#[derive(Debug, Serialize)]
+struct LoginChallengeResponse {
+    challenge_token: String,
+    exp: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum LoginResult {
+    Success(LoginResponse),
+    TotpRequired(LoginChallengeResponse),
+}
+
+#[derive(Debug, Deserialize)]
+struct OidcCallbackRequest {
+    code: String,
+    state: String,
+}
+
 #[derive(Debug, Deserialize)]
 struct RefreshRequest {
     pub refresh_token: Uuid,
@@ -34,6 +73,9 @@ struct RefreshRequest {
 pub fn router() -> Router<Arc<BlazeBooruServer>> {
     Router::new()
         .route("/login", post(login))
+        .route("/login/totp", post(login_totp))
+        .route("/oidc/authorize", get(oidc_authorize))
+        .route("/oidc/callback", post(oidc_callback))
         .route("/logout", post(logout))
         .route("/refresh", post(refresh))
 }
@@ -42,36 +84,176 @@ pub fn router() -> Router<Arc<BlazeBooruServer>> {
 async fn login(
     State(server): State<Arc<BlazeBooruServer>>,
     SecureClientIp(ip): SecureClientIp,
+    user_agent: Option<TypedHeader<UserAgent>>,
     Json(req): Json<LoginRequest>,
-) -> Result<Json<LoginResponse>, ApiError> {
+) -> Result<Json<LoginResult>, ApiError> {
+    let rate_limit_keys = [RateLimitKey::Ip(ip), RateLimitKey::UserName(req.name.clone())];
+
+    server
+        .login_limiter
+        .attempt(&rate_limit_keys)
+        .map_err(ApiError::TooManyRequests)?;
+
     let user = server.core.login(&req.name, &req.password).await?;
 
     if let Some(user) = user {
-        let lm::CreateRefreshTokenResult {
-            token: refresh_token,
-            session,
-        } = server.core.create_refresh_token(user.id, ip).await?;
+        server.login_limiter.succeeded(&rate_limit_keys);
 
-        let claims = AuthClaims { user_id: user.id };
-        let claims = SessionClaims { session, claims };
+        // Users with TOTP enabled need to complete the login with a code
+        if server.core.is_totp_enabled(user.id).await? {
+            let claims = JwtClaims::totp_challenge(TotpChallengeClaims { totp_user_id: user.id });
+            let exp = claims.exp;
+            let challenge_token = server.auth.generate_token(&claims)?;
 
-        let claims = JwtClaims::short(claims);
-        let exp = claims.exp;
-        let access_token = server.auth.generate_token(&claims)?;
+            return Ok(Json(LoginResult::TotpRequired(LoginChallengeResponse {
+                challenge_token,
+                exp,
+            })));
+        }
 
-        Ok(Json(LoginResponse {
-            access_token,
-            exp,
-            refresh_token,
-        }))
+        let user_agent = user_agent.as_ref().map(|TypedHeader(ua)| ua.as_str());
+
+        Ok(Json(LoginResult::Success(
+            create_session(&server, user.id, ip, user_agent).await?,
+        )))
     } else {
         Err(ApiError::Unauthorized)
     }
 }
 
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn login_totp(
+    State(server): State<Arc<BlazeBooruServer>>,
+    SecureClientIp(ip): SecureClientIp,
+    user_agent: Option<TypedHeader<UserAgent>>,
+    Json(req): Json<LoginTotpRequest>,
+) -> Result<Json<LoginResponse>, ApiError> {
+    let TotpChallengeClaims { totp_user_id: user_id } = server.auth.verify(&req.challenge_token)?;
+
+    let rate_limit_keys = [RateLimitKey::Ip(ip), RateLimitKey::Totp(user_id)];
+
+    server
+        .login_limiter
+        .attempt(&rate_limit_keys)
+        .map_err(ApiError::TooManyRequests)?;
+
+    if !server.core.verify_totp(user_id, &req.code).await? {
+        return Err(ApiError::Unauthorized);
+    }
+
+    server.login_limiter.succeeded(&rate_limit_keys);
+
+    let user_agent = user_agent.as_ref().map(|TypedHeader(ua)| ua.as_str());
+
+    Ok(Json(create_session(&server, user_id, ip, user_agent).await?))
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn oidc_authorize(
+    State(server): State<Arc<BlazeBooruServer>>,
+) -> Result<([(header::HeaderName, String); 1], Redirect), ApiError> {
+    let oidc = server.oidc.as_ref().ok_or(ApiError::NotFound)?;
+
+    let (url, pending_login) = oidc.authorize_url().await?;
+
+    // Tie the login to this browser, so that the callback
+    // cannot be completed in another browser (login CSRF),
+    // and any server instance can complete it.
+    let pending_login = server.auth.generate_token(&JwtClaims::oidc_login(pending_login))?;
+    let secure = if oidc.config.redirect_url.starts_with("https://") {
+        "; Secure"
+    } else {
+        ""
+    };
+    let cookie = format!(
+        "{OIDC_LOGIN_COOKIE}={pending_login}; Path={OIDC_LOGIN_COOKIE_PATH}; Max-Age={}; HttpOnly; SameSite=Lax{secure}",
+        oidc_login_lifetime().num_seconds()
+    );
+
+    Ok(([(header::SET_COOKIE, cookie)], Redirect::to(url.as_str())))
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn oidc_callback(
+    State(server): State<Arc<BlazeBooruServer>>,
+    SecureClientIp(ip): SecureClientIp,
+    user_agent: Option<TypedHeader<UserAgent>>,
+    cookies: Option<TypedHeader<Cookie>>,
+    Json(req): Json<OidcCallbackRequest>,
+) -> Result<([(header::HeaderName, String); 1], Json<LoginResponse>), ApiError> {
+    let oidc = server.oidc.as_ref().ok_or(ApiError::NotFound)?;
+
+    // Only the browser that started the login can complete it
+    let pending_login = cookies
+        .as_ref()
+        .and_then(|TypedHeader(cookies)| cookies.get(OIDC_LOGIN_COOKIE))
+        .ok_or(ApiError::BadRequest)?;
+    let pending_login = server
+        .auth
+        .verify::<PendingLogin>(pending_login)
+        .map_err(|_| ApiError::BadRequest)?;
+
+    let identity = oidc
+        .exchange_code(&req.code, &req.state, &pending_login)
+        .await
+        .context("Error completing OIDC login")?
+        .ok_or(ApiError::BadRequest)?;
+
+    let identity = lm::OidcIdentity {
+        issuer: identity.issuer.into(),
+        subject: identity.subject.into(),
+        preferred_name: identity.preferred_username.map(Into::into),
+    };
+
+    // The provider is responsible for any second factor
+    let user = server
+        .core
+        .get_or_create_oidc_user(identity, oidc.config.allow_registration)
+        .await
+        .context("Error getting OIDC user")?
+        .ok_or(ApiError::Forbidden)?;
+
+    let user_agent = user_agent.as_ref().map(|TypedHeader(ua)| ua.as_str());
+    let login = create_session(&server, user.id, ip, user_agent).await?;
+
+    // The login has been completed, so the cookie is no longer needed
+    let cookie = format!("{OIDC_LOGIN_COOKIE}=; Path={OIDC_LOGIN_COOKIE_PATH}; Max-Age=0; HttpOnly; SameSite=Lax");
+
+    Ok(([(header::SET_COOKIE, cookie)], Json(login)))
+}
+
+/// Start a new session for a user that has been successfully authenticated.
+async fn create_session(
+    server: &BlazeBooruServer,
+    user_id: i32,
+    ip: IpAddr,
+    user_agent: Option<&str>,
+) -> Result<LoginResponse, ApiError> {
+    let lm::CreateRefreshTokenResult {
+        token: refresh_token,
+        session,
+    } = server.core.create_refresh_token(user_id, ip, user_agent).await?;
+
+    let claims = AuthClaims { user_id };
+    let claims = SessionClaims { session, claims };
+
+    let claims = JwtClaims::short(claims);
+    let exp = claims.exp;
+    let access_token = server.auth.generate_token(&claims)?;
+
+    Ok(LoginResponse {
+        access_token,
+        exp,
+        refresh_token,
+    })
+}
+
 #[axum::debug_handler(state = Arc<BlazeBooruServer>)]
 async fn logout(State(server): State<Arc<BlazeBooruServer>>, auth: Authorized) -> Result<(), ApiError> {
-    server.core.logout(auth.session).await?;
+    let session = auth.session()?;
+
+    server.core.logout(session).await?;
+    server.auth.revoke_session(session);
 
     Ok(())
 }
@@ -80,27 +262,40 @@ async fn logout(State(server): State<Arc<BlazeBooruServer>>, auth: Authorized) -
 async fn refresh(
     State(server): State<Arc<BlazeBooruServer>>,
     SecureClientIp(ip): SecureClientIp,
+    user_agent: Option<TypedHeader<UserAgent>>,
     Json(req): Json<RefreshRequest>,
 ) -> Result<Json<LoginResponse>, ApiError> {
-    if let Some(lm::RefreshRefreshTokenResult {
-        token: refresh_token,
-        session,
-        user_id,
-    }) = server.core.refresh_refresh_token(req.refresh_token, ip).await?
+    let user_agent = user_agent.as_ref().map(|TypedHeader(ua)| ua.as_str());
+
+    match server
+        .core
+        .refresh_refresh_token(req.refresh_token, ip, user_agent)
+        .await?
     {
-        let claims = AuthClaims { user_id };
-        let claims = SessionClaims { session, claims };
-
-        let claims = JwtClaims::short(claims);
-        let access_token = server.auth.generate_token(&claims)?;
-        let exp = claims.exp;
-
-        return Ok(Json(LoginResponse {
-            access_token,
-            exp,
-            refresh_token,
-        }));
-    }
+        Some(lm::RefreshRefreshTokenResult::Refreshed {
+            token: refresh_token,
+            session,
+            user_id,
+        }) => {
+            let claims = AuthClaims { user_id };
+            let claims = SessionClaims { session, claims };
+
+            let claims = JwtClaims::short(claims);
+            let access_token = server.auth.generate_token(&claims)?;
+            let exp = claims.exp;
 
-    Err(ApiError::Unauthorized)
+            Ok(Json(LoginResponse {
+                access_token,
+                exp,
+                refresh_token,
+            }))
+        }
+        Some(lm::RefreshRefreshTokenResult::Reused { session }) => {
+            // The token may have been stolen, so access tokens of the session must stop working immediately
+            server.auth.revoke_session(session);
+
+            Err(ApiError::Unauthorized)
+        }
+        None => Err(ApiError::Unauthorized),
+    }
 }
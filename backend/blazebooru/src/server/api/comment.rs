@@ -0,0 +1,123 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::extract::Path;
+use axum::extract::State;
+use axum::routing::{delete, get, post};
+use axum::Json;
+use axum::Router;
+use chrono::Duration;
+
+use blazebooru_models::view as vm;
+
+use crate::server::api::Authorized;
+use crate::server::ApiError;
+use crate::server::BlazeBooruServer;
+
+pub fn router() -> Router<Arc<BlazeBooruServer>> {
+    Router::new()
+        .route("/held", get(get_held_comments))
+        .route("/{id}", delete(delete_comment))
+        .route("/{id}/approve", post(approve_comment))
+        .route("/{id}/update", post(update_comment))
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn get_held_comments(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+) -> Result<Json<Vec<vm::Comment>>, ApiError> {
+    // Moderation is not available to API keys
+    auth.require_user()?;
+
+    if !server.core.is_user_admin(auth.claims.user_id).await? {
+        return Err(ApiError::Forbidden);
+    }
+
+    let comments = server
+        .core
+        .get_held_comments()
+        .await
+        .context("Error getting held comments")?;
+
+    Ok(Json(comments))
+}
+
+/// Approve a held comment.
+/// Held comments are rejected by deleting them.
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn approve_comment(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+    Path(id): Path<i32>,
+) -> Result<(), ApiError> {
+    // Moderation is not available to API keys
+    auth.require_user()?;
+
+    let success = server
+        .core
+        .approve_comment(id, auth.claims.user_id)
+        .await
+        .context("Error approving comment")?;
+
+    if !success {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(())
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn update_comment(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+    Path(id): Path<i32>,
+    Json(req): Json<vm::UpdateComment>,
+) -> Result<(), ApiError> {
+    // Commenting is not available to API keys
+    auth.require_user()?;
+
+    let edit_window = Duration::seconds(server.config.comment_edit_seconds.into());
+
+    let success = server
+        .core
+        .update_comment(
+            id,
+            req,
+            auth.claims.user_id,
+            edit_window,
+            server.config.comment_filter.as_ref(),
+        )
+        .await
+        .context("Error updating comment")?;
+
+    if !success {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(())
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn delete_comment(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+    Path(id): Path<i32>,
+) -> Result<(), ApiError> {
+    // Commenting is not available to API keys
+    auth.require_user()?;
+
+    let edit_window = Duration::seconds(server.config.comment_edit_seconds.into());
+
+    let success = server
+        .core
+        .delete_comment(id, auth.claims.user_id, edit_window)
+        .await
+        .context("Error deleting comment")?;
+
+    if !success {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,126 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::{Query, State};
+use axum::response::sse::{Event as SseEvent, KeepAlive, Sse};
+use axum::routing::get;
+use axum::Router;
+use futures::Stream;
+use serde::Deserialize;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use tokio::time::Interval;
+
+use blazebooru_models::view as vm;
+
+use crate::server::api::Authorized;
+use crate::server::ApiError;
+use crate::server::BlazeBooruServer;
+
+/// How often open event streams check that the session they were opened with is still valid
+const SESSION_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Deserialize)]
+struct EventQuery {
+    /// Post to receive comment events for.
+    /// If specified, tag change events are also limited to this post.
+    post_id: Option<i32>,
+}
+
+struct EventStream {
+    server: Arc<BlazeBooruServer>,
+    auth: Option<Authorized>,
+    receiver: Receiver<vm::Event>,
+    session_check: Interval,
+}
+
+pub fn router() -> Router<Arc<BlazeBooruServer>> {
+    Router::new().route("/", get(get_events))
+}
+
+/// Stream live updates as server-sent events.
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn get_events(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Option<Authorized>,
+    Query(EventQuery { post_id }): Query<EventQuery>,
+) -> Result<Sse<impl Stream<Item = Result<SseEvent, axum::Error>>>, ApiError> {
+    if server.config.require_login {
+        let auth = auth.as_ref().ok_or(ApiError::Unauthorized)?;
+        auth.require_scope(vm::ApiKeyScope::Read)?;
+    }
+
+    let state = EventStream {
+        receiver: server.events.subscribe(),
+        session_check: tokio::time::interval(SESSION_CHECK_INTERVAL),
+        server,
+        auth,
+    };
+
+    let stream = futures::stream::unfold(state, move |mut state| async move {
+        loop {
+            tokio::select! {
+                result = state.receiver.recv() => match result {
+                    Ok(event) if is_event_wanted(&event, post_id) => {
+                        return Some((SseEvent::default().json_data(&event), state));
+                    }
+                    // Clients that fall behind miss some events rather than being disconnected
+                    Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return None,
+                },
+                _ = state.session_check.tick() => {
+                    // End the stream once the session has ended,
+                    // so that the client has to reconnect with a valid token.
+                    let server = &state.server;
+
+                    if state.auth.as_ref().is_some_and(|auth| auth.has_session_ended(&server.auth)) {
+                        return None;
+                    }
+                }
+            }
+        }
+    });
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+fn is_event_wanted(event: &vm::Event, wanted_post_id: Option<i32>) -> bool {
+    match event {
+        vm::Event::Post { .. } => true,
+        vm::Event::Comment { post_id, .. } => wanted_post_id == Some(*post_id),
+        vm::Event::TagChange { post_id } => wanted_post_id.is_none_or(|id| id == *post_id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn post_events_are_always_wanted() {
+        let event = vm::Event::Post { post_id: 1 };
+
+        assert!(is_event_wanted(&event, None));
+        assert!(is_event_wanted(&event, Some(2)));
+    }
+
+    #[test]
+    fn comment_events_are_only_wanted_for_the_watched_post() {
+        let event = vm::Event::Comment {
+            post_id: 1,
+            comment_id: 10,
+        };
+
+        assert!(!is_event_wanted(&event, None));
+        assert!(is_event_wanted(&event, Some(1)));
+        assert!(!is_event_wanted(&event, Some(2)));
+    }
+
+    #[test]
+    fn tag_change_events_are_limited_to_the_watched_post() {
+        let event = vm::Event::TagChange { post_id: 1 };
+
+        assert!(is_event_wanted(&event, None));
+        assert!(is_event_wanted(&event, Some(1)));
+        assert!(!is_event_wanted(&event, Some(2)));
+    }
+}
@@ -0,0 +1,212 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::extract::{Query, State};
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use chrono::{DateTime, SecondsFormat, Utc};
+use reqwest::Url;
+use serde::Deserialize;
+
+use blazebooru_models::view as vm;
+
+use crate::server::api::post::{apply_blacklist, get_max_rating, PostSearchQuery};
+use crate::server::api::Authorized;
+use crate::server::ApiError;
+use crate::server::BlazeBooruServer;
+
+/// Number of posts included in a feed
+const FEED_POST_COUNT: i32 = 50;
+
+#[derive(Deserialize)]
+struct FeedQuery {
+    /// API key, for feed readers that can not set the authorization header
+    key: Option<String>,
+}
+
+pub fn router() -> Router<Arc<BlazeBooruServer>> {
+    Router::new().route("/feed.atom", get(get_atom_feed))
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn get_atom_feed(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Option<Authorized>,
+    Query(mut search): Query<PostSearchQuery>,
+    Query(FeedQuery { key }): Query<FeedQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let auth = match (auth, key) {
+        (None, Some(key)) => Some(Authorized::from_api_key(&server, &key).await?),
+        (auth, _) => auth,
+    };
+
+    if server.config.require_login {
+        let auth = auth.as_ref().ok_or(ApiError::Unauthorized)?;
+        auth.require_scope(vm::ApiKeyScope::Read)?;
+    }
+
+    let base_url = get_base_url(server.config.public_url.as_deref())?;
+
+    // The feed links to itself without the API key, to avoid leaking it
+    let mut self_url = Url::parse(&format!("{base_url}/api/feed.atom")).context("Error creating feed URL")?;
+    if !search.include_tags.is_empty() {
+        self_url
+            .query_pairs_mut()
+            .append_pair("t", &search.include_tags.join(","));
+    }
+    if !search.exclude_tags.is_empty() {
+        self_url
+            .query_pairs_mut()
+            .append_pair("e", &search.exclude_tags.join(","));
+    }
+
+    let query: Vec<_> = search
+        .include_tags
+        .iter()
+        .cloned()
+        .chain(search.exclude_tags.iter().map(|t| format!("-{t}")))
+        .collect();
+
+    let title = if query.is_empty() {
+        "BlazeBooru".to_string()
+    } else {
+        format!("BlazeBooru: {}", query.join(" "))
+    };
+
+    let max_rating = get_max_rating(&server, auth.as_ref()).await?;
+    apply_blacklist(&server, auth.as_ref(), &mut search).await?;
+
+    let posts = server
+        .core
+        .get_view_posts(
+            search.include_tags,
+            search.exclude_tags,
+            max_rating,
+            i32::MAX,
+            FEED_POST_COUNT,
+        )
+        .await
+        .context("Error getting view posts")?;
+
+    // Posts are never edited in a way that warrants showing them again,
+    // so the feed is updated when the newest post was created.
+    // The newest post is not necessarily the first, as the search can be sorted differently.
+    let updated = posts.iter().map(|p| p.created_at).max().unwrap_or_else(Utc::now);
+
+    let mut xml = String::new();
+    xml.push_str(r#"<?xml version="1.0" encoding="utf-8"?>"#);
+    xml.push('\n');
+    xml.push_str(r#"<feed xmlns="http://www.w3.org/2005/Atom">"#);
+    xml.push('\n');
+    xml.push_str(&format!("  <id>{}</id>\n", escape_xml(self_url.as_str())));
+    xml.push_str(&format!("  <title>{}</title>\n", escape_xml(&title)));
+    xml.push_str(&format!("  <updated>{}</updated>\n", format_timestamp(updated)));
+    xml.push_str(&format!(
+        "  <link rel=\"self\" type=\"application/atom+xml\" href=\"{}\"/>\n",
+        escape_xml(self_url.as_str())
+    ));
+    xml.push_str(&format!(
+        "  <link rel=\"alternate\" type=\"text/html\" href=\"{}/\"/>\n",
+        escape_xml(base_url)
+    ));
+
+    for post in &posts {
+        xml.push_str(&format_entry(post, base_url));
+    }
+
+    xml.push_str("</feed>\n");
+
+    Ok(([(header::CONTENT_TYPE, "application/atom+xml; charset=utf-8")], xml))
+}
+
+fn format_entry(post: &vm::Post, base_url: &str) -> String {
+    let post_url = format!("{base_url}/post/{}", post.id);
+    let image_url = format!("{base_url}/f/o/{}.{}", post.hash, post.ext);
+    let thumbnail_url = format!("{base_url}/f/t/{}.{}", post.hash, post.tn_ext);
+
+    let title = match &post.title {
+        Some(title) => title.clone(),
+        None => format!("Post #{}", post.id),
+    };
+
+    let mut content = format!(
+        r#"<a href="{}"><img src="{}"/></a>"#,
+        escape_xml(&post_url),
+        escape_xml(&thumbnail_url)
+    );
+    if let Some(description) = &post.description {
+        content.push_str(&format!("<p>{}</p>", escape_xml(description)));
+    }
+
+    let timestamp = format_timestamp(post.created_at);
+
+    let mut xml = String::new();
+    xml.push_str("  <entry>\n");
+    xml.push_str(&format!("    <id>{}</id>\n", escape_xml(&post_url)));
+    xml.push_str(&format!("    <title>{}</title>\n", escape_xml(&title)));
+    xml.push_str(&format!("    <published>{timestamp}</published>\n"));
+    xml.push_str(&format!("    <updated>{timestamp}</updated>\n"));
+    xml.push_str(&format!(
+        "    <author><name>{}</name></author>\n",
+        escape_xml(&post.user_name)
+    ));
+    xml.push_str(&format!(
+        "    <link rel=\"alternate\" type=\"text/html\" href=\"{}\"/>\n",
+        escape_xml(&post_url)
+    ));
+    xml.push_str(&format!(
+        "    <link rel=\"enclosure\" href=\"{}\" length=\"{}\"/>\n",
+        escape_xml(&image_url),
+        post.size
+    ));
+    for tag in &post.tags {
+        xml.push_str(&format!("    <category term=\"{}\"/>\n", escape_xml(tag)));
+    }
+    xml.push_str(&format!(
+        "    <content type=\"html\">{}</content>\n",
+        escape_xml(&content)
+    ));
+    xml.push_str("  </entry>\n");
+
+    xml
+}
+
+/// Get the URL of the site, without a trailing slash.
+/// Feeds are only available if a public URL is configured,
+/// as the request headers can not be trusted to contain the URL clients use.
+fn get_base_url(public_url: Option<&str>) -> Result<&str, ApiError> {
+    let public_url = public_url.ok_or(ApiError::NotFound)?;
+
+    Ok(public_url.trim_end_matches('/'))
+}
+
+fn format_timestamp(timestamp: DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base_url_is_the_configured_url() {
+        let base_url = get_base_url(Some("https://booru.example.com/")).unwrap();
+        assert_eq!(base_url, "https://booru.example.com");
+    }
+
+    #[test]
+    fn feeds_require_a_public_url() {
+        assert!(matches!(get_base_url(None), Err(ApiError::NotFound)));
+    }
+}
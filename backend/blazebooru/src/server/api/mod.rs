@@ -1,48 +1,88 @@
+mod audit_log;
 mod auth;
+mod comment;
+mod event;
+mod feed;
+mod notification;
+mod pool;
 mod post;
+mod report;
+mod saved_search;
 mod sys;
 mod tag;
 mod user;
 
+use std::net::SocketAddr;
 use std::sync::Arc;
 
+use anyhow::anyhow;
 use axum::{
-    extract::{FromRequestParts, OptionalFromRequestParts},
+    extract::{ConnectInfo, FromRequestParts, OptionalFromRequestParts},
     http::{request::Parts, StatusCode},
     response::{IntoResponse, Response},
     RequestPartsExt, Router,
 };
+use chrono::{DateTime, Utc};
 
 use axum_extra::{
     headers::{authorization::Bearer, Authorization},
     TypedHeader,
 };
-use blazebooru_core::config::BlazeBooruConfig;
+use blazebooru_core::{config::BlazeBooruConfig, API_KEY_PREFIX};
+use blazebooru_models::local as lm;
+use blazebooru_models::view as vm;
 
 use crate::{
-    auth::{AuthClaims, AuthError, SessionClaims},
-    server::BlazeBooruServer,
+    auth::{AuthClaims, AuthError, BlazeBooruAuth, SessionClaims},
+    server::{ApiError, BlazeBooruServer},
 };
 
 #[derive(Debug)]
 struct Authorized {
-    session: i64,
+    credential: Credential,
     claims: AuthClaims,
 }
 
+#[derive(Debug)]
+enum Credential {
+    Session {
+        session: i64,
+        expires_at: DateTime<Utc>,
+    },
+    ApiKey(lm::ApiKey),
+    /// User name asserted by a trusted reverse proxy
+    Proxy,
+}
+
 pub fn router(config: &BlazeBooruConfig) -> Router<Arc<BlazeBooruServer>> {
+    let audit_log = audit_log::router();
     let auth = auth::router();
+    let comment = comment::router();
+    let event = event::router();
+    let feed = feed::router();
+    let notification = notification::router();
+    let pool = pool::router();
     let post = post::router(config);
+    let report = report::router();
+    let saved_search = saved_search::router();
     let sys = sys::router();
     let user = user::router();
     let tag = tag::router();
 
     Router::new()
+        .nest("/audit-log", audit_log)
         .nest("/auth", auth)
         .nest("/sys", sys)
+        .nest("/comment", comment)
+        .nest("/events", event)
+        .nest("/notifications", notification)
+        .nest("/pool", pool)
         .nest("/post", post)
+        .nest("/report", report)
+        .nest("/saved-search", saved_search)
         .nest("/user", user)
         .nest("/tag", tag)
+        .merge(feed)
 }
 
 impl IntoResponse for AuthError {
@@ -51,32 +91,119 @@ impl IntoResponse for AuthError {
             AuthError::ExpiredToken => StatusCode::UNAUTHORIZED,
             AuthError::TokenCreation => StatusCode::INTERNAL_SERVER_ERROR,
             AuthError::InvalidToken => StatusCode::BAD_REQUEST,
+            AuthError::RevokedToken => StatusCode::UNAUTHORIZED,
         };
 
         (status, self.to_string()).into_response()
     }
 }
 
+impl Authorized {
+    /// Get the session, if authorized using an access token.
+    fn session(&self) -> Result<i64, ApiError> {
+        match self.credential {
+            Credential::Session { session, .. } => Ok(session),
+            Credential::ApiKey(_) | Credential::Proxy => Err(ApiError::Forbidden),
+        }
+    }
+
+    /// Authorize using an API key.
+    async fn from_api_key(server: &BlazeBooruServer, key: &str) -> Result<Self, ApiError> {
+        let api_key = server
+            .core
+            .authenticate_api_key(key)
+            .await?
+            .ok_or(AuthError::InvalidToken)?;
+
+        let claims = AuthClaims {
+            user_id: api_key.user_id,
+        };
+
+        Ok(Authorized {
+            credential: Credential::ApiKey(api_key),
+            claims,
+        })
+    }
+
+    /// Check that the user is acting directly, rather than through an API key.
+    /// Actions not covered by any API key scope require this.
+    fn require_user(&self) -> Result<(), ApiError> {
+        match self.credential {
+            Credential::Session { .. } | Credential::Proxy => Ok(()),
+            Credential::ApiKey(_) => Err(ApiError::Forbidden),
+        }
+    }
+
+    /// Check that the credential permits actions in the specified scope.
+    /// Users acting directly are permitted everything.
+    fn require_scope(&self, scope: vm::ApiKeyScope) -> Result<(), ApiError> {
+        match &self.credential {
+            Credential::Session { .. } | Credential::Proxy => Ok(()),
+            Credential::ApiKey(api_key) if api_key.scopes.contains(&scope) => Ok(()),
+            Credential::ApiKey(_) => Err(ApiError::Forbidden),
+        }
+    }
+
+    /// Check whether the access token has expired or been revoked since it was verified,
+    /// for requests that outlive the token, such as event streams.
+    fn has_session_ended(&self, auth: &BlazeBooruAuth) -> bool {
+        match self.credential {
+            Credential::Session { session, expires_at } => expires_at <= Utc::now() || auth.is_session_revoked(session),
+            Credential::ApiKey(_) | Credential::Proxy => false,
+        }
+    }
+}
+
 impl FromRequestParts<Arc<BlazeBooruServer>> for Authorized {
-    type Rejection = AuthError;
+    type Rejection = ApiError;
 
     async fn from_request_parts(parts: &mut Parts, state: &Arc<BlazeBooruServer>) -> Result<Self, Self::Rejection> {
         let authorized = Option::<Authorized>::from_request_parts(parts, state).await;
 
         match authorized {
-            Ok(v) => v.ok_or(AuthError::InvalidToken),
+            Ok(v) => v.ok_or(ApiError::AuthError(AuthError::InvalidToken)),
             Err(err) => Err(err),
         }
     }
 }
 
 impl OptionalFromRequestParts<Arc<BlazeBooruServer>> for Authorized {
-    type Rejection = AuthError;
+    type Rejection = ApiError;
 
     async fn from_request_parts(
         parts: &mut Parts,
         state: &Arc<BlazeBooruServer>,
     ) -> Result<Option<Self>, Self::Rejection> {
+        // Trust the user name header if the request came through a trusted proxy
+        if let Some(proxy_auth) = &state.config.proxy_auth {
+            // This needs the address of the proxy itself, rather than the client IP
+            // from SecureClientIp, which is what rate limits and sessions are keyed on.
+            let ConnectInfo(peer_addr) = parts
+                .extract::<ConnectInfo<SocketAddr>>()
+                .await
+                .map_err(|err| anyhow!("{err}"))?;
+
+            let user_name = parts.headers.get(&proxy_auth.header);
+
+            // Listening on IPv6 makes IPv4 peers show up as IPv4-mapped addresses
+            let is_trusted = proxy_auth.trusted_proxies.contains(&peer_addr.ip().to_canonical());
+
+            if let Some(user_name) = user_name.filter(|_| is_trusted) {
+                let user_name = user_name.to_str().map_err(|_| ApiError::BadRequest)?;
+
+                let user = state
+                    .core
+                    .get_or_create_proxy_user(user_name, proxy_auth.allow_registration)
+                    .await?
+                    .ok_or(ApiError::Forbidden)?;
+
+                return Ok(Some(Authorized {
+                    credential: Credential::Proxy,
+                    claims: AuthClaims { user_id: user.id },
+                }));
+            }
+        }
+
         // Extract the token from the authorization header
         let auth_header = parts
             .extract::<Option<TypedHeader<Authorization<Bearer>>>>()
@@ -89,8 +216,23 @@ impl OptionalFromRequestParts<Arc<BlazeBooruServer>> for Authorized {
 
         let token = bearer.token();
 
-        let SessionClaims { session, claims } = state.auth.verify::<SessionClaims>(token)?;
+        // API keys are told apart from access tokens by their prefix
+        if token.starts_with(API_KEY_PREFIX) {
+            return Authorized::from_api_key(state, token).await.map(Some);
+        }
+
+        let jwt_claims = state.auth.verify_jwt::<SessionClaims>(token)?;
+        let expires_at = jwt_claims.expires_at().ok_or(AuthError::InvalidToken)?;
+        let SessionClaims { session, claims } = jwt_claims.claims;
+
+        // Reject tokens belonging to sessions that have been logged out
+        if state.auth.is_session_revoked(session) {
+            return Err(AuthError::RevokedToken.into());
+        }
 
-        Ok(Some(Authorized { session, claims }))
+        Ok(Some(Authorized {
+            credential: Credential::Session { session, expires_at },
+            claims,
+        }))
     }
 }
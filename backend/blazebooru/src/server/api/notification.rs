@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::extract::Query;
+use axum::extract::State;
+use axum::routing::{get, post};
+use axum::Json;
+use axum::Router;
+use serde::Deserialize;
+
+use blazebooru_models::view as vm;
+
+use crate::server::api::Authorized;
+use crate::server::ApiError;
+use crate::server::BlazeBooruServer;
+
+#[derive(Deserialize)]
+struct PaginatedQuery {
+    #[serde(rename = "sid")]
+    start_id: Option<i32>,
+    limit: i32,
+}
+
+pub fn router() -> Router<Arc<BlazeBooruServer>> {
+    Router::new()
+        .route("/", get(get_notifications))
+        .route("/unread-count", get(get_unread_notification_count))
+        .route("/read", post(mark_notifications_read))
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn get_notifications(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+    Query(PaginatedQuery { start_id, limit }): Query<PaginatedQuery>,
+) -> Result<Json<Vec<vm::Notification>>, ApiError> {
+    // Notifications are not available to API keys
+    auth.require_user()?;
+
+    let notifications = server
+        .core
+        .get_notifications(auth.claims.user_id, start_id, limit)
+        .await
+        .context("Error getting notifications")?;
+
+    Ok(Json(notifications))
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn get_unread_notification_count(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+) -> Result<Json<i64>, ApiError> {
+    // Notifications are not available to API keys
+    auth.require_user()?;
+
+    let count = server
+        .core
+        .get_unread_notification_count(auth.claims.user_id)
+        .await
+        .context("Error getting unread notification count")?;
+
+    Ok(Json(count))
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn mark_notifications_read(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+    Json(req): Json<vm::MarkNotificationsRead>,
+) -> Result<(), ApiError> {
+    // Notifications are not available to API keys
+    auth.require_user()?;
+
+    server
+        .core
+        .mark_notifications_read(req, auth.claims.user_id)
+        .await
+        .context("Error marking notifications as read")?;
+
+    Ok(())
+}
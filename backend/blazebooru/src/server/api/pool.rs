@@ -0,0 +1,116 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::extract::Path;
+use axum::extract::State;
+use axum::routing::{get, post};
+use axum::Json;
+use axum::Router;
+
+use blazebooru_models::view as vm;
+
+use crate::server::api::Authorized;
+use crate::server::ApiError;
+use crate::server::BlazeBooruServer;
+
+pub fn router() -> Router<Arc<BlazeBooruServer>> {
+    Router::new()
+        .route("/", get(get_view_pools))
+        .route("/new", post(create_pool))
+        .route("/{id}", get(get_view_pool).delete(delete_pool))
+        .route("/{id}/update", post(update_pool))
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn get_view_pool(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Option<Authorized>,
+    Path(id): Path<i32>,
+) -> Result<Json<vm::Pool>, ApiError> {
+    if server.config.require_login {
+        let auth = auth.as_ref().ok_or(ApiError::Unauthorized)?;
+        auth.require_scope(vm::ApiKeyScope::Read)?;
+    }
+
+    let pool = server.core.get_view_pool(id).await.context("Error getting view pool")?;
+
+    Ok(Json(pool.ok_or(ApiError::NotFound)?))
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn get_view_pools(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Option<Authorized>,
+) -> Result<Json<Vec<vm::Pool>>, ApiError> {
+    if server.config.require_login {
+        let auth = auth.as_ref().ok_or(ApiError::Unauthorized)?;
+        auth.require_scope(vm::ApiKeyScope::Read)?;
+    }
+
+    let pools = server.core.get_view_pools().await.context("Error getting view pools")?;
+
+    Ok(Json(pools))
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn create_pool(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+    Json(req): Json<vm::NewPool>,
+) -> Result<Json<i32>, ApiError> {
+    // Pools are not available to API keys
+    auth.require_user()?;
+
+    let new_pool_id = server
+        .core
+        .create_pool(req, auth.claims.user_id)
+        .await
+        .context("Error creating pool")?;
+
+    Ok(Json(new_pool_id))
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn update_pool(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+    Path(id): Path<i32>,
+    Json(req): Json<vm::UpdatePool>,
+) -> Result<(), ApiError> {
+    // Pools are not available to API keys
+    auth.require_user()?;
+
+    let success = server
+        .core
+        .update_pool(id, req, auth.claims.user_id)
+        .await
+        .context("Error updating pool")?;
+
+    if !success {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(())
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn delete_pool(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+    Path(id): Path<i32>,
+) -> Result<(), ApiError> {
+    // Pools are not available to API keys
+    auth.require_user()?;
+
+    let success = server
+        .core
+        .delete_pool(id, auth.claims.user_id)
+        .await
+        .context("Error deleting pool")?;
+
+    if !success {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(())
+}
@@ -10,6 +10,7 @@ use axum::handler::Handler;
 use axum::routing::{get, post};
 use axum::Json;
 use axum::Router;
+use axum_client_ip::SecureClientIp;
 use serde::Deserialize;
 
 use blazebooru_core::config::BlazeBooruConfig;
@@ -42,11 +43,18 @@ struct PostInfo {
     title: Option<String>,
     description: Option<String>,
     source: Option<String>,
+    parent_id: Option<i32>,
+    rating: Option<vm::PostRating>,
 
     #[serde(default)]
     tags: Vec<String>,
 }
 
+#[derive(Deserialize)]
+struct VoteRequest {
+    score: i32,
+}
+
 #[derive(Deserialize)]
 struct PaginatedQuery {
     #[serde(default)]
@@ -56,15 +64,26 @@ struct PaginatedQuery {
 }
 
 #[derive(Deserialize)]
-struct PostSearchQuery {
+struct DeletedPostsQuery {
+    #[serde(rename = "sid")]
+    start_id: Option<i32>,
+    limit: i32,
+}
+
+#[derive(Deserialize)]
+pub(super) struct PostSearchQuery {
     #[serde(rename = "t")]
     #[serde(default)]
     #[serde(deserialize_with = "crate::deserialize::comma_separated")]
-    include_tags: Vec<String>,
+    pub(super) include_tags: Vec<String>,
     #[serde(rename = "e")]
     #[serde(default)]
     #[serde(deserialize_with = "crate::deserialize::comma_separated")]
-    exclude_tags: Vec<String>,
+    pub(super) exclude_tags: Vec<String>,
+    /// Do not exclude the user's blacklisted tags
+    #[serde(rename = "nbl")]
+    #[serde(default)]
+    no_blacklist: bool,
 }
 
 pub fn router(config: &BlazeBooruConfig) -> Router<Arc<BlazeBooruServer>> {
@@ -72,8 +91,14 @@ pub fn router(config: &BlazeBooruConfig) -> Router<Arc<BlazeBooruServer>> {
         .route("/", get(get_view_posts))
         .route("/{id}", get(get_view_post).delete(delete_post))
         .route("/{id}/update", post(update_post))
+        .route("/{id}/undelete", post(undelete_post))
+        .route("/{id}/purge", post(purge_post))
+        .route("/{id}/favorite", post(favorite_post).delete(unfavorite_post))
+        .route("/{id}/vote", post(vote_post).delete(unvote_post))
         .route("/{id}/comments", get(get_post_comments))
         .route("/{id}/comments/new", post(post_comment))
+        .route("/{id}/report", post(report_post))
+        .route("/trash", get(get_deleted_posts))
         .route("/pages", get(calculate_pages))
         .route("/pages/last", get(calculate_last_page))
         .route(
@@ -88,8 +113,9 @@ async fn get_view_post(
     auth: Option<Authorized>,
     Path(id): Path<i32>,
 ) -> Result<Json<vm::Post>, ApiError> {
-    if server.config.require_login && auth.is_none() {
-        return Err(ApiError::Unauthorized);
+    if server.config.require_login {
+        let auth = auth.as_ref().ok_or(ApiError::Unauthorized)?;
+        auth.require_scope(vm::ApiKeyScope::Read)?;
     }
 
     let post = server.core.get_view_post(id).await.context("Error getting view post")?;
@@ -104,17 +130,19 @@ async fn update_post(
     Path(id): Path<i32>,
     Json(req): Json<vm::UpdatePost>,
 ) -> Result<(), ApiError> {
-    let post = server
+    auth.require_scope(vm::ApiKeyScope::EditTags)?;
+
+    let result = server
         .core
         .update_post(id, req, auth.claims.user_id)
         .await
         .context("Error updating post")?;
 
-    if !post {
-        return Err(ApiError::NotFound);
+    match result {
+        lm::UpdatePostResult::Updated => Ok(()),
+        lm::UpdatePostResult::NotFound => Err(ApiError::NotFound),
+        lm::UpdatePostResult::InvalidParent => Err(ApiError::BadRequest),
     }
-
-    Ok(())
 }
 
 #[axum::debug_handler(state = Arc<BlazeBooruServer>)]
@@ -123,6 +151,9 @@ async fn delete_post(
     auth: Authorized,
     Path(id): Path<i32>,
 ) -> Result<(), ApiError> {
+    // Deleting posts is not available to API keys
+    auth.require_user()?;
+
     let success = server
         .core
         .delete_post(id, auth.claims.user_id)
@@ -136,23 +167,184 @@ async fn delete_post(
     Ok(())
 }
 
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn get_deleted_posts(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+    Query(DeletedPostsQuery { start_id, limit }): Query<DeletedPostsQuery>,
+) -> Result<Json<Vec<vm::Post>>, ApiError> {
+    // Moderation is not available to API keys
+    auth.require_user()?;
+
+    if !server.core.is_user_admin(auth.claims.user_id).await? {
+        return Err(ApiError::Forbidden);
+    }
+
+    let posts = server
+        .core
+        .get_deleted_posts(start_id, limit)
+        .await
+        .context("Error getting deleted posts")?;
+
+    Ok(Json(posts))
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn undelete_post(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+    Path(id): Path<i32>,
+) -> Result<(), ApiError> {
+    // Moderation is not available to API keys
+    auth.require_user()?;
+
+    let success = server
+        .core
+        .undelete_post(id, auth.claims.user_id)
+        .await
+        .context("Error undeleting post")?;
+
+    if !success {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(())
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn purge_post(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+    Path(id): Path<i32>,
+) -> Result<(), ApiError> {
+    // Moderation is not available to API keys
+    auth.require_user()?;
+
+    let success = server
+        .core
+        .purge_post(id, auth.claims.user_id)
+        .await
+        .context("Error purging post")?;
+
+    if !success {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(())
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn favorite_post(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+    Path(id): Path<i32>,
+) -> Result<(), ApiError> {
+    // Favorites are not available to API keys
+    auth.require_user()?;
+
+    let success = server
+        .core
+        .favorite_post(id, auth.claims.user_id)
+        .await
+        .context("Error favoriting post")?;
+
+    if !success {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(())
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn unfavorite_post(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+    Path(id): Path<i32>,
+) -> Result<(), ApiError> {
+    // Favorites are not available to API keys
+    auth.require_user()?;
+
+    let success = server
+        .core
+        .unfavorite_post(id, auth.claims.user_id)
+        .await
+        .context("Error unfavoriting post")?;
+
+    if !success {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(())
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn vote_post(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+    Path(id): Path<i32>,
+    Json(req): Json<VoteRequest>,
+) -> Result<(), ApiError> {
+    // Voting is not available to API keys
+    auth.require_user()?;
+
+    // Votes are either up or down
+    if req.score != 1 && req.score != -1 {
+        return Err(ApiError::BadRequest);
+    }
+
+    let success = server
+        .core
+        .vote_post(id, req.score, auth.claims.user_id)
+        .await
+        .context("Error voting on post")?;
+
+    if !success {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(())
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn unvote_post(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+    Path(id): Path<i32>,
+) -> Result<(), ApiError> {
+    // Voting is not available to API keys
+    auth.require_user()?;
+
+    let success = server
+        .core
+        .unvote_post(id, auth.claims.user_id)
+        .await
+        .context("Error retracting post vote")?;
+
+    if !success {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(())
+}
+
 #[axum::debug_handler(state = Arc<BlazeBooruServer>)]
 async fn get_view_posts(
     State(server): State<Arc<BlazeBooruServer>>,
     auth: Option<Authorized>,
-    Query(PostSearchQuery {
-        include_tags,
-        exclude_tags,
-    }): Query<PostSearchQuery>,
+    Query(mut search): Query<PostSearchQuery>,
     Query(PaginatedQuery { start_id, limit }): Query<PaginatedQuery>,
 ) -> Result<Json<Vec<vm::Post>>, ApiError> {
-    if server.config.require_login && auth.is_none() {
-        return Err(ApiError::Unauthorized);
+    if server.config.require_login {
+        let auth = auth.as_ref().ok_or(ApiError::Unauthorized)?;
+        auth.require_scope(vm::ApiKeyScope::Read)?;
     }
 
+    let max_rating = get_max_rating(&server, auth.as_ref()).await?;
+    apply_blacklist(&server, auth.as_ref(), &mut search).await?;
+
     let posts = server
         .core
-        .get_view_posts(include_tags, exclude_tags, start_id, limit)
+        .get_view_posts(search.include_tags, search.exclude_tags, max_rating, start_id, limit)
         .await
         .context("Error getting view posts")?;
 
@@ -162,10 +354,8 @@ async fn get_view_posts(
 #[axum::debug_handler(state = Arc<BlazeBooruServer>)]
 async fn calculate_pages(
     State(server): State<Arc<BlazeBooruServer>>,
-    Query(PostSearchQuery {
-        include_tags,
-        exclude_tags,
-    }): Query<PostSearchQuery>,
+    auth: Option<Authorized>,
+    Query(mut search): Query<PostSearchQuery>,
     Query(CalculatePageQuery { posts_per_page }): Query<CalculatePageQuery>,
     Query(CalculatePagesQuery {
         page_count,
@@ -173,8 +363,11 @@ async fn calculate_pages(
         origin_page_start_id,
     }): Query<CalculatePagesQuery>,
 ) -> Result<Json<Vec<vm::PageInfo>>, ApiError> {
-    let include_tags = include_tags.iter().map(|t| t.as_str()).collect();
-    let exclude_tags = exclude_tags.iter().map(|t| t.as_str()).collect();
+    let max_rating = get_max_rating(&server, auth.as_ref()).await?;
+    apply_blacklist(&server, auth.as_ref(), &mut search).await?;
+
+    let include_tags = search.include_tags.iter().map(|t| t.as_str()).collect();
+    let exclude_tags = search.exclude_tags.iter().map(|t| t.as_str()).collect();
 
     let origin_page = if let (Some(no), Some(start_id)) = (origin_page_no, origin_page_start_id) {
         Some(vm::PageInfo { no, start_id })
@@ -184,7 +377,14 @@ async fn calculate_pages(
 
     let pages = server
         .core
-        .calculate_pages(include_tags, exclude_tags, posts_per_page, page_count, origin_page)
+        .calculate_pages(
+            include_tags,
+            exclude_tags,
+            max_rating,
+            posts_per_page,
+            page_count,
+            origin_page,
+        )
         .await
         .context("Error calculating pages")?;
 
@@ -194,18 +394,19 @@ async fn calculate_pages(
 #[axum::debug_handler(state = Arc<BlazeBooruServer>)]
 async fn calculate_last_page(
     State(server): State<Arc<BlazeBooruServer>>,
-    Query(PostSearchQuery {
-        include_tags,
-        exclude_tags,
-    }): Query<PostSearchQuery>,
+    auth: Option<Authorized>,
+    Query(mut search): Query<PostSearchQuery>,
     Query(CalculatePageQuery { posts_per_page }): Query<CalculatePageQuery>,
 ) -> Result<Json<vm::PageInfo>, ApiError> {
-    let include_tags = include_tags.iter().map(|t| t.as_str()).collect();
-    let exclude_tags = exclude_tags.iter().map(|t| t.as_str()).collect();
+    let max_rating = get_max_rating(&server, auth.as_ref()).await?;
+    apply_blacklist(&server, auth.as_ref(), &mut search).await?;
+
+    let include_tags = search.include_tags.iter().map(|t| t.as_str()).collect();
+    let exclude_tags = search.exclude_tags.iter().map(|t| t.as_str()).collect();
 
     let page = server
         .core
-        .calculate_last_page(include_tags, exclude_tags, posts_per_page)
+        .calculate_last_page(include_tags, exclude_tags, max_rating, posts_per_page)
         .await
         .context("Error calculating last page")?;
 
@@ -218,6 +419,8 @@ async fn upload_post(
     auth: Authorized,
     mut multipart: Multipart,
 ) -> Result<Json<i32>, ApiError> {
+    auth.require_scope(vm::ApiKeyScope::Upload)?;
+
     let mut info: Option<PostInfo> = None;
     let mut file: Option<(HashedFile, String)> = None;
 
@@ -257,9 +460,16 @@ async fn upload_post(
             filename: filename.into(),
             file,
             tags: info.tags.iter().map(|t| t.as_str()).collect(),
+            parent_id: info.parent_id,
+            rating: info.rating,
         };
 
-        let new_post_id = server.core.create_post(new_post).await.context("Error creating post")?;
+        let new_post_id = server
+            .core
+            .create_post(new_post)
+            .await
+            .context("Error creating post")?
+            .ok_or(ApiError::BadRequest)?;
 
         Ok(Json(new_post_id))
     } else {
@@ -285,14 +495,99 @@ async fn get_post_comments(
 async fn post_comment(
     State(server): State<Arc<BlazeBooruServer>>,
     auth: Option<Authorized>,
+    SecureClientIp(ip): SecureClientIp,
     Path(id): Path<i32>,
     Json(req): Json<vm::NewPostComment>,
 ) -> Result<Json<vm::Comment>, ApiError> {
+    match &auth {
+        // Commenting is not available to API keys
+        Some(auth) => auth.require_user()?,
+        None if !server.config.allow_anonymous_comments => return Err(ApiError::Unauthorized),
+        None => {}
+    }
+
+    server.comment_limiter.attempt(ip).map_err(ApiError::TooManyRequests)?;
+
     let comment = server
         .core
-        .create_post_comment(req, id, auth.map(|a| a.claims.user_id))
+        .create_post_comment(
+            req,
+            id,
+            auth.map(|a| a.claims.user_id),
+            server.config.comment_filter.as_ref(),
+        )
         .await
         .context("Error creating post comment")?;
 
     Ok(Json(comment))
 }
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn report_post(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+    Path(id): Path<i32>,
+    Json(req): Json<vm::NewPostReport>,
+) -> Result<(), ApiError> {
+    // Reporting is not available to API keys
+    auth.require_user()?;
+
+    let success = server
+        .core
+        .create_post_report(id, req, auth.claims.user_id)
+        .await
+        .context("Error reporting post")?;
+
+    if !success {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(())
+}
+
+/// Get the highest rating of posts shown by default,
+/// using the user's own setting if they have one.
+pub(super) async fn get_max_rating(
+    server: &BlazeBooruServer,
+    auth: Option<&Authorized>,
+) -> Result<vm::PostRating, ApiError> {
+    let user_max_rating = if let Some(auth) = auth {
+        server
+            .core
+            .get_user_settings(auth.claims.user_id)
+            .await
+            .context("Error getting user settings")?
+            .and_then(|s| s.max_rating)
+    } else {
+        None
+    };
+
+    Ok(user_max_rating.unwrap_or(server.config.default_max_rating))
+}
+
+/// Exclude the user's blacklisted tags from a search, unless it opts out.
+/// Blacklisted tags that are explicitly included in the search are not excluded.
+pub(super) async fn apply_blacklist(
+    server: &BlazeBooruServer,
+    auth: Option<&Authorized>,
+    search: &mut PostSearchQuery,
+) -> Result<(), ApiError> {
+    let Some(auth) = auth else {
+        return Ok(());
+    };
+
+    if search.no_blacklist {
+        return Ok(());
+    }
+
+    let blacklist = server
+        .core
+        .get_user_tag_blacklist(auth.claims.user_id)
+        .await
+        .context("Error getting user tag blacklist")?;
+
+    let tags = blacklist.tags.into_iter().filter(|t| !search.include_tags.contains(t));
+    search.exclude_tags.extend(tags);
+
+    Ok(())
+}
@@ -0,0 +1,76 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::extract::Path;
+use axum::extract::Query;
+use axum::extract::State;
+use axum::routing::{get, post};
+use axum::Json;
+use axum::Router;
+use serde::Deserialize;
+
+use blazebooru_models::view as vm;
+
+use crate::server::api::Authorized;
+use crate::server::ApiError;
+use crate::server::BlazeBooruServer;
+
+#[derive(Deserialize)]
+struct ReportQuery {
+    /// Defaults to open reports
+    status: Option<vm::ReportStatus>,
+}
+
+pub fn router() -> Router<Arc<BlazeBooruServer>> {
+    Router::new()
+        .route("/", get(get_post_reports))
+        .route("/{id}/{action}", post(resolve_post_report))
+}
+
+/// Get reports with the post they are about.
+/// Open reports form the moderation queue,
+/// while resolved reports record the decisions made.
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn get_post_reports(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+    Query(ReportQuery { status }): Query<ReportQuery>,
+) -> Result<Json<Vec<vm::PostReport>>, ApiError> {
+    // Moderation is not available to API keys
+    auth.require_user()?;
+
+    if !server.core.is_user_admin(auth.claims.user_id).await? {
+        return Err(ApiError::Forbidden);
+    }
+
+    let reports = server
+        .core
+        .get_post_reports(status.unwrap_or(vm::ReportStatus::Open))
+        .await
+        .context("Error getting post reports")?;
+
+    Ok(Json(reports))
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn resolve_post_report(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+    Path((id, action)): Path<(i32, vm::ReportAction)>,
+) -> Result<(), ApiError> {
+    // Moderation is not available to API keys
+    auth.require_user()?;
+
+    let result = server
+        .core
+        .resolve_post_report(id, action, auth.claims.user_id)
+        .await
+        .context("Error resolving post report")?
+        .ok_or(ApiError::NotFound)?;
+
+    // The banned user was logged out everywhere along with the ban.
+    // Other server instances are told about the revoked sessions by the database.
+    server.auth.revoke_sessions(result.sessions);
+
+    Ok(())
+}
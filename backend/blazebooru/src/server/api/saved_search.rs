@@ -0,0 +1,179 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::extract::Path;
+use axum::extract::Query;
+use axum::extract::State;
+use axum::routing::{get, post};
+use axum::Json;
+use axum::Router;
+use serde::Deserialize;
+
+use blazebooru_models::view as vm;
+
+use crate::server::api::post::get_max_rating;
+use crate::server::api::Authorized;
+use crate::server::ApiError;
+use crate::server::BlazeBooruServer;
+
+#[derive(Deserialize)]
+struct PaginatedQuery {
+    #[serde(default)]
+    #[serde(rename = "sid")]
+    start_id: i32,
+    limit: i32,
+}
+
+pub fn router() -> Router<Arc<BlazeBooruServer>> {
+    Router::new()
+        .route("/", get(get_saved_searches))
+        .route("/new", post(create_saved_search))
+        .route("/{id}", get(get_saved_search).delete(delete_saved_search))
+        .route("/{id}/update", post(update_saved_search))
+        .route("/{id}/posts", get(get_saved_search_posts))
+        .route("/{id}/seen", post(mark_saved_search_seen))
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn get_saved_search(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+    Path(id): Path<i32>,
+) -> Result<Json<vm::SavedSearch>, ApiError> {
+    // Saved searches are not available to API keys
+    auth.require_user()?;
+
+    let max_rating = get_max_rating(&server, Some(&auth)).await?;
+
+    let saved_search = server
+        .core
+        .get_saved_search(id, auth.claims.user_id, max_rating)
+        .await
+        .context("Error getting saved search")?;
+
+    Ok(Json(saved_search.ok_or(ApiError::NotFound)?))
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn get_saved_searches(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+) -> Result<Json<Vec<vm::SavedSearch>>, ApiError> {
+    // Saved searches are not available to API keys
+    auth.require_user()?;
+
+    let saved_searches = server
+        .core
+        .get_saved_searches(auth.claims.user_id)
+        .await
+        .context("Error getting saved searches")?;
+
+    Ok(Json(saved_searches))
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn get_saved_search_posts(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+    Path(id): Path<i32>,
+    Query(PaginatedQuery { start_id, limit }): Query<PaginatedQuery>,
+) -> Result<Json<Vec<vm::Post>>, ApiError> {
+    // Saved searches are not available to API keys
+    auth.require_user()?;
+
+    let max_rating = get_max_rating(&server, Some(&auth)).await?;
+
+    let posts = server
+        .core
+        .get_saved_search_posts(id, auth.claims.user_id, max_rating, start_id, limit)
+        .await
+        .context("Error getting saved search posts")?;
+
+    Ok(Json(posts.ok_or(ApiError::NotFound)?))
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn create_saved_search(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+    Json(req): Json<vm::NewSavedSearch>,
+) -> Result<Json<i32>, ApiError> {
+    // Saved searches are not available to API keys
+    auth.require_user()?;
+
+    let new_saved_search_id = server
+        .core
+        .create_saved_search(req, auth.claims.user_id)
+        .await
+        .context("Error creating saved search")?;
+
+    Ok(Json(new_saved_search_id))
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn update_saved_search(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+    Path(id): Path<i32>,
+    Json(req): Json<vm::UpdateSavedSearch>,
+) -> Result<(), ApiError> {
+    // Saved searches are not available to API keys
+    auth.require_user()?;
+
+    let success = server
+        .core
+        .update_saved_search(id, req, auth.claims.user_id)
+        .await
+        .context("Error updating saved search")?;
+
+    if !success {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(())
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn delete_saved_search(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+    Path(id): Path<i32>,
+) -> Result<(), ApiError> {
+    // Saved searches are not available to API keys
+    auth.require_user()?;
+
+    let success = server
+        .core
+        .delete_saved_search(id, auth.claims.user_id)
+        .await
+        .context("Error deleting saved search")?;
+
+    if !success {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(())
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn mark_saved_search_seen(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+    Path(id): Path<i32>,
+    Json(req): Json<vm::MarkSavedSearchSeen>,
+) -> Result<(), ApiError> {
+    // Saved searches are not available to API keys
+    auth.require_user()?;
+
+    let success = server
+        .core
+        .mark_saved_search_seen(id, req, auth.claims.user_id)
+        .await
+        .context("Error marking saved search as seen")?;
+
+    if !success {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(())
+}
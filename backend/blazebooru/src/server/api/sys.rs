@@ -3,13 +3,16 @@ use std::sync::Arc;
 use axum::extract::State;
 use axum::routing::get;
 use axum::{Json, Router};
+use jsonwebtoken::jwk::JwkSet;
 
 use blazebooru_models::view as vm;
 
 use crate::server::{ApiError, BlazeBooruServer};
 
 pub fn router() -> Router<Arc<BlazeBooruServer>> {
-    Router::new().route("/config", get(get_config))
+    Router::new()
+        .route("/config", get(get_config))
+        .route("/jwks", get(get_jwks))
 }
 
 #[axum::debug_handler(state = Arc<BlazeBooruServer>)]
@@ -18,7 +21,16 @@ async fn get_config(State(server): State<Arc<BlazeBooruServer>>) -> Result<Json<
         max_image_size: server.config.max_image_size,
         require_login: server.config.require_login,
         allow_registration: server.config.allow_registration,
+        require_invite_code: server.config.require_invite_code,
+        oidc_enabled: server.oidc.is_some(),
+        proxy_auth_enabled: server.config.proxy_auth.is_some(),
+        default_max_rating: server.config.default_max_rating,
     };
 
     Ok(Json(config))
 }
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn get_jwks(State(server): State<Arc<BlazeBooruServer>>) -> Result<Json<JwkSet>, ApiError> {
+    Ok(Json(server.auth.jwks().clone()))
+}
@@ -44,6 +44,8 @@ async fn update_tag(
     Path(id): Path<i32>,
     Json(req): Json<vm::UpdateTag>,
 ) -> Result<(), ApiError> {
+    auth.require_scope(vm::ApiKeyScope::EditTags)?;
+
     let success = server
         .core
         .update_tag(id, req, auth.claims.user_id)
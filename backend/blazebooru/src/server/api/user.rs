@@ -1,15 +1,19 @@
 use std::sync::Arc;
 
-use axum::extract::State;
-use axum::routing::{get, post};
+use anyhow::Context;
+use axum::extract::{Path, State};
+use axum::routing::{delete, get, post};
 use axum::{Json, Router};
 use axum_client_ip::SecureClientIp;
+use axum_extra::headers::UserAgent;
+use axum_extra::TypedHeader;
 use serde::Deserialize;
 
 use blazebooru_models::local as lm;
 use blazebooru_models::view as vm;
 
 use crate::auth::{AuthClaims, JwtClaims, SessionClaims};
+use crate::rate_limit::RateLimitKey;
 use crate::server::api::auth::LoginResponse;
 use crate::server::api::Authorized;
 use crate::server::{ApiError, BlazeBooruServer};
@@ -18,12 +22,33 @@ use crate::server::{ApiError, BlazeBooruServer};
 struct RegisterUserRequest {
     name: String,
     password: String,
+    invite_code: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TotpCodeRequest {
+    code: String,
 }
 
 pub fn router() -> Router<Arc<BlazeBooruServer>> {
     Router::new()
         .route("/profile", get(get_user_profile))
+        .route("/settings", get(get_user_settings).post(update_user_settings))
+        .route("/blacklist", get(get_user_tag_blacklist).post(set_user_tag_blacklist))
         .route("/register", post(register_user))
+        .route("/api-keys", get(get_user_api_keys))
+        .route("/api-keys/new", post(create_api_key))
+        .route("/api-keys/{id}", delete(delete_api_key))
+        .route("/invites", get(get_user_invite_codes))
+        .route("/invites/new", post(create_invite_code))
+        .route("/invites/{id}", delete(delete_invite_code))
+        .route("/totp", get(get_totp_status))
+        .route("/totp/enroll", post(enroll_totp))
+        .route("/totp/confirm", post(confirm_totp))
+        .route("/totp/disable", post(disable_totp))
+        .route("/sessions", get(get_user_sessions).delete(delete_user_sessions))
+        .route("/sessions/{session}", delete(delete_user_session))
+        .route("/{id}/unban", post(unban_user))
 }
 
 #[axum::debug_handler(state = Arc<BlazeBooruServer>)]
@@ -36,29 +61,130 @@ async fn get_user_profile(
     Ok(Json(user.ok_or(ApiError::NotFound)?))
 }
 
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn get_user_settings(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+) -> Result<Json<vm::UserSettings>, ApiError> {
+    auth.require_user()?;
+
+    let settings = server
+        .core
+        .get_user_settings(auth.claims.user_id)
+        .await
+        .context("Error getting user settings")?;
+
+    Ok(Json(settings.ok_or(ApiError::NotFound)?))
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn update_user_settings(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+    Json(req): Json<vm::UserSettings>,
+) -> Result<(), ApiError> {
+    // Changing settings is not available to API keys
+    auth.require_user()?;
+
+    server
+        .core
+        .update_user_settings(auth.claims.user_id, req)
+        .await
+        .context("Error updating user settings")?;
+
+    Ok(())
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn get_user_tag_blacklist(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+) -> Result<Json<vm::TagBlacklist>, ApiError> {
+    auth.require_user()?;
+
+    let blacklist = server
+        .core
+        .get_user_tag_blacklist(auth.claims.user_id)
+        .await
+        .context("Error getting user tag blacklist")?;
+
+    Ok(Json(blacklist))
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn set_user_tag_blacklist(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+    Json(req): Json<vm::TagBlacklist>,
+) -> Result<(), ApiError> {
+    // Changing settings is not available to API keys
+    auth.require_user()?;
+
+    server
+        .core
+        .set_user_tag_blacklist(auth.claims.user_id, req)
+        .await
+        .context("Error setting user tag blacklist")?;
+
+    Ok(())
+}
+
 #[axum::debug_handler(state = Arc<BlazeBooruServer>)]
 async fn register_user(
     State(server): State<Arc<BlazeBooruServer>>,
     SecureClientIp(ip): SecureClientIp,
+    user_agent: Option<TypedHeader<UserAgent>>,
     Json(req): Json<RegisterUserRequest>,
 ) -> Result<Json<LoginResponse>, ApiError> {
     if !server.config.allow_registration {
         return Err(ApiError::Forbidden);
     }
 
+    // Registrations always count towards the limit,
+    // to prevent mass creation of accounts.
+    server
+        .login_limiter
+        .attempt(&[RateLimitKey::Ip(ip)])
+        .map_err(ApiError::TooManyRequests)?;
+
+    // Invite codes are only used if they are required
+    let require_invite_code = server.config.require_invite_code;
+    let invite_code = req.invite_code.filter(|_| require_invite_code);
+
+    if let Some(invite_code) = &invite_code {
+        if !server.core.is_invite_code_valid(invite_code).await? {
+            return Err(ApiError::Forbidden);
+        }
+    }
+
+    let has_invite_code = invite_code.is_some();
+
     let user = lm::NewUser {
         name: req.name.into(),
         password: req.password.into(),
+        invite_code: invite_code.map(Into::into),
     };
 
-    let user_id = server.core.create_user(user).await?;
+    // The invite code may have been used up since it was checked.
+    // Without one, only the first user can register, which is decided when the user is created.
+    let user_id = server
+        .core
+        .create_user(user, require_invite_code)
+        .await?
+        .ok_or(if has_invite_code {
+            ApiError::BadRequest
+        } else {
+            ApiError::Forbidden
+        })?;
 
     let claims = AuthClaims { user_id };
 
+    let user_agent = user_agent.as_ref().map(|TypedHeader(ua)| ua.as_str());
+
     let lm::CreateRefreshTokenResult {
         token: refresh_token,
         session,
-    } = server.core.create_refresh_token(user_id, ip).await?;
+    } = server.core.create_refresh_token(user_id, ip, user_agent).await?;
     let claims = SessionClaims { session, claims };
 
     let claims = JwtClaims::short(claims);
@@ -71,3 +197,272 @@ async fn register_user(
         refresh_token,
     }))
 }
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn get_user_sessions(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+) -> Result<Json<Vec<vm::Session>>, ApiError> {
+    let sessions = server
+        .core
+        .get_user_sessions(auth.claims.user_id, auth.session()?)
+        .await
+        .context("Error getting user sessions")?;
+
+    Ok(Json(sessions))
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn delete_user_session(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+    Path(session): Path<i64>,
+) -> Result<(), ApiError> {
+    auth.require_user()?;
+
+    let success = server
+        .core
+        .invalidate_user_session(auth.claims.user_id, session)
+        .await
+        .context("Error invalidating user session")?;
+
+    if !success {
+        return Err(ApiError::NotFound);
+    }
+
+    server.auth.revoke_session(session);
+
+    Ok(())
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn unban_user(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+    Path(id): Path<i32>,
+) -> Result<(), ApiError> {
+    // Moderation is not available to API keys
+    auth.require_user()?;
+
+    let success = server
+        .core
+        .unban_user(id, auth.claims.user_id)
+        .await
+        .context("Error unbanning user")?;
+
+    if !success {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(())
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn delete_user_sessions(State(server): State<Arc<BlazeBooruServer>>, auth: Authorized) -> Result<(), ApiError> {
+    auth.require_user()?;
+
+    let sessions = server
+        .core
+        .logout_all(auth.claims.user_id)
+        .await
+        .context("Error invalidating user sessions")?;
+
+    server.auth.revoke_sessions(sessions);
+
+    Ok(())
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn get_user_api_keys(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+) -> Result<Json<Vec<vm::ApiKey>>, ApiError> {
+    auth.require_user()?;
+
+    let api_keys = server
+        .core
+        .get_user_api_keys(auth.claims.user_id)
+        .await
+        .context("Error getting user API keys")?;
+
+    Ok(Json(api_keys))
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn create_api_key(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+    Json(req): Json<vm::NewApiKey>,
+) -> Result<Json<vm::CreateApiKeyResult>, ApiError> {
+    // API keys can not be used to create more API keys
+    auth.require_user()?;
+
+    let result = server
+        .core
+        .create_api_key(req, auth.claims.user_id)
+        .await
+        .context("Error creating API key")?;
+
+    Ok(Json(result))
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn delete_api_key(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+    Path(id): Path<i32>,
+) -> Result<(), ApiError> {
+    auth.require_user()?;
+
+    let success = server
+        .core
+        .delete_api_key(id, auth.claims.user_id)
+        .await
+        .context("Error deleting API key")?;
+
+    if !success {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(())
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn get_user_invite_codes(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+) -> Result<Json<Vec<vm::InviteCode>>, ApiError> {
+    auth.require_user()?;
+
+    let invite_codes = server
+        .core
+        .get_user_invite_codes(auth.claims.user_id)
+        .await
+        .context("Error getting user invite codes")?;
+
+    Ok(Json(invite_codes))
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn create_invite_code(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+    Json(req): Json<vm::NewInviteCode>,
+) -> Result<Json<vm::InviteCode>, ApiError> {
+    auth.require_user()?;
+
+    // Only admins can create invite codes, unless regular users are allowed to
+    if !server.config.allow_user_invites && !server.core.is_user_admin(auth.claims.user_id).await? {
+        return Err(ApiError::Forbidden);
+    }
+
+    let invite_code = server
+        .core
+        .create_invite_code(req, auth.claims.user_id)
+        .await
+        .context("Error creating invite code")?;
+
+    Ok(Json(invite_code))
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn delete_invite_code(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+    Path(id): Path<i32>,
+) -> Result<(), ApiError> {
+    auth.require_user()?;
+
+    let success = server
+        .core
+        .delete_invite_code(id, auth.claims.user_id)
+        .await
+        .context("Error deleting invite code")?;
+
+    if !success {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(())
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn get_totp_status(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+) -> Result<Json<vm::TotpStatus>, ApiError> {
+    auth.require_user()?;
+
+    let status = server
+        .core
+        .get_totp_status(auth.claims.user_id)
+        .await
+        .context("Error getting TOTP status")?;
+
+    Ok(Json(status))
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn enroll_totp(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+) -> Result<Json<vm::TotpEnrollment>, ApiError> {
+    auth.require_user()?;
+
+    let enrollment = server
+        .core
+        .enroll_totp(auth.claims.user_id)
+        .await
+        .context("Error enrolling TOTP")?;
+
+    // TOTP is already enabled
+    Ok(Json(enrollment.ok_or(ApiError::BadRequest)?))
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn confirm_totp(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+    Json(req): Json<TotpCodeRequest>,
+) -> Result<Json<vm::TotpRecoveryCodes>, ApiError> {
+    auth.require_user()?;
+
+    let recovery_codes = server
+        .core
+        .confirm_totp(auth.claims.user_id, &req.code)
+        .await
+        .context("Error confirming TOTP")?;
+
+    Ok(Json(recovery_codes.ok_or(ApiError::BadRequest)?))
+}
+
+#[axum::debug_handler(state = Arc<BlazeBooruServer>)]
+async fn disable_totp(
+    State(server): State<Arc<BlazeBooruServer>>,
+    auth: Authorized,
+    Json(req): Json<TotpCodeRequest>,
+) -> Result<(), ApiError> {
+    auth.require_user()?;
+
+    // Prevent a stolen session from being used to guess codes
+    let rate_limit_keys = [RateLimitKey::Totp(auth.claims.user_id)];
+
+    server
+        .login_limiter
+        .attempt(&rate_limit_keys)
+        .map_err(ApiError::TooManyRequests)?;
+
+    let success = server
+        .core
+        .disable_totp(auth.claims.user_id, &req.code)
+        .await
+        .context("Error disabling TOTP")?;
+
+    if !success {
+        return Err(ApiError::Unauthorized);
+    }
+
+    server.login_limiter.succeeded(&rate_limit_keys);
+
+    Ok(())
+}
@@ -1,25 +1,39 @@
 mod api;
 
-use std::{net::SocketAddr, sync::Arc};
+use std::{net::SocketAddr, sync::Arc, time::Duration};
 
-use axum::http::StatusCode;
+use axum::http::{header, StatusCode};
 use axum::response::IntoResponse;
 use axum::Router;
 use axum_client_ip::SecureClientIpSource;
+use chrono::{Duration as ChronoDuration, Utc};
 use futures::Future;
 use thiserror::Error;
 use tokio::net::TcpListener;
+use tokio::sync::broadcast;
 use tower_http::services::ServeDir;
-use tracing::{error, info};
+use tracing::{debug, error, info, warn};
 
 use blazebooru_core::{config::BlazeBooruConfig, BlazeBooruCore};
+use blazebooru_models::local as lm;
+use blazebooru_models::view as vm;
 
-use crate::auth::{AuthError, BlazeBooruAuth};
+use crate::auth::{access_token_lifetime, AuthError, BlazeBooruAuth};
+use crate::oidc::OidcClient;
+use crate::rate_limit::{CommentRateLimiter, LoginRateLimiter};
+
+const PRUNE_AUTH_DATA_INTERVAL: Duration = Duration::from_secs(60 * 60); // 1 hour
+const EVENT_RETRY_INTERVAL: Duration = Duration::from_secs(5);
 
 pub struct BlazeBooruServer {
     pub config: BlazeBooruConfig,
     pub auth: BlazeBooruAuth,
     pub core: BlazeBooruCore,
+    pub login_limiter: LoginRateLimiter,
+    pub comment_limiter: CommentRateLimiter,
+    /// Events received from the database, relayed to connected clients
+    pub events: broadcast::Sender<vm::Event>,
+    pub oidc: Option<OidcClient>,
     pub serve_files: bool,
 }
 
@@ -37,6 +51,8 @@ enum ApiError {
     Unauthorized,
     #[error("Forbidden")]
     Forbidden,
+    #[error("Too many requests")]
+    TooManyRequests(ChronoDuration),
 }
 
 impl BlazeBooruServer {
@@ -45,6 +61,11 @@ impl BlazeBooruServer {
 
         let server = Arc::new(self);
 
+        // Periodically prune refresh tokens, session revocations, login attempts and comment counts that are no longer needed
+        tokio::spawn(prune_auth_data(server.clone()));
+
+        tokio::spawn(relay_events(server.clone()));
+
         let mut app = Router::new().nest("/api", api);
 
         // If file serving is enabled, serve public files under /f.
@@ -64,12 +85,85 @@ impl BlazeBooruServer {
 
         info!("Web server listening on: {addr}");
         let listener = TcpListener::bind(&addr).await?;
-        axum::serve(listener, app).with_graceful_shutdown(shutdown).await?;
+        // The peer address is needed to check whether requests come from a trusted proxy
+        axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+            .with_graceful_shutdown(shutdown)
+            .await?;
 
         Ok(())
     }
 }
 
+async fn prune_auth_data(server: Arc<BlazeBooruServer>) {
+    let mut interval = tokio::time::interval(PRUNE_AUTH_DATA_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        match server.core.prune_refresh_tokens().await {
+            Ok(count) => debug!("Pruned {count} refresh tokens."),
+            Err(err) => error!("Error pruning refresh tokens: {err:#}"),
+        }
+
+        // Revocations only need to be remembered until
+        // the last access token of the session has expired.
+        let before = Utc::now() - access_token_lifetime();
+
+        server.auth.prune_revoked_sessions(before);
+
+        match server.core.prune_revoked_sessions(before).await {
+            Ok(count) => debug!("Pruned {count} revoked sessions."),
+            Err(err) => error!("Error pruning revoked sessions: {err:#}"),
+        }
+
+        server.login_limiter.prune();
+        server.comment_limiter.prune();
+    }
+}
+
+/// Relay events from the database to connected clients,
+/// and apply sessions revoked by other server instances,
+/// reconnecting if the connection to the database is lost.
+async fn relay_events(server: Arc<BlazeBooruServer>) {
+    loop {
+        match server.core.listen_events().await {
+            Ok(mut listener) => {
+                // Catch up on sessions revoked while the listener was not connected
+                match server
+                    .core
+                    .get_revoked_sessions(Utc::now() - access_token_lifetime())
+                    .await
+                {
+                    Ok(sessions) => server.auth.revoke_sessions(sessions),
+                    Err(err) => error!("Error getting revoked sessions: {err:#}"),
+                }
+
+                loop {
+                    match listener.recv().await {
+                        Ok(Some(lm::Event::Client(event))) => {
+                            // Sending only fails if no clients are connected
+                            let _ = server.events.send(event);
+                        }
+                        Ok(Some(lm::Event::SessionRevoked { session })) => server.auth.revoke_session(session),
+                        Ok(None) => {
+                            // Listen again on a new connection, catching up on missed revocations
+                            warn!("Event listener lost its connection to the database");
+                            break;
+                        }
+                        Err(err) => {
+                            error!("Error receiving event: {err:#}");
+                            break;
+                        }
+                    }
+                }
+            }
+            Err(err) => error!("Error listening for events: {err:#}"),
+        }
+
+        tokio::time::sleep(EVENT_RETRY_INTERVAL).await;
+    }
+}
+
 impl IntoResponse for ApiError {
     fn into_response(self) -> axum::response::Response {
         match self {
@@ -77,12 +171,24 @@ impl IntoResponse for ApiError {
                 error!("{err:#}");
                 (StatusCode::INTERNAL_SERVER_ERROR, format!("{err:#}")).into_response()
             }
-            Self::AuthError(AuthError::ExpiredToken) => (StatusCode::UNAUTHORIZED, ()).into_response(),
+            Self::AuthError(AuthError::ExpiredToken | AuthError::RevokedToken) => {
+                (StatusCode::UNAUTHORIZED, ()).into_response()
+            }
             Self::AuthError(err) => (StatusCode::BAD_REQUEST, format!("{err:#}")).into_response(),
             Self::BadRequest => (StatusCode::BAD_REQUEST, ()).into_response(),
             Self::NotFound => (StatusCode::NOT_FOUND, ()).into_response(),
             Self::Unauthorized => (StatusCode::UNAUTHORIZED, ()).into_response(),
             Self::Forbidden => (StatusCode::FORBIDDEN, ()).into_response(),
+            Self::TooManyRequests(retry_after) => {
+                // Round up, so that retrying after the specified time always succeeds
+                let seconds = (retry_after.num_milliseconds() + 999) / 1000;
+
+                (
+                    StatusCode::TOO_MANY_REQUESTS,
+                    [(header::RETRY_AFTER, seconds.to_string())],
+                )
+                    .into_response()
+            }
         }
     }
 }
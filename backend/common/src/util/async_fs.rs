@@ -22,6 +22,14 @@ pub async fn move_file(from: impl AsRef<Path>, to: impl AsRef<Path>) -> io::Resu
     Ok(())
 }
 
+/// Remove the file, ignoring it if it does not exist.
+pub async fn remove_file_if_exists(path: impl AsRef<Path>) -> io::Result<()> {
+    match fs::remove_file(path).await {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
 /// If possible hard-link the file, otherwise fall back to copying it.
 pub async fn hard_link_or_copy(from: impl AsRef<Path>, to: impl AsRef<Path>) -> io::Result<()> {
     if fs::hard_link(&from, &to).await.is_err() {
@@ -0,0 +1,87 @@
+use anyhow::anyhow;
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+
+use blazebooru_models::local as lm;
+use blazebooru_models::view as vm;
+use blazebooru_store::models as dbm;
+use blazebooru_store::transform::dbm_api_key_scopes_from_vm;
+
+use super::BlazeBooruCore;
+
+/// Prefix of all API keys, used to tell them apart from access tokens
+pub const API_KEY_PREFIX: &str = "bb_";
+
+/// Number of random bytes in an API key
+const API_KEY_SIZE: usize = 32;
+
+/// Number of characters of the key (after the prefix) stored in plain text,
+/// so that users can tell their keys apart.
+const API_KEY_VISIBLE_CHARS: usize = 6;
+
+impl BlazeBooruCore {
+    pub async fn create_api_key(
+        &self,
+        request: vm::NewApiKey,
+        user_id: i32,
+    ) -> Result<vm::CreateApiKeyResult, anyhow::Error> {
+        if request.name.is_empty() {
+            return Err(anyhow!("API key name can not be blank"));
+        }
+
+        let key = generate_api_key();
+
+        let api_key = dbm::NewApiKey {
+            name: Some(request.name),
+            key_hash: Some(hash_api_key(&key)),
+            key_prefix: Some(key[..API_KEY_PREFIX.len() + API_KEY_VISIBLE_CHARS].to_string()),
+            scopes: dbm_api_key_scopes_from_vm(&request.scopes),
+        };
+
+        let api_key = self.store.create_api_key(&api_key, user_id).await?;
+
+        Ok(vm::CreateApiKeyResult {
+            api_key: vm::ApiKey::from(api_key),
+            key,
+        })
+    }
+
+    pub async fn delete_api_key(&self, id: i32, user_id: i32) -> Result<bool, anyhow::Error> {
+        let success = self.store.delete_api_key(id, user_id).await?;
+
+        Ok(success)
+    }
+
+    pub async fn get_user_api_keys(&self, user_id: i32) -> Result<Vec<vm::ApiKey>, anyhow::Error> {
+        let api_keys = self
+            .store
+            .get_user_api_keys(user_id)
+            .await?
+            .into_iter()
+            .map(vm::ApiKey::from)
+            .collect();
+
+        Ok(api_keys)
+    }
+
+    /// Look up an API key, recording that it was used.
+    /// Returns None if the key does not exist or has been deleted.
+    pub async fn authenticate_api_key(&self, key: &str) -> Result<Option<lm::ApiKey>, anyhow::Error> {
+        let api_key = self.store.use_api_key(&hash_api_key(key)).await?;
+
+        Ok(api_key.map(lm::ApiKey::from))
+    }
+}
+
+fn generate_api_key() -> String {
+    let mut bytes = [0u8; API_KEY_SIZE];
+    OsRng.fill_bytes(&mut bytes);
+
+    let key: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+
+    format!("{API_KEY_PREFIX}{key}")
+}
+
+/// API keys are long and random, so a fast hash is sufficient.
+fn hash_api_key(key: &str) -> String {
+    blake3::hash(key.as_bytes()).to_hex().to_string()
+}
@@ -0,0 +1,25 @@
+use blazebooru_models::view as vm;
+use blazebooru_store::models as dbm;
+
+use super::BlazeBooruCore;
+
+impl BlazeBooruCore {
+    /// Get the audit log entries matching a filter, newest first.
+    pub async fn get_audit_log(
+        &self,
+        filter: vm::AuditLogFilter,
+        start_id: Option<i32>,
+        limit: i32,
+    ) -> Result<Vec<vm::AuditLogEntry>, anyhow::Error> {
+        let filter = dbm::AuditLogFilter::from(filter);
+        let entries = self
+            .store
+            .get_audit_log(&filter, start_id.unwrap_or(i32::MAX), limit)
+            .await?
+            .into_iter()
+            .map(vm::AuditLogEntry::from)
+            .collect();
+
+        Ok(entries)
+    }
+}
@@ -2,16 +2,27 @@ use std::net::IpAddr;
 
 use anyhow::anyhow;
 use argon2::{Argon2, PasswordHash, PasswordVerifier};
+use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
 use blazebooru_models::local as lm;
+use blazebooru_models::view as vm;
 
 use super::BlazeBooruCore;
 
 impl BlazeBooruCore {
     pub async fn login(&self, user_name: &str, password: &str) -> Result<Option<lm::User>, anyhow::Error> {
         if let Some(user) = self.store.get_user_by_name(user_name).await? {
-            let password_hash = PasswordHash::new(&user.password_hash).map_err(|err| anyhow!("{err}"))?;
+            if user.is_banned {
+                return Ok(None);
+            }
+
+            // Users without a password can only log in through OpenID Connect
+            let Some(password_hash) = &user.password_hash else {
+                return Ok(None);
+            };
+
+            let password_hash = PasswordHash::new(password_hash).map_err(|err| anyhow!("{err}"))?;
 
             let argon2 = Argon2::default();
             if argon2.verify_password(password.as_bytes(), &password_hash).is_ok() {
@@ -28,13 +39,60 @@ impl BlazeBooruCore {
         self.store.invalidate_session(session).await
     }
 
+    /// Log out all sessions belonging to a user.
+    /// Returns the sessions that were invalidated.
+    pub async fn logout_all(&self, user_id: i32) -> Result<Vec<i64>, anyhow::Error> {
+        self.store.invalidate_user_sessions(user_id).await
+    }
+
+    /// Get sessions that have been invalidated since the specified time.
+    pub async fn get_revoked_sessions(&self, since: DateTime<Utc>) -> Result<Vec<i64>, anyhow::Error> {
+        self.store.get_revoked_sessions(since).await
+    }
+
+    /// Forget session revocations older than the specified time.
+    pub async fn prune_revoked_sessions(&self, before: DateTime<Utc>) -> Result<u64, anyhow::Error> {
+        self.store.prune_revoked_sessions(before).await
+    }
+
+    pub async fn get_user_sessions(
+        &self,
+        user_id: i32,
+        current_session: i64,
+    ) -> Result<Vec<vm::Session>, anyhow::Error> {
+        let sessions = self
+            .store
+            .get_user_sessions(user_id)
+            .await?
+            .into_iter()
+            .map(|s| vm::Session {
+                is_current: s.session == Some(current_session),
+                ..vm::Session::from(s)
+            })
+            .collect();
+
+        Ok(sessions)
+    }
+
+    /// Invalidate one of a user's sessions.
+    /// Returns false if the session does not exist or belongs to another user.
+    pub async fn invalidate_user_session(&self, user_id: i32, session: i64) -> Result<bool, anyhow::Error> {
+        self.store.invalidate_user_session(user_id, session).await
+    }
+
+    /// Delete refresh tokens that can no longer be used.
+    pub async fn prune_refresh_tokens(&self) -> Result<i32, anyhow::Error> {
+        self.store.prune_refresh_tokens().await
+    }
+
     pub async fn create_refresh_token(
         &self,
         user_id: i32,
         ip: IpAddr,
+        user_agent: Option<&str>,
     ) -> Result<lm::CreateRefreshTokenResult, anyhow::Error> {
         Ok(lm::CreateRefreshTokenResult::from(
-            self.store.create_refresh_token(user_id, ip).await?,
+            self.store.create_refresh_token(user_id, ip, user_agent).await?,
         ))
     }
 
@@ -42,16 +100,17 @@ impl BlazeBooruCore {
         &self,
         token: Uuid,
         ip: IpAddr,
+        user_agent: Option<&str>,
     ) -> Result<Option<lm::RefreshRefreshTokenResult>, anyhow::Error> {
-        let r = self.store.refresh_refresh_token(token, ip).await?;
-        if let (Some(token), Some(session), Some(user_id)) = (r.token, r.session, r.user_id) {
-            Ok(Some(lm::RefreshRefreshTokenResult {
+        let r = self.store.refresh_refresh_token(token, ip, user_agent).await?;
+        match (r.token, r.session, r.user_id) {
+            (Some(token), Some(session), Some(user_id)) => Ok(Some(lm::RefreshRefreshTokenResult::Refreshed {
                 token,
                 session,
                 user_id,
-            }))
-        } else {
-            Ok(None)
+            })),
+            (None, Some(session), _) => Ok(Some(lm::RefreshRefreshTokenResult::Reused { session })),
+            _ => Ok(None),
         }
     }
 }
@@ -1,25 +1,55 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::anyhow;
+use chrono::{Duration, Utc};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
 use blazebooru_models::view as vm;
 use blazebooru_store::models as dbm;
 
+use crate::config::CommentFilterConfig;
+
 use super::BlazeBooruCore;
 
+/// Matches @username mentions that are not part of a word, such as an email address
+static RE_MENTION: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?:^|\W)@(\w+)").unwrap());
+
+static RE_LINK: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)\b(?:https?://|www\.)").unwrap());
+
 impl BlazeBooruCore {
+    /// Create a comment on a post, optionally as a reply to another comment on the same post.
+    /// Mentioned users, the author of the comment replied to and the uploader are notified.
+    /// Mentions of users that do not exist are left as text.
+    /// Comments matching the filter are held until approved by an admin.
     pub async fn create_post_comment(
         &self,
         comment: vm::NewPostComment,
         post_id: i32,
         user_id: Option<i32>,
+        filter: Option<&CommentFilterConfig>,
     ) -> Result<vm::Comment, anyhow::Error> {
+        if comment.comment.trim().is_empty() {
+            return Err(anyhow!("Comment cannot be empty"));
+        }
+
+        let is_held = filter.is_some_and(|f| is_comment_filtered(&comment.comment, user_id.is_none(), f));
+        let mentions = parse_mentions(&comment.comment);
+
         let comment = dbm::NewPostComment {
             post_id,
             comment: comment.comment,
+            reply_to_id: comment.reply_to_id,
+            is_held,
         };
 
-        let comment = self.store.create_post_comment(comment, user_id).await?;
+        let comment = self.store.create_post_comment(comment, user_id, &mentions).await?;
 
         Ok(vm::Comment::from(comment))
     }
 
+    /// Get the comments on a post in thread order,
+    /// with each reply following the comment it is a reply to.
     pub async fn get_post_comments(&self, post_id: i32) -> Result<Vec<vm::Comment>, anyhow::Error> {
         let comments = self
             .store
@@ -29,6 +59,214 @@ impl BlazeBooruCore {
             .map(vm::Comment::from)
             .collect();
 
+        Ok(thread_comments(comments))
+    }
+
+    /// Get the comments that are held for approval, oldest first.
+    pub async fn get_held_comments(&self) -> Result<Vec<vm::Comment>, anyhow::Error> {
+        let comments = self
+            .store
+            .get_held_comments()
+            .await?
+            .into_iter()
+            .map(vm::Comment::from)
+            .collect();
+
         Ok(comments)
     }
+
+    /// Approve a held comment, making it visible and notifying users of it.
+    /// Only admins can approve comments.
+    pub async fn approve_comment(&self, id: i32, user_id: i32) -> Result<bool, anyhow::Error> {
+        let success = self.store.approve_comment(id, user_id).await?;
+
+        Ok(success)
+    }
+
+    /// Edit a comment.
+    /// Users can only edit their own comments, within the edit window after posting them.
+    /// Newly mentioned users are notified.
+    /// Comments that match the filter after being edited are held until approved by an admin.
+    pub async fn update_comment(
+        &self,
+        id: i32,
+        request: vm::UpdateComment,
+        user_id: i32,
+        edit_window: Duration,
+        filter: Option<&CommentFilterConfig>,
+    ) -> Result<bool, anyhow::Error> {
+        if request.comment.trim().is_empty() {
+            return Err(anyhow!("Comment cannot be empty"));
+        }
+
+        // Only registered users can edit comments
+        let is_held = filter.is_some_and(|f| is_comment_filtered(&request.comment, false, f));
+        let mentions = parse_mentions(&request.comment);
+
+        let created_after = Utc::now() - edit_window;
+        let success = self
+            .store
+            .update_comment(id, &request.comment, user_id, created_after, &mentions, is_held)
+            .await?;
+
+        Ok(success)
+    }
+
+    /// Delete a comment, leaving a placeholder in its place.
+    /// Users can only delete their own comments, within the edit window after posting them.
+    /// Admins can delete any comment.
+    pub async fn delete_comment(&self, id: i32, user_id: i32, edit_window: Duration) -> Result<bool, anyhow::Error> {
+        let created_after = Utc::now() - edit_window;
+        let success = self.store.delete_comment(id, user_id, created_after).await?;
+
+        Ok(success)
+    }
+}
+
+/// Get the distinct user names mentioned in a comment.
+fn parse_mentions(comment: &str) -> Vec<String> {
+    let mut mentions: Vec<String> = Vec::new();
+    for captures in RE_MENTION.captures_iter(comment) {
+        let name = &captures[1];
+        if !mentions.iter().any(|m| m == name) {
+            mentions.push(name.to_string());
+        }
+    }
+
+    mentions
+}
+
+/// Check whether a comment should be held for approval.
+fn is_comment_filtered(comment: &str, is_anonymous: bool, filter: &CommentFilterConfig) -> bool {
+    if is_anonymous && filter.hold_anonymous {
+        return true;
+    }
+
+    if let Some(max_links) = filter.max_links {
+        if RE_LINK.find_iter(comment).count() > max_links {
+            return true;
+        }
+    }
+
+    filter.words.as_ref().is_some_and(|re| re.is_match(comment))
+}
+
+/// Order comments depth-first, keeping sibling comments in the order they were posted.
+fn thread_comments(comments: Vec<vm::Comment>) -> Vec<vm::Comment> {
+    let ids: HashSet<i32> = comments.iter().map(|c| c.id).collect();
+
+    let mut replies: HashMap<Option<i32>, Vec<vm::Comment>> = HashMap::new();
+    for comment in comments {
+        let reply_to_id = comment.reply_to_id.filter(|id| ids.contains(id));
+        replies.entry(reply_to_id).or_default().push(comment);
+    }
+
+    let mut threaded = Vec::with_capacity(ids.len());
+
+    // Comments are pushed in reverse, so that the oldest one is popped first
+    let mut stack: Vec<vm::Comment> = replies.remove(&None).unwrap_or_default().into_iter().rev().collect();
+    while let Some(comment) = stack.pop() {
+        if let Some(children) = replies.remove(&Some(comment.id)) {
+            stack.extend(children.into_iter().rev());
+        }
+
+        threaded.push(comment);
+    }
+
+    threaded
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+
+    fn filter(config: &str) -> CommentFilterConfig {
+        toml::from_str(config).unwrap()
+    }
+
+    #[test]
+    fn filter_matches_whole_words_regardless_of_case() {
+        let filter = filter("words = ['spam', ' ']");
+
+        assert!(is_comment_filtered("Buy SPAM now", false, &filter));
+        assert!(is_comment_filtered("spam", false, &filter));
+        assert!(!is_comment_filtered("spammer", false, &filter));
+        assert!(!is_comment_filtered("Nice picture", false, &filter));
+    }
+
+    #[test]
+    fn filter_matches_words_with_non_word_characters() {
+        let filter = filter("words = ['c++', '$$$']");
+
+        assert!(is_comment_filtered("I love c++!", false, &filter));
+        assert!(is_comment_filtered("$$$ fast", false, &filter));
+        assert!(!is_comment_filtered("c", false, &filter));
+    }
+
+    #[test]
+    fn filter_holds_comments_with_too_many_links() {
+        let filter = filter("max-links = 1");
+
+        assert!(!is_comment_filtered("See https://example.com", false, &filter));
+        assert!(is_comment_filtered(
+            "https://a.example and www.b.example",
+            false,
+            &filter
+        ));
+    }
+
+    #[test]
+    fn filter_holds_anonymous_comments() {
+        let filter = filter("hold-anonymous = true");
+
+        assert!(is_comment_filtered("Hello", true, &filter));
+        assert!(!is_comment_filtered("Hello", false, &filter));
+    }
+
+    fn comment(id: i32, reply_to_id: Option<i32>) -> vm::Comment {
+        vm::Comment {
+            id,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            user_id: None,
+            user_name: None,
+            post_id: 1,
+            comment: String::new(),
+            is_edited: false,
+            is_deleted: false,
+            is_held: false,
+            reply_to_id,
+            depth: 0,
+        }
+    }
+
+    #[test]
+    fn mentions_are_distinct_and_not_part_of_words() {
+        let mentions = parse_mentions("@alice and @bob_2, but not bob@example.com. Thanks @alice!");
+
+        assert_eq!(mentions, ["alice", "bob_2"]);
+    }
+
+    #[test]
+    fn replies_follow_the_comment_they_reply_to() {
+        let comments = vec![
+            comment(1, None),
+            comment(2, None),
+            comment(3, Some(1)),
+            comment(4, Some(3)),
+        ];
+
+        let ids: Vec<_> = thread_comments(comments).iter().map(|c| c.id).collect();
+        assert_eq!(ids, [1, 3, 4, 2]);
+    }
+
+    #[test]
+    fn replies_to_missing_comments_are_top_level() {
+        let comments = vec![comment(2, Some(1)), comment(3, None)];
+
+        let ids: Vec<_> = thread_comments(comments).iter().map(|c| c.id).collect();
+        assert_eq!(ids, [2, 3]);
+    }
 }
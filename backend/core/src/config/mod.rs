@@ -1,14 +1,18 @@
 use std::env;
 use std::fs;
 use std::io::Write;
+use std::net::IpAddr;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use anyhow::Context;
+use regex::Regex;
+use serde::{Deserialize as _, Deserializer};
 use serde_derive::Deserialize;
 use tracing::error;
 
 use blazebooru_common::util;
+use blazebooru_models::view as vm;
 
 pub const CONFIG_DIR_NAME: &str = "blazebooru";
 pub const CONFIG_FILENAME: &str = "config.toml";
@@ -18,6 +22,22 @@ pub const DEFAULT_CONFIG: &str = include_str!("default_config.toml");
 const DEFAULT_MAX_IMAGE_SIZE: usize = 10_000_000; // 10MB
 const DEFAULT_REQUIRE_LOGIN: bool = false;
 const DEFAULT_ALLOW_REGISTRATION: bool = true;
+const DEFAULT_REQUIRE_INVITE_CODE: bool = false;
+const DEFAULT_ALLOW_USER_INVITES: bool = false;
+const DEFAULT_MAX_RATING: vm::PostRating = vm::PostRating::Explicit;
+const DEFAULT_COMMENT_EDIT_SECONDS: u32 = 60 * 60; // 1 hour
+const DEFAULT_ALLOW_ANONYMOUS_COMMENTS: bool = true;
+const DEFAULT_COMMENT_RATE_LIMIT: u32 = 5;
+const DEFAULT_COMMENT_RATE_LIMIT_SECONDS: u32 = 60; // 1 minute
+const DEFAULT_OIDC_SCOPES: &str = "openid profile";
+const DEFAULT_OIDC_ALLOW_REGISTRATION: bool = true;
+const DEFAULT_PROXY_AUTH_HEADER: &str = "X-Remote-User";
+const DEFAULT_PROXY_AUTH_ALLOW_REGISTRATION: bool = true;
+const DEFAULT_LOGIN_FREE_ATTEMPTS: u32 = 5;
+const DEFAULT_LOGIN_BACKOFF_BASE_SECONDS: u32 = 1;
+const DEFAULT_LOGIN_BACKOFF_MAX_SECONDS: u32 = 5 * 60; // 5 minutes
+const DEFAULT_LOGIN_LOCKOUT_THRESHOLD: u32 = 20;
+const DEFAULT_LOGIN_LOCKOUT_SECONDS: u32 = 15 * 60; // 15 minutes
 
 // Workaround for serde not supporting specifying default values directly
 fn default_max_image_size() -> usize {
@@ -32,14 +52,88 @@ fn default_allow_registration() -> bool {
     DEFAULT_ALLOW_REGISTRATION
 }
 
-#[derive(Debug, Default, Deserialize)]
+fn default_require_invite_code() -> bool {
+    DEFAULT_REQUIRE_INVITE_CODE
+}
+
+fn default_allow_user_invites() -> bool {
+    DEFAULT_ALLOW_USER_INVITES
+}
+
+fn default_max_rating() -> vm::PostRating {
+    DEFAULT_MAX_RATING
+}
+
+fn default_comment_edit_seconds() -> u32 {
+    DEFAULT_COMMENT_EDIT_SECONDS
+}
+
+fn default_allow_anonymous_comments() -> bool {
+    DEFAULT_ALLOW_ANONYMOUS_COMMENTS
+}
+
+fn default_comment_rate_limit() -> u32 {
+    DEFAULT_COMMENT_RATE_LIMIT
+}
+
+fn default_comment_rate_limit_seconds() -> u32 {
+    DEFAULT_COMMENT_RATE_LIMIT_SECONDS
+}
+
+fn default_oidc_scopes() -> String {
+    DEFAULT_OIDC_SCOPES.to_string()
+}
+
+fn default_oidc_allow_registration() -> bool {
+    DEFAULT_OIDC_ALLOW_REGISTRATION
+}
+
+fn default_proxy_auth_header() -> String {
+    DEFAULT_PROXY_AUTH_HEADER.to_string()
+}
+
+fn default_proxy_auth_allow_registration() -> bool {
+    DEFAULT_PROXY_AUTH_ALLOW_REGISTRATION
+}
+
+fn default_login_free_attempts() -> u32 {
+    DEFAULT_LOGIN_FREE_ATTEMPTS
+}
+
+fn default_login_backoff_base_seconds() -> u32 {
+    DEFAULT_LOGIN_BACKOFF_BASE_SECONDS
+}
+
+fn default_login_backoff_max_seconds() -> u32 {
+    DEFAULT_LOGIN_BACKOFF_MAX_SECONDS
+}
+
+fn default_login_lockout_threshold() -> u32 {
+    DEFAULT_LOGIN_LOCKOUT_THRESHOLD
+}
+
+fn default_login_lockout_seconds() -> u32 {
+    DEFAULT_LOGIN_LOCKOUT_SECONDS
+}
+
+#[derive(Debug, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct BlazeBooruConfig {
     pub files_path: Option<PathBuf>,
     pub database_uri: Option<String>,
 
+    /// Public URL of the site, used for absolute links such as those in feeds.
+    /// Feeds are only available if it is specified.
+    pub public_url: Option<String>,
+
     pub jwt_secret: Option<String>,
 
+    /// Key pairs for signing access tokens.
+    /// The first key with a private key is used to sign new tokens,
+    /// while the rest are only used to verify tokens signed before a key rotation.
+    #[serde(default)]
+    pub jwt_keys: Vec<JwtKeyConfig>,
+
     #[serde(default = "default_max_image_size")]
     pub max_image_size: usize,
 
@@ -48,6 +142,158 @@ pub struct BlazeBooruConfig {
 
     #[serde(default = "default_allow_registration")]
     pub allow_registration: bool,
+
+    /// Only allow registration using an invite code.
+    /// Has no effect if registration is not allowed.
+    #[serde(default = "default_require_invite_code")]
+    pub require_invite_code: bool,
+
+    /// Allow regular users to create invite codes.
+    /// Admins can always create invite codes.
+    #[serde(default = "default_allow_user_invites")]
+    pub allow_user_invites: bool,
+
+    /// Highest rating of posts shown by default,
+    /// to anonymous users and users that have not chosen their own.
+    #[serde(default = "default_max_rating")]
+    pub default_max_rating: vm::PostRating,
+
+    /// How long after posting users can edit or delete their own comments.
+    /// Admins can always delete comments.
+    #[serde(default = "default_comment_edit_seconds")]
+    pub comment_edit_seconds: u32,
+
+    #[serde(default = "default_allow_anonymous_comments")]
+    pub allow_anonymous_comments: bool,
+
+    /// Number of comments that can be posted from an IP
+    /// within the rate limit period.
+    #[serde(default = "default_comment_rate_limit")]
+    pub comment_rate_limit: u32,
+
+    #[serde(default = "default_comment_rate_limit_seconds")]
+    pub comment_rate_limit_seconds: u32,
+
+    /// Hold comments for approval by an admin if they match the filter.
+    /// Disabled if not specified.
+    pub comment_filter: Option<CommentFilterConfig>,
+
+    /// Number of login or registration attempts allowed
+    /// before further attempts are delayed.
+    #[serde(default = "default_login_free_attempts")]
+    pub login_free_attempts: u32,
+
+    /// Delay after the free attempts are used up.
+    /// Doubles with each further attempt.
+    #[serde(default = "default_login_backoff_base_seconds")]
+    pub login_backoff_base_seconds: u32,
+
+    #[serde(default = "default_login_backoff_max_seconds")]
+    pub login_backoff_max_seconds: u32,
+
+    /// Number of failed logins after which a user name is locked out.
+    #[serde(default = "default_login_lockout_threshold")]
+    pub login_lockout_threshold: u32,
+
+    /// How long a user name stays locked out.
+    /// Attempts older than this are forgotten.
+    #[serde(default = "default_login_lockout_seconds")]
+    pub login_lockout_seconds: u32,
+
+    /// OpenID Connect login.
+    /// Disabled if not specified.
+    pub oidc: Option<OidcConfig>,
+
+    /// Authentication using a user name header set by a reverse proxy.
+    /// Disabled if not specified.
+    pub proxy_auth: Option<ProxyAuthConfig>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct JwtKeyConfig {
+    /// Key ID, included in the header of tokens signed with the key
+    pub kid: String,
+    /// EdDSA or RS256
+    pub algorithm: String,
+    pub public_key_path: PathBuf,
+    pub private_key_path: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CommentFilterConfig {
+    /// Words that cause a comment to be held, matched as whole words regardless of case.
+    /// Compiled into a single pattern when the config is loaded.
+    #[serde(default, deserialize_with = "deserialize_filter_words")]
+    pub words: Option<Regex>,
+
+    /// Number of links a comment can contain without being held
+    pub max_links: Option<usize>,
+
+    /// Hold all comments by anonymous users
+    #[serde(default)]
+    pub hold_anonymous: bool,
+}
+
+/// Compile the comment filter words into a single case-insensitive pattern.
+/// Words are matched when they are not part of a longer word. Word boundaries (`\b`) are not used,
+/// as they never match next to words starting or ending with non-word characters.
+fn deserialize_filter_words<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Regex>, D::Error> {
+    let words = Vec::<String>::deserialize(deserializer)?;
+
+    let words: Vec<_> = words
+        .iter()
+        .map(|w| w.trim())
+        .filter(|w| !w.is_empty())
+        .map(regex::escape)
+        .collect();
+
+    if words.is_empty() {
+        return Ok(None);
+    }
+
+    let re_words =
+        Regex::new(&format!(r"(?i)(?:^|\W)(?:{})(?:$|\W)", words.join("|"))).map_err(serde::de::Error::custom)?;
+
+    Ok(Some(re_words))
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct OidcConfig {
+    /// URL of the provider, used to discover its endpoints
+    pub issuer_url: String,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+
+    /// URL the provider redirects back to after authorization.
+    /// This should be a frontend page that completes the login.
+    pub redirect_url: String,
+
+    #[serde(default = "default_oidc_scopes")]
+    pub scopes: String,
+
+    /// Create users logging in through the provider for the first time.
+    /// Invite codes are not required for these.
+    #[serde(default = "default_oidc_allow_registration")]
+    pub allow_registration: bool,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ProxyAuthConfig {
+    /// Header containing the name of the authenticated user
+    #[serde(default = "default_proxy_auth_header")]
+    pub header: String,
+
+    /// Addresses of the proxies allowed to set the header.
+    /// The header is ignored on requests from anywhere else.
+    pub trusted_proxies: Vec<IpAddr>,
+
+    /// Create users that do not exist yet
+    #[serde(default = "default_proxy_auth_allow_registration")]
+    pub allow_registration: bool,
 }
 
 impl BlazeBooruConfig {
@@ -0,0 +1,73 @@
+use tracing::warn;
+
+use blazebooru_models::local as lm;
+
+use super::BlazeBooruCore;
+
+/// Receives events from the database, including those caused by other server instances.
+pub struct EventListener {
+    listener: blazebooru_store::EventListener,
+}
+
+impl BlazeBooruCore {
+    pub async fn listen_events(&self) -> Result<EventListener, anyhow::Error> {
+        let listener = self.store.listen_events().await?;
+
+        Ok(EventListener { listener })
+    }
+}
+
+impl EventListener {
+    /// Wait for the next event.
+    /// Events that cannot be parsed are skipped, so only connection errors are returned.
+    /// Returns None if the connection was lost, in which case events may have been missed.
+    pub async fn recv(&mut self) -> Result<Option<lm::Event>, anyhow::Error> {
+        loop {
+            let Some(payload) = self.listener.recv().await? else {
+                return Ok(None);
+            };
+
+            match parse_event(&payload) {
+                Ok(event) => return Ok(Some(event)),
+                Err(err) => warn!("Error parsing event {payload:?}: {err}"),
+            }
+        }
+    }
+}
+
+fn parse_event(payload: &str) -> Result<lm::Event, serde_json::Error> {
+    serde_json::from_str(payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use blazebooru_models::view as vm;
+
+    use super::*;
+
+    #[test]
+    fn client_events_are_parsed() {
+        let event = parse_event(r#"{"kind" : "comment", "post_id" : 1, "comment_id" : 2}"#).unwrap();
+
+        assert!(matches!(
+            event,
+            lm::Event::Client(vm::Event::Comment {
+                post_id: 1,
+                comment_id: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn session_revocations_are_parsed() {
+        let event = parse_event(r#"{"kind" : "session_revoked", "session" : 8000000000}"#).unwrap();
+
+        assert!(matches!(event, lm::Event::SessionRevoked { session: 8000000000 }));
+    }
+
+    #[test]
+    fn unknown_events_are_rejected() {
+        assert!(parse_event(r#"{"kind" : "unknown"}"#).is_err());
+        assert!(parse_event("not json").is_err());
+    }
+}
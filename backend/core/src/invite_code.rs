@@ -0,0 +1,61 @@
+use anyhow::anyhow;
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+
+use blazebooru_models::view as vm;
+use blazebooru_store::models as dbm;
+
+use super::BlazeBooruCore;
+
+/// Number of random bytes in an invite code
+const INVITE_CODE_SIZE: usize = 8;
+
+impl BlazeBooruCore {
+    pub async fn create_invite_code(
+        &self,
+        request: vm::NewInviteCode,
+        user_id: i32,
+    ) -> Result<vm::InviteCode, anyhow::Error> {
+        if request.max_uses < 1 {
+            return Err(anyhow!("Invite code must allow at least one use"));
+        }
+
+        let invite_code = dbm::NewInviteCode {
+            code: Some(generate_invite_code()),
+            max_uses: Some(request.max_uses),
+            expires_at: request.expires_at,
+        };
+
+        let invite_code = self.store.create_invite_code(&invite_code, user_id).await?;
+
+        Ok(vm::InviteCode::from(invite_code))
+    }
+
+    pub async fn delete_invite_code(&self, id: i32, user_id: i32) -> Result<bool, anyhow::Error> {
+        let success = self.store.delete_invite_code(id, user_id).await?;
+
+        Ok(success)
+    }
+
+    pub async fn get_user_invite_codes(&self, user_id: i32) -> Result<Vec<vm::InviteCode>, anyhow::Error> {
+        let invite_codes = self
+            .store
+            .get_user_invite_codes(user_id)
+            .await?
+            .into_iter()
+            .map(vm::InviteCode::from)
+            .collect();
+
+        Ok(invite_codes)
+    }
+
+    pub async fn is_invite_code_valid(&self, code: &str) -> Result<bool, anyhow::Error> {
+        Ok(self.store.is_invite_code_valid(code).await?)
+    }
+}
+
+fn generate_invite_code() -> String {
+    let mut bytes = [0u8; INVITE_CODE_SIZE];
+    OsRng.fill_bytes(&mut bytes);
+
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
@@ -6,12 +6,25 @@ use anyhow::Context;
 use blazebooru_store::PgStore;
 use config::BlazeBooruConfig;
 
+pub use api_key::API_KEY_PREFIX;
+pub use event::EventListener;
+
+mod api_key;
+mod audit_log;
 mod auth;
 mod comment;
 pub mod config;
+mod event;
 pub mod image;
+mod invite_code;
+mod notification;
+mod oidc;
+mod pool;
 mod post;
+mod report;
+mod saved_search;
 mod tag;
+mod totp;
 mod user;
 
 pub struct BlazeBooruCore {
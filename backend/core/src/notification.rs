@@ -0,0 +1,41 @@
+use blazebooru_models::view as vm;
+
+use super::BlazeBooruCore;
+
+impl BlazeBooruCore {
+    /// Get the notifications of a user, newest first.
+    pub async fn get_notifications(
+        &self,
+        user_id: i32,
+        start_id: Option<i32>,
+        limit: i32,
+    ) -> Result<Vec<vm::Notification>, anyhow::Error> {
+        let notifications = self
+            .store
+            .get_notifications(user_id, start_id.unwrap_or(i32::MAX), limit)
+            .await?
+            .into_iter()
+            .map(vm::Notification::from)
+            .collect();
+
+        Ok(notifications)
+    }
+
+    pub async fn get_unread_notification_count(&self, user_id: i32) -> Result<i64, anyhow::Error> {
+        let count = self.store.get_unread_notification_count(user_id).await?;
+
+        Ok(count)
+    }
+
+    pub async fn mark_notifications_read(
+        &self,
+        request: vm::MarkNotificationsRead,
+        user_id: i32,
+    ) -> Result<(), anyhow::Error> {
+        self.store
+            .mark_notifications_read(user_id, request.ids.as_deref())
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,103 @@
+use anyhow::anyhow;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use blazebooru_models::local as lm;
+use blazebooru_store::models as dbm;
+
+use super::BlazeBooruCore;
+
+static RE_INVALID_USERNAME_CHARS: Lazy<Regex> = Lazy::new(|| Regex::new(r"[^\d\w_]").unwrap());
+
+/// Name used for new users if the provider does not supply a usable one
+const DEFAULT_OIDC_USER_NAME: &str = "user";
+
+/// Maximum number of suffixes to try if the name of a new user is taken
+const MAX_OIDC_USER_NAME_ATTEMPTS: usize = 100;
+
+impl BlazeBooruCore {
+    /// Get the local user linked to an OpenID Connect identity,
+    /// creating one if there is none and registration is allowed.
+    /// Banned users are not returned.
+    pub async fn get_or_create_oidc_user(
+        &self,
+        identity: lm::OidcIdentity<'_>,
+        allow_registration: bool,
+    ) -> Result<Option<lm::User>, anyhow::Error> {
+        let base_name = oidc_base_user_name(identity.preferred_name.as_deref());
+
+        for attempt in 1..=MAX_OIDC_USER_NAME_ATTEMPTS {
+            // Checked on every attempt, as a concurrent login may have created the user
+            if let Some(user) = self.store.get_oidc_user(&identity.issuer, &identity.subject).await? {
+                if user.is_banned {
+                    return Ok(None);
+                }
+
+                return Ok(Some(lm::User::from(user)));
+            }
+
+            if !allow_registration {
+                return Ok(None);
+            }
+
+            let user = dbm::NewUser {
+                name: Some(oidc_user_name(&base_name, attempt)),
+                password_hash: None,
+                invite_code: None,
+            };
+
+            // The name is taken if the user could not be created
+            let user = self
+                .store
+                .create_oidc_user(&user, &identity.issuer, &identity.subject)
+                .await?;
+
+            if let Some(user) = user {
+                return Ok(Some(lm::User::from(user)));
+            }
+        }
+
+        Err(anyhow!("Could not find an available user name for {base_name}"))
+    }
+}
+
+/// Turn the name supplied by the provider into a valid user name.
+fn oidc_base_user_name(preferred_name: Option<&str>) -> String {
+    preferred_name
+        .map(|n| RE_INVALID_USERNAME_CHARS.replace_all(n, "_").into_owned())
+        .filter(|n| !n.is_empty())
+        .unwrap_or_else(|| DEFAULT_OIDC_USER_NAME.to_string())
+}
+
+/// Get the name to try when creating a user,
+/// adding a number to the name if earlier attempts found it taken.
+fn oidc_user_name(base_name: &str, attempt: usize) -> String {
+    if attempt == 1 {
+        base_name.to_string()
+    } else {
+        format!("{base_name}_{attempt}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_characters_are_replaced() {
+        assert_eq!(oidc_base_user_name(Some("alice.smith@example")), "alice_smith_example");
+        assert_eq!(oidc_base_user_name(Some("bob_2")), "bob_2");
+    }
+
+    #[test]
+    fn missing_names_use_the_default() {
+        assert_eq!(oidc_base_user_name(None), DEFAULT_OIDC_USER_NAME);
+        assert_eq!(oidc_base_user_name(Some("")), DEFAULT_OIDC_USER_NAME);
+    }
+
+    #[test]
+    fn later_attempts_are_numbered() {
+        assert_eq!(oidc_user_name("alice", 1), "alice");
+        assert_eq!(oidc_user_name("alice", 2), "alice_2");
+    }
+}
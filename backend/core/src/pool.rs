@@ -0,0 +1,97 @@
+use anyhow::anyhow;
+
+use blazebooru_models::export as em;
+use blazebooru_models::view as vm;
+use blazebooru_store::models as dbm;
+
+use super::BlazeBooruCore;
+
+impl BlazeBooruCore {
+    pub async fn get_view_pool(&self, id: i32) -> Result<Option<vm::Pool>, anyhow::Error> {
+        let pool = self.store.get_view_pool(id).await?.map(vm::Pool::from);
+
+        Ok(pool)
+    }
+
+    pub async fn get_view_pools(&self) -> Result<Vec<vm::Pool>, anyhow::Error> {
+        let pools = self
+            .store
+            .get_view_pools()
+            .await?
+            .into_iter()
+            .map(vm::Pool::from)
+            .collect();
+
+        Ok(pools)
+    }
+
+    pub async fn create_pool(&self, request: vm::NewPool, user_id: i32) -> Result<i32, anyhow::Error> {
+        if request.name.trim().is_empty() {
+            return Err(anyhow!("Pool name cannot be empty"));
+        }
+
+        let new_pool = dbm::NewPool {
+            user_id: Some(user_id),
+            name: Some(request.name),
+            description: request.description.filter(|v| !v.is_empty()),
+            post_ids: request.post_ids,
+            created_at: None,
+        };
+
+        let new_pool_id = self.store.create_pool(&new_pool).await?;
+
+        Ok(new_pool_id)
+    }
+
+    /// Import a pool, keeping its creation time.
+    /// The pool is owned by its original creator if a user with the same name exists,
+    /// or by the importing user otherwise.
+    pub async fn import_pool(&self, pool: em::Pool, post_ids: Vec<i32>, user_id: i32) -> Result<i32, anyhow::Error> {
+        let creator_id = self
+            .store
+            .get_user_by_name(&pool.user_name)
+            .await?
+            .map_or(user_id, |u| u.id);
+
+        let new_pool = dbm::NewPool {
+            user_id: Some(creator_id),
+            name: Some(pool.name),
+            description: pool.description.filter(|v| !v.is_empty()),
+            post_ids,
+            created_at: Some(pool.created_at),
+        };
+
+        let new_pool_id = self.store.create_pool(&new_pool).await?;
+
+        Ok(new_pool_id)
+    }
+
+    pub async fn update_pool(&self, id: i32, request: vm::UpdatePool, user_id: i32) -> Result<bool, anyhow::Error> {
+        if request.name.trim().is_empty() {
+            return Err(anyhow!("Pool name cannot be empty"));
+        }
+
+        let update_pool = dbm::UpdatePool::from(request);
+        let success = self.store.update_pool(id, &update_pool, user_id).await?;
+
+        Ok(success)
+    }
+
+    pub async fn delete_pool(&self, id: i32, user_id: i32) -> Result<bool, anyhow::Error> {
+        let success = self.store.delete_pool(id, user_id).await?;
+
+        Ok(success)
+    }
+
+    pub async fn get_export_pools(&self) -> Result<Vec<em::Pool>, anyhow::Error> {
+        let pools = self
+            .store
+            .get_export_pools()
+            .await?
+            .into_iter()
+            .map(em::Pool::from)
+            .collect();
+
+        Ok(pools)
+    }
+}
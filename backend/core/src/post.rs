@@ -1,11 +1,15 @@
 use std::path::Path;
 
+use anyhow::Context;
+
+use blazebooru_common::util;
+
 use blazebooru_models::export as em;
 use blazebooru_models::local as lm;
 use blazebooru_models::view as vm;
 use blazebooru_store::models as dbm;
 
-use blazebooru_store::transform::dbm_update_post_from_vm;
+use blazebooru_store::transform::{dbm_rating_from_vm, dbm_update_post_from_vm};
 
 use crate::image::ProcessFileResult;
 use crate::image::ProcessImageResult;
@@ -13,9 +17,21 @@ use crate::image::ProcessImageResult;
 use super::BlazeBooruCore;
 
 impl BlazeBooruCore {
-    pub async fn create_post(&self, post: lm::NewPost<'_>) -> Result<i32, anyhow::Error> {
+    /// Returns None if the parent post is not valid.
+    pub async fn create_post(&self, post: lm::NewPost<'_>) -> Result<Option<i32>, anyhow::Error> {
+        // Avoid processing the file if the post can not be created.
+        // The parent is checked again when the post is created.
+        if let Some(parent_id) = post.parent_id {
+            if !self.store.is_valid_post_parent(None, parent_id).await? {
+                return Ok(None);
+            }
+        }
+
         let size = post.file.size as i32;
 
+        // Keep the files from being purged until the post using them exists
+        let files_lock = self.store.lock_post_files(&post.file.hash).await?;
+
         // Process file
         let process_file_result = self
             .process_file(post.file, &post.filename, &self.public_original_path)
@@ -38,14 +54,21 @@ impl BlazeBooruCore {
             hash: Some(hash.to_string()),
             ext: Some(ext.as_ref().into()),
             tn_ext: Some(tn_ext.into()),
+            parent_id: post.parent_id,
+            rating: post.rating.map(dbm_rating_from_vm),
         };
 
         let new_post_id = self.store.create_post(&db_post, &post.tags).await?;
 
+        files_lock.release().await?;
+
         Ok(new_post_id)
     }
 
     pub async fn import_post(&self, post: em::Post, user_id: i32, file: Option<&Path>) -> Result<i32, anyhow::Error> {
+        // Keep the files from being purged until the post using them exists
+        let files_lock = self.store.lock_post_files(&post.hash).await?;
+
         if let Some(path) = file {
             let hashed_file = self.hash_file_to_temp_file(path).await?;
 
@@ -58,6 +81,12 @@ impl BlazeBooruCore {
             self.process_image(&process_file_result).await?;
         }
 
+        // Invalid ratings would violate the constraint on the rating column
+        let rating = post
+            .rating
+            .map(|r| parse_rating(&r).with_context(|| format!("Invalid rating: {r}")))
+            .transpose()?;
+
         let db_post = dbm::NewPost {
             user_id: Some(user_id),
             title: post.title,
@@ -70,34 +99,139 @@ impl BlazeBooruCore {
             hash: Some(post.hash),
             ext: Some(post.ext),
             tn_ext: Some(post.tn_ext),
+            parent_id: None,
+            rating: rating.map(dbm_rating_from_vm),
         };
 
         let tags: Vec<_> = post.tags.iter().map(|t| t.as_str()).collect();
 
-        let new_post_id = self.store.create_post(&db_post, &tags).await?;
+        let new_post_id = self
+            .store
+            .create_post(&db_post, &tags)
+            .await?
+            .context("Imported post has an invalid parent")?;
+
+        files_lock.release().await?;
 
         Ok(new_post_id)
     }
 
     pub async fn get_view_post(&self, id: i32) -> Result<Option<vm::Post>, anyhow::Error> {
-        let post = self.store.get_view_post(id).await?.map(vm::Post::from);
+        let Some(mut post) = self.store.get_view_post(id).await?.map(vm::Post::from) else {
+            return Ok(None);
+        };
+
+        post.child_ids = Some(self.store.get_post_child_ids(id).await?);
 
-        Ok(post)
+        let pools = self.store.get_post_pools(id).await?;
+        post.pools = Some(pools.into_iter().map(vm::PostPool::from).collect());
+
+        Ok(Some(post))
     }
 
-    pub async fn update_post(&self, id: i32, request: vm::UpdatePost, user_id: i32) -> Result<bool, anyhow::Error> {
+    pub async fn update_post(
+        &self,
+        id: i32,
+        request: vm::UpdatePost,
+        user_id: i32,
+    ) -> Result<lm::UpdatePostResult, anyhow::Error> {
         let update_post = dbm_update_post_from_vm(id, request);
-        let success = self.store.update_post(&update_post, user_id).await?;
+        let result = self.store.update_post(&update_post, user_id).await?;
+
+        Ok(update_post_result(result))
+    }
+
+    /// Get deleted posts, newest first.
+    pub async fn get_deleted_posts(&self, start_id: Option<i32>, limit: i32) -> Result<Vec<vm::Post>, anyhow::Error> {
+        let posts = self
+            .store
+            .get_deleted_posts(start_id.unwrap_or(i32::MAX), limit)
+            .await?
+            .into_iter()
+            .map(vm::Post::from)
+            .collect();
+
+        Ok(posts)
+    }
+
+    /// Restore a deleted post.
+    /// Only admins can undelete posts.
+    pub async fn undelete_post(&self, id: i32, user_id: i32) -> Result<bool, anyhow::Error> {
+        let success = self.store.undelete_post(id, user_id).await?;
 
         Ok(success)
     }
 
+    /// Permanently remove a deleted post, along with its files
+    /// if no other post uses them.
+    /// Only admins can purge posts.
+    pub async fn purge_post(&self, id: i32, user_id: i32) -> Result<bool, anyhow::Error> {
+        let Some(result) = self.store.purge_post(id, user_id).await? else {
+            return Ok(false);
+        };
+
+        let hash = result.hash.context("Purged post has no hash")?;
+        let ext = result.ext.context("Purged post has no extension")?;
+        let tn_ext = result.tn_ext.context("Purged post has no thumbnail extension")?;
+        let is_file_shared = result.is_file_shared.context("Purged post has no file usage")?;
+
+        if is_file_shared {
+            return Ok(true);
+        }
+
+        // A post using the files may have been created since the post was purged,
+        // so check again while no new post can be created with them.
+        let files_lock = self.store.lock_post_files(&hash).await?;
+
+        if !self.store.is_post_file_used(&hash).await? {
+            let original_path = self.public_original_path.join(format!("{hash}.{ext}"));
+            let thumbnail_path = self.public_thumbnail_path.join(format!("{hash}.{tn_ext}"));
+
+            util::async_fs::remove_file_if_exists(&original_path)
+                .await
+                .context("Error removing original file")?;
+            util::async_fs::remove_file_if_exists(&thumbnail_path)
+                .await
+                .context("Error removing thumbnail file")?;
+        }
+
+        files_lock.release().await?;
+
+        Ok(true)
+    }
+
     pub async fn delete_post(&self, id: i32, user_id: i32) -> Result<bool, anyhow::Error> {
         let success = self.store.delete_post(id, user_id).await?;
 
         Ok(success)
     }
 
+    pub async fn favorite_post(&self, id: i32, user_id: i32) -> Result<bool, anyhow::Error> {
+        let success = self.store.favorite_post(id, user_id).await?;
+
+        Ok(success)
+    }
+
+    pub async fn unfavorite_post(&self, id: i32, user_id: i32) -> Result<bool, anyhow::Error> {
+        let success = self.store.unfavorite_post(id, user_id).await?;
+
+        Ok(success)
+    }
+
+    /// Vote on a post, replacing any previous vote by the user.
+    /// The score must be either 1 or -1.
+    pub async fn vote_post(&self, id: i32, score: i32, user_id: i32) -> Result<bool, anyhow::Error> {
+        let success = self.store.vote_post(id, user_id, score).await?;
+
+        Ok(success)
+    }
+
+    pub async fn unvote_post(&self, id: i32, user_id: i32) -> Result<bool, anyhow::Error> {
+        let success = self.store.unvote_post(id, user_id).await?;
+
+        Ok(success)
+    }
+
     pub async fn get_export_posts(
         &self,
         include_tags: Vec<String>,
@@ -105,9 +239,11 @@ impl BlazeBooruCore {
         start_id: i32,
         limit: i32,
     ) -> Result<Vec<em::Post>, anyhow::Error> {
+        let search = parse_search(&include_tags, &exclude_tags);
+
         let posts = self
             .store
-            .get_view_posts(&include_tags, &exclude_tags, start_id, limit)
+            .get_view_posts(&search, start_id, limit)
             .await?
             .into_iter()
             .map(em::Post::from)
@@ -116,16 +252,22 @@ impl BlazeBooruCore {
         Ok(posts)
     }
 
+    /// Get posts matching a search.
+    /// Unless the search specifies ratings, only posts up to `max_rating` are included.
     pub async fn get_view_posts(
         &self,
         include_tags: Vec<String>,
         exclude_tags: Vec<String>,
+        max_rating: vm::PostRating,
         start_id: i32,
         limit: i32,
     ) -> Result<Vec<vm::Post>, anyhow::Error> {
+        let mut search = parse_search(&include_tags, &exclude_tags);
+        apply_default_rating(&mut search, max_rating);
+
         let posts = self
             .store
-            .get_view_posts(&include_tags, &exclude_tags, start_id, limit)
+            .get_view_posts(&search, start_id, limit)
             .await?
             .into_iter()
             .map(vm::Post::from)
@@ -138,15 +280,18 @@ impl BlazeBooruCore {
         &self,
         include_tags: Vec<&str>,
         exclude_tags: Vec<&str>,
+        max_rating: vm::PostRating,
         posts_per_page: i32,
         page_count: i32,
         origin_page: Option<vm::PageInfo>,
     ) -> Result<Vec<vm::PageInfo>, anyhow::Error> {
+        let mut search = parse_search(&include_tags, &exclude_tags);
+        apply_default_rating(&mut search, max_rating);
+
         let pages = self
             .store
             .calculate_pages(
-                &include_tags,
-                &exclude_tags,
+                &search,
                 posts_per_page,
                 page_count,
                 origin_page.map(dbm::PageInfo::from),
@@ -160,13 +305,330 @@ impl BlazeBooruCore {
         &self,
         include_tags: Vec<&str>,
         exclude_tags: Vec<&str>,
+        max_rating: vm::PostRating,
         posts_per_page: i32,
     ) -> Result<vm::PageInfo, anyhow::Error> {
-        let page = self
-            .store
-            .calculate_last_page(&include_tags, &exclude_tags, posts_per_page)
-            .await?;
+        let mut search = parse_search(&include_tags, &exclude_tags);
+        apply_default_rating(&mut search, max_rating);
+
+        let page = self.store.calculate_last_page(&search, posts_per_page).await?;
 
         Ok(vm::PageInfo::from(page))
     }
 }
+
+/// Separate metatags from the tags of a search.
+///
+/// Supported metatags:
+/// * `fav:<user name>` - Only include posts favorited by the user
+/// * `pool:<id>` - Only include posts in the pool
+/// * `parent:<id>` - Only include children of the post, `parent:any` and `parent:none` filter by having a parent
+/// * `child:<id>` - Only include the parent of the post, `child:any` and `child:none` filter by having children
+/// * `rating:<rating>` - Only include posts with the rating, or exclude them if negated.
+///   The rating can be `safe`, `questionable` or `explicit`, or their first letter.
+///   Only including ratings replaces the default rating filter, while excluding them narrows it.
+/// * `score:<n>` - Filter posts by score, where `n` can be prefixed by `>`, `>=`, `<` or `<=`
+/// * `order:favcount` - Sort posts by favorite count
+/// * `order:score` - Sort posts by score
+pub(crate) fn parse_search<'a, S: AsRef<str>>(include_tags: &'a [S], exclude_tags: &'a [S]) -> lm::PostSearch<'a> {
+    let mut search = lm::PostSearch {
+        include_tags: Vec::new(),
+        exclude_tags: Vec::new(),
+        fav_user_name: None,
+        min_score: None,
+        max_score: None,
+        pool_id: None,
+        parent: None,
+        child: None,
+        ratings: None,
+        exclude_ratings: Vec::new(),
+        after_id: None,
+        sort: lm::PostSort::Id,
+    };
+
+    for tag in exclude_tags.iter().map(|t| t.as_ref()) {
+        if let Some(rating) = tag.strip_prefix("rating:").and_then(parse_rating) {
+            search.exclude_ratings.push(rating);
+            continue;
+        }
+
+        search.exclude_tags.push(tag);
+    }
+
+    for tag in include_tags.iter().map(|t| t.as_ref()) {
+        if let Some(user_name) = tag.strip_prefix("fav:").filter(|n| !n.is_empty()) {
+            search.fav_user_name = Some(user_name);
+            continue;
+        }
+
+        if let Some(pool_id) = tag.strip_prefix("pool:").and_then(|v| v.parse().ok()) {
+            search.pool_id = Some(pool_id);
+            continue;
+        }
+
+        if let Some(parent) = tag.strip_prefix("parent:").and_then(parse_relation_filter) {
+            search.parent = Some(parent);
+            continue;
+        }
+
+        if let Some(child) = tag.strip_prefix("child:").and_then(parse_relation_filter) {
+            search.child = Some(child);
+            continue;
+        }
+
+        if let Some(rating) = tag.strip_prefix("rating:").and_then(parse_rating) {
+            search
+                .ratings
+                .get_or_insert_with(|| vm::PostRating::ALL.to_vec())
+                .retain(|r| *r == rating);
+            continue;
+        }
+
+        if let Some((min, max)) = tag.strip_prefix("score:").and_then(parse_score_range) {
+            search.min_score = search.min_score.max(min);
+            search.max_score = match (search.max_score, max) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+            continue;
+        }
+
+        match tag {
+            "order:id" => search.sort = lm::PostSort::Id,
+            "order:favcount" => search.sort = lm::PostSort::FavCount,
+            "order:score" => search.sort = lm::PostSort::Score,
+            _ => search.include_tags.push(tag),
+        }
+    }
+
+    search
+}
+
+/// Parse a score range, such as `>=5`.
+/// Returns the minimum and maximum score.
+fn parse_score_range(value: &str) -> Option<(Option<i32>, Option<i32>)> {
+    let range = if let Some(n) = value.strip_prefix(">=") {
+        (Some(n.parse().ok()?), None)
+    } else if let Some(n) = value.strip_prefix("<=") {
+        (None, Some(n.parse().ok()?))
+    } else if let Some(n) = value.strip_prefix('>') {
+        (Some(n.parse::<i32>().ok()?.checked_add(1)?), None)
+    } else if let Some(n) = value.strip_prefix('<') {
+        (None, Some(n.parse::<i32>().ok()?.checked_sub(1)?))
+    } else {
+        let n = value.parse().ok()?;
+        (Some(n), Some(n))
+    };
+
+    Some(range)
+}
+
+fn parse_relation_filter(value: &str) -> Option<lm::RelationFilter> {
+    match value {
+        "any" => Some(lm::RelationFilter::Any),
+        "none" => Some(lm::RelationFilter::None),
+        _ => value.parse().ok().map(lm::RelationFilter::Post),
+    }
+}
+
+fn parse_rating(value: &str) -> Option<vm::PostRating> {
+    match value {
+        "s" | "safe" => Some(vm::PostRating::Safe),
+        "q" | "questionable" => Some(vm::PostRating::Questionable),
+        "e" | "explicit" => Some(vm::PostRating::Explicit),
+        _ => None,
+    }
+}
+
+/// Only include posts up to the maximum rating, unless the search specifies ratings.
+pub(crate) fn apply_default_rating(search: &mut lm::PostSearch, max_rating: vm::PostRating) {
+    if search.ratings.is_none() && max_rating < vm::PostRating::Explicit {
+        let ratings = vm::PostRating::ALL.into_iter().filter(|r| *r <= max_rating).collect();
+        search.ratings = Some(ratings);
+    }
+}
+
+/// Interpret the result of updating a post in the database.
+/// The parent is only validated once the post is known to be editable by the user,
+/// so an invalid parent does not reveal whether the post exists.
+fn update_post_result(result: Option<bool>) -> lm::UpdatePostResult {
+    match result {
+        Some(true) => lm::UpdatePostResult::Updated,
+        Some(false) => lm::UpdatePostResult::NotFound,
+        None => lm::UpdatePostResult::InvalidParent,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use blazebooru_store::transform::dbm_search_options_from_lm;
+
+    use super::*;
+
+    #[test]
+    fn update_post_result_distinguishes_invalid_parents() {
+        assert_eq!(update_post_result(Some(true)), lm::UpdatePostResult::Updated);
+        assert_eq!(update_post_result(Some(false)), lm::UpdatePostResult::NotFound);
+        assert_eq!(update_post_result(None), lm::UpdatePostResult::InvalidParent);
+    }
+
+    #[test]
+    fn fav_metatag_filters_by_user() {
+        let search = parse_search(&["fav:alice", "cat"], &[]);
+        assert_eq!(search.fav_user_name, Some("alice"));
+        assert_eq!(search.include_tags, vec!["cat"]);
+    }
+
+    #[test]
+    fn negated_fav_metatag_is_a_plain_tag() {
+        let search = parse_search(&[], &["fav:alice"]);
+        assert_eq!(search.fav_user_name, None);
+        assert_eq!(search.exclude_tags, vec!["fav:alice"]);
+    }
+
+    #[test]
+    fn fav_metatag_without_user_is_a_plain_tag() {
+        let search = parse_search(&["fav:"], &[]);
+        assert_eq!(search.fav_user_name, None);
+        assert_eq!(search.include_tags, vec!["fav:"]);
+    }
+
+    #[test]
+    fn order_favcount_sorts_by_favorite_count() {
+        let search = parse_search(&["order:favcount"], &[]);
+        assert!(matches!(search.sort, lm::PostSort::FavCount));
+        assert!(search.include_tags.is_empty());
+    }
+
+    #[test]
+    fn score_metatag_parses_ranges() {
+        assert_eq!(parse_score_range("5"), Some((Some(5), Some(5))));
+        assert_eq!(parse_score_range(">=5"), Some((Some(5), None)));
+        assert_eq!(parse_score_range(">5"), Some((Some(6), None)));
+        assert_eq!(parse_score_range("<=-5"), Some((None, Some(-5))));
+        assert_eq!(parse_score_range("<-5"), Some((None, Some(-6))));
+    }
+
+    #[test]
+    fn score_metatags_are_combined() {
+        let search = parse_search(&["score:>=1", "score:<10", "score:>2"], &[]);
+        assert_eq!(search.min_score, Some(3));
+        assert_eq!(search.max_score, Some(9));
+        assert!(search.include_tags.is_empty());
+    }
+
+    #[test]
+    fn invalid_score_metatags_are_plain_tags() {
+        let search = parse_search(&["score:high", "score:>2147483647", "score:"], &["score:>1"]);
+        assert_eq!(search.min_score, None);
+        assert_eq!(search.max_score, None);
+        assert_eq!(search.include_tags, vec!["score:high", "score:>2147483647", "score:"]);
+        assert_eq!(search.exclude_tags, vec!["score:>1"]);
+    }
+
+    #[test]
+    fn order_score_sorts_by_score() {
+        let search = parse_search(&["order:score"], &[]);
+        assert!(matches!(search.sort, lm::PostSort::Score));
+        assert!(search.include_tags.is_empty());
+    }
+
+    #[test]
+    fn pool_metatag_filters_by_pool() {
+        let search = parse_search(&["pool:12"], &[]);
+        assert_eq!(search.pool_id, Some(12));
+        assert!(search.include_tags.is_empty());
+    }
+
+    #[test]
+    fn invalid_pool_metatags_are_plain_tags() {
+        let search = parse_search(&["pool:favorites", "pool:"], &["pool:12"]);
+        assert_eq!(search.pool_id, None);
+        assert_eq!(search.include_tags, vec!["pool:favorites", "pool:"]);
+        assert_eq!(search.exclude_tags, vec!["pool:12"]);
+    }
+
+    #[test]
+    fn parent_metatag_filters_by_parent() {
+        for (tag, filter) in [
+            ("parent:3", lm::RelationFilter::Post(3)),
+            ("parent:any", lm::RelationFilter::Any),
+            ("parent:none", lm::RelationFilter::None),
+        ] {
+            let tags = [tag];
+            let search = parse_search(&tags, &[]);
+            assert_eq!(search.parent, Some(filter));
+            assert!(search.include_tags.is_empty());
+        }
+    }
+
+    #[test]
+    fn child_metatag_filters_by_child() {
+        for (tag, filter) in [
+            ("child:3", lm::RelationFilter::Post(3)),
+            ("child:any", lm::RelationFilter::Any),
+            ("child:none", lm::RelationFilter::None),
+        ] {
+            let tags = [tag];
+            let search = parse_search(&tags, &[]);
+            assert_eq!(search.child, Some(filter));
+            assert!(search.include_tags.is_empty());
+        }
+    }
+
+    #[test]
+    fn invalid_relation_metatags_are_plain_tags() {
+        let search = parse_search(&["parent:me", "child:"], &["parent:none", "child:3"]);
+        assert_eq!(search.parent, None);
+        assert_eq!(search.child, None);
+        assert_eq!(search.include_tags, vec!["parent:me", "child:"]);
+        assert_eq!(search.exclude_tags, vec!["parent:none", "child:3"]);
+    }
+
+    #[test]
+    fn rating_metatag_includes_the_rating() {
+        for tags in [["rating:q"], ["rating:questionable"]] {
+            let search = parse_search(&tags, &[]);
+            assert_eq!(search.ratings, Some(vec![vm::PostRating::Questionable]));
+            assert!(search.include_tags.is_empty());
+        }
+    }
+
+    #[test]
+    fn included_ratings_replace_the_default_rating() {
+        let mut search = parse_search(&["rating:explicit"], &[]);
+        apply_default_rating(&mut search, vm::PostRating::Safe);
+
+        let options = dbm_search_options_from_lm(&search);
+        assert_eq!(options.ratings, Some(vec!["explicit".to_string()]));
+    }
+
+    #[test]
+    fn excluded_ratings_narrow_the_default_rating() {
+        let mut search = parse_search(&[], &["rating:s"]);
+        assert_eq!(search.exclude_ratings, vec![vm::PostRating::Safe]);
+        assert!(search.exclude_tags.is_empty());
+
+        apply_default_rating(&mut search, vm::PostRating::Questionable);
+
+        let options = dbm_search_options_from_lm(&search);
+        assert_eq!(options.ratings, Some(vec!["questionable".to_string()]));
+    }
+
+    #[test]
+    fn default_rating_allowing_explicit_posts_does_not_filter() {
+        let mut search = parse_search::<&str>(&[], &[]);
+        apply_default_rating(&mut search, vm::PostRating::Explicit);
+
+        assert_eq!(dbm_search_options_from_lm(&search).ratings, None);
+    }
+
+    #[test]
+    fn invalid_ratings_are_plain_tags() {
+        let search = parse_search(&["rating:x"], &["rating:"]);
+        assert_eq!(search.ratings, None);
+        assert!(search.exclude_ratings.is_empty());
+        assert_eq!(search.include_tags, vec!["rating:x"]);
+        assert_eq!(search.exclude_tags, vec!["rating:"]);
+    }
+}
@@ -0,0 +1,77 @@
+use blazebooru_models::local as lm;
+use blazebooru_models::view as vm;
+use blazebooru_store::models as dbm;
+use blazebooru_store::transform::{dbm_report_reason_from_vm, dbm_report_status_from_vm, report_status_from_dbm};
+
+use super::BlazeBooruCore;
+
+impl BlazeBooruCore {
+    /// Report a post to the moderators.
+    /// Returns false if the post does not exist.
+    pub async fn create_post_report(
+        &self,
+        post_id: i32,
+        request: vm::NewPostReport,
+        user_id: i32,
+    ) -> Result<bool, anyhow::Error> {
+        let report = dbm::NewPostReport {
+            post_id,
+            reason: dbm_report_reason_from_vm(request.reason),
+            comment: request.comment.map(|c| c.trim().to_string()).filter(|c| !c.is_empty()),
+        };
+
+        let success = self.store.create_post_report(&report, user_id).await?;
+
+        Ok(success)
+    }
+
+    /// Get the reports with a status, oldest first.
+    pub async fn get_post_reports(&self, status: vm::ReportStatus) -> Result<Vec<vm::PostReport>, anyhow::Error> {
+        let reports = self.store.get_post_reports(&dbm_report_status_from_vm(status)).await?;
+
+        let mut view_reports = Vec::with_capacity(reports.len());
+        for report in reports {
+            view_reports.push(self.view_post_report(report).await?);
+        }
+
+        Ok(view_reports)
+    }
+
+    /// Resolve an open report.
+    /// Only admins can resolve reports.
+    /// The actions actually taken are recorded in the audit log.
+    /// Returns the status the report was resolved with, which is only banned if the uploader was banned,
+    /// along with the sessions of the banned user that were invalidated,
+    /// or None if the report could not be resolved.
+    pub async fn resolve_post_report(
+        &self,
+        id: i32,
+        action: vm::ReportAction,
+        user_id: i32,
+    ) -> Result<Option<lm::ResolvePostReportResult>, anyhow::Error> {
+        let status = match action {
+            vm::ReportAction::Dismiss => vm::ReportStatus::Dismissed,
+            vm::ReportAction::Delete => vm::ReportStatus::Deleted,
+            vm::ReportAction::Ban => vm::ReportStatus::Banned,
+        };
+
+        let result = self
+            .store
+            .resolve_post_report(id, &dbm_report_status_from_vm(status), user_id)
+            .await?
+            .map(|r| lm::ResolvePostReportResult {
+                status: report_status_from_dbm(&r.status.unwrap()),
+                sessions: r.sessions.unwrap_or_default(),
+            });
+
+        Ok(result)
+    }
+
+    /// Include the reported post, so that moderators can see what was reported.
+    async fn view_post_report(&self, report: dbm::ViewPostReport) -> Result<vm::PostReport, anyhow::Error> {
+        let mut report = vm::PostReport::from(report);
+        report.post = self.store.get_view_post(report.post_id).await?.map(vm::Post::from);
+
+        Ok(report)
+    }
+}
@@ -0,0 +1,182 @@
+use anyhow::anyhow;
+
+use blazebooru_models::view as vm;
+use blazebooru_store::models as dbm;
+
+use crate::post::{apply_default_rating, parse_search};
+
+use super::BlazeBooruCore;
+
+impl BlazeBooruCore {
+    /// Get a saved search, along with the number of new posts matching it.
+    pub async fn get_saved_search(
+        &self,
+        id: i32,
+        user_id: i32,
+        max_rating: vm::PostRating,
+    ) -> Result<Option<vm::SavedSearch>, anyhow::Error> {
+        let Some(saved_search) = self.store.get_saved_search(id, user_id).await? else {
+            return Ok(None);
+        };
+
+        let blacklist = self.store.get_user_tag_blacklist(user_id).await?;
+        let exclude_tags = saved_search_exclude_tags(&saved_search, &blacklist);
+
+        let mut search = parse_search(&saved_search.include_tags, &exclude_tags);
+        apply_default_rating(&mut search, max_rating);
+        search.after_id = Some(saved_search.last_seen_post_id);
+
+        let new_count = self.store.count_posts(&search).await?;
+
+        let mut saved_search = vm::SavedSearch::from(saved_search);
+        saved_search.new_count = Some(new_count);
+
+        Ok(Some(saved_search))
+    }
+
+    /// Get the saved searches of a user.
+    /// Counting new posts requires a search for each, so they are only counted for single saved searches.
+    pub async fn get_saved_searches(&self, user_id: i32) -> Result<Vec<vm::SavedSearch>, anyhow::Error> {
+        let saved_searches = self
+            .store
+            .get_saved_searches(user_id)
+            .await?
+            .into_iter()
+            .map(vm::SavedSearch::from)
+            .collect();
+
+        Ok(saved_searches)
+    }
+
+    /// Get the posts matching a saved search that were added since it was last seen.
+    pub async fn get_saved_search_posts(
+        &self,
+        id: i32,
+        user_id: i32,
+        max_rating: vm::PostRating,
+        start_id: i32,
+        limit: i32,
+    ) -> Result<Option<Vec<vm::Post>>, anyhow::Error> {
+        let Some(saved_search) = self.store.get_saved_search(id, user_id).await? else {
+            return Ok(None);
+        };
+
+        let blacklist = self.store.get_user_tag_blacklist(user_id).await?;
+        let exclude_tags = saved_search_exclude_tags(&saved_search, &blacklist);
+
+        let mut search = parse_search(&saved_search.include_tags, &exclude_tags);
+        apply_default_rating(&mut search, max_rating);
+        search.after_id = Some(saved_search.last_seen_post_id);
+
+        let posts = self
+            .store
+            .get_view_posts(&search, start_id, limit)
+            .await?
+            .into_iter()
+            .map(vm::Post::from)
+            .collect();
+
+        Ok(Some(posts))
+    }
+
+    pub async fn create_saved_search(&self, request: vm::NewSavedSearch, user_id: i32) -> Result<i32, anyhow::Error> {
+        if request.name.trim().is_empty() {
+            return Err(anyhow!("Saved search name cannot be empty"));
+        }
+
+        let new_saved_search = dbm::NewSavedSearch {
+            user_id: Some(user_id),
+            name: Some(request.name),
+            include_tags: request.include_tags,
+            exclude_tags: request.exclude_tags,
+        };
+
+        let new_saved_search_id = self.store.create_saved_search(&new_saved_search).await?;
+
+        Ok(new_saved_search_id)
+    }
+
+    pub async fn update_saved_search(
+        &self,
+        id: i32,
+        request: vm::UpdateSavedSearch,
+        user_id: i32,
+    ) -> Result<bool, anyhow::Error> {
+        if request.name.trim().is_empty() {
+            return Err(anyhow!("Saved search name cannot be empty"));
+        }
+
+        let update_saved_search = dbm::UpdateSavedSearch::from(request);
+        let success = self
+            .store
+            .update_saved_search(id, &update_saved_search, user_id)
+            .await?;
+
+        Ok(success)
+    }
+
+    pub async fn delete_saved_search(&self, id: i32, user_id: i32) -> Result<bool, anyhow::Error> {
+        let success = self.store.delete_saved_search(id, user_id).await?;
+
+        Ok(success)
+    }
+
+    /// Mark posts up to the newest one the user has seen as seen for a saved search.
+    pub async fn mark_saved_search_seen(
+        &self,
+        id: i32,
+        request: vm::MarkSavedSearchSeen,
+        user_id: i32,
+    ) -> Result<bool, anyhow::Error> {
+        let success = self
+            .store
+            .mark_saved_search_seen(id, request.last_seen_post_id, user_id)
+            .await?;
+
+        Ok(success)
+    }
+}
+
+/// Exclude the user's blacklisted tags from a saved search,
+/// unless they are explicitly included in it.
+fn saved_search_exclude_tags(saved_search: &dbm::SavedSearch, blacklist: &[String]) -> Vec<String> {
+    let blacklisted = blacklist.iter().filter(|t| !saved_search.include_tags.contains(t));
+
+    saved_search.exclude_tags.iter().chain(blacklisted).cloned().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+
+    fn saved_search(include_tags: &[&str], exclude_tags: &[&str]) -> dbm::SavedSearch {
+        dbm::SavedSearch {
+            id: 1,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            user_id: 1,
+            name: "search".to_owned(),
+            include_tags: include_tags.iter().map(|t| t.to_string()).collect(),
+            exclude_tags: exclude_tags.iter().map(|t| t.to_string()).collect(),
+            last_seen_post_id: 0,
+        }
+    }
+
+    #[test]
+    fn blacklisted_tags_are_excluded() {
+        let saved_search = saved_search(&["cat"], &["dog"]);
+        let blacklist = ["gore".to_owned()];
+
+        assert_eq!(saved_search_exclude_tags(&saved_search, &blacklist), ["dog", "gore"]);
+    }
+
+    #[test]
+    fn included_tags_override_blacklist() {
+        let saved_search = saved_search(&["gore"], &[]);
+        let blacklist = ["gore".to_owned(), "spoilers".to_owned()];
+
+        assert_eq!(saved_search_exclude_tags(&saved_search, &blacklist), ["spoilers"]);
+    }
+}
@@ -0,0 +1,177 @@
+use anyhow::{anyhow, Context};
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use chrono::Utc;
+use totp_rs::{Algorithm, TOTP};
+
+use blazebooru_models::view as vm;
+
+use super::BlazeBooruCore;
+
+const TOTP_ISSUER: &str = "BlazeBooru";
+const TOTP_DIGITS: usize = 6;
+const TOTP_STEP: u64 = 30;
+
+/// Number of random bytes in a TOTP secret (160 bits, as recommended by RFC 4226)
+const TOTP_SECRET_SIZE: usize = 20;
+
+/// Number of steps before and after the current one that codes are accepted for,
+/// to allow for clock drift.
+const TOTP_SKEW: u64 = 1;
+
+const RECOVERY_CODE_COUNT: usize = 10;
+
+/// Number of random bytes in a recovery code
+const RECOVERY_CODE_SIZE: usize = 8;
+
+impl BlazeBooruCore {
+    pub async fn get_totp_status(&self, user_id: i32) -> Result<vm::TotpStatus, anyhow::Error> {
+        Ok(vm::TotpStatus {
+            enabled: self.is_totp_enabled(user_id).await?,
+        })
+    }
+
+    pub async fn is_totp_enabled(&self, user_id: i32) -> Result<bool, anyhow::Error> {
+        let totp = self.store.get_user_totp(user_id).await?;
+
+        Ok(totp.is_some_and(|t| t.confirmed))
+    }
+
+    /// Generate a new TOTP secret for a user.
+    /// TOTP is not enabled until the enrollment has been confirmed with a code.
+    /// Returns None if TOTP is already enabled.
+    pub async fn enroll_totp(&self, user_id: i32) -> Result<Option<vm::TotpEnrollment>, anyhow::Error> {
+        let user = self.store.get_user(user_id).await?.context("User not found")?;
+
+        let mut secret = vec![0u8; TOTP_SECRET_SIZE];
+        OsRng.fill_bytes(&mut secret);
+
+        if !self.store.enroll_user_totp(user_id, &secret).await? {
+            return Ok(None);
+        }
+
+        let totp = create_totp(secret, user.name)?;
+
+        Ok(Some(vm::TotpEnrollment {
+            secret: totp.get_secret_base32(),
+            uri: totp.get_url(),
+        }))
+    }
+
+    /// Confirm a pending TOTP enrollment, enabling TOTP for the user.
+    /// Returns the user's new recovery codes, or None if the code was invalid
+    /// or there was no pending enrollment.
+    pub async fn confirm_totp(&self, user_id: i32, code: &str) -> Result<Option<vm::TotpRecoveryCodes>, anyhow::Error> {
+        let Some(totp) = self.store.get_user_totp(user_id).await? else {
+            return Ok(None);
+        };
+
+        if totp.confirmed {
+            return Ok(None);
+        }
+
+        let Some(step) = check_totp_code(totp.secret, code)? else {
+            return Ok(None);
+        };
+
+        let recovery_codes: Vec<String> = (0..RECOVERY_CODE_COUNT).map(|_| generate_recovery_code()).collect();
+        let recovery_code_hashes: Vec<String> = recovery_codes.iter().map(|c| hash_recovery_code(c)).collect();
+
+        if !self
+            .store
+            .confirm_user_totp(user_id, step, &recovery_code_hashes)
+            .await?
+        {
+            return Ok(None);
+        }
+
+        Ok(Some(vm::TotpRecoveryCodes { recovery_codes }))
+    }
+
+    /// Disable TOTP for a user.
+    /// Returns false if the code was invalid.
+    pub async fn disable_totp(&self, user_id: i32, code: &str) -> Result<bool, anyhow::Error> {
+        if !self.verify_totp(user_id, code).await? {
+            return Ok(false);
+        }
+
+        Ok(self.store.disable_user_totp(user_id).await?)
+    }
+
+    /// Verify a TOTP code or recovery code for a user that has TOTP enabled.
+    /// Each code can only be used once.
+    pub async fn verify_totp(&self, user_id: i32, code: &str) -> Result<bool, anyhow::Error> {
+        let Some(totp) = self.store.get_user_totp(user_id).await? else {
+            return Ok(false);
+        };
+
+        if !totp.confirmed {
+            return Ok(false);
+        }
+
+        if let Some(step) = check_totp_code(totp.secret, code)? {
+            return Ok(self.store.use_user_totp_step(user_id, step).await?);
+        }
+
+        Ok(self
+            .store
+            .use_user_recovery_code(user_id, &hash_recovery_code(code))
+            .await?)
+    }
+}
+
+fn create_totp(secret: Vec<u8>, account_name: String) -> Result<TOTP, anyhow::Error> {
+    // Skew is handled manually when checking codes, in order to know which step a code belongs to
+    TOTP::new(
+        Algorithm::SHA1,
+        TOTP_DIGITS,
+        0,
+        TOTP_STEP,
+        secret,
+        Some(TOTP_ISSUER.to_string()),
+        account_name,
+    )
+    .map_err(|err| anyhow!("{err}"))
+}
+
+/// Check a TOTP code against the secret.
+/// Returns the step the code is valid for, if it is valid.
+fn check_totp_code(secret: Vec<u8>, code: &str) -> Result<Option<i64>, anyhow::Error> {
+    let code = code.trim();
+
+    if code.len() != TOTP_DIGITS || !code.chars().all(|c| c.is_ascii_digit()) {
+        return Ok(None);
+    }
+
+    let totp = create_totp(secret, String::new())?;
+
+    let current_step = Utc::now().timestamp() as u64 / TOTP_STEP;
+    let step = (current_step - TOTP_SKEW..=current_step + TOTP_SKEW).find(|s| totp.check(code, s * TOTP_STEP));
+
+    Ok(step.map(|s| s as i64))
+}
+
+fn generate_recovery_code() -> String {
+    let mut bytes = [0u8; RECOVERY_CODE_SIZE];
+    OsRng.fill_bytes(&mut bytes);
+
+    let code: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+
+    // Split into groups of 4 characters for readability
+    code.as_bytes()
+        .chunks(4)
+        .map(|c| std::str::from_utf8(c).unwrap())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Recovery codes are long and random, so a fast hash is sufficient.
+/// Separators and case are ignored.
+fn hash_recovery_code(code: &str) -> String {
+    let code: String = code
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+
+    blake3::hash(code.as_bytes()).to_hex().to_string()
+}
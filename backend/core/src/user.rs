@@ -9,13 +9,21 @@ use regex::Regex;
 use blazebooru_models::local as lm;
 use blazebooru_models::view as vm;
 use blazebooru_store::models as dbm;
+use blazebooru_store::transform::dbm_rating_from_vm;
 
 use super::BlazeBooruCore;
 
 static RE_VALID_USERNAME: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[\d\w_]+$").unwrap());
 
 impl BlazeBooruCore {
-    pub async fn create_user(&self, user: lm::NewUser<'_>) -> Result<i32, anyhow::Error> {
+    /// Create a user.
+    /// Returns None if the invite code is not valid,
+    /// or if an invite code is required but none was specified and the user is not the first one.
+    pub async fn create_user(
+        &self,
+        user: lm::NewUser<'_>,
+        require_invite_code: bool,
+    ) -> Result<Option<i32>, anyhow::Error> {
         if user.password.is_empty() {
             return Err(anyhow!("Password can not be blank"));
         }
@@ -35,11 +43,44 @@ impl BlazeBooruCore {
         let user = dbm::NewUser {
             name: Some(user.name.into()),
             password_hash: Some(password_hash.to_string()),
+            invite_code: user.invite_code.map(Into::into),
         };
 
-        let user = self.store.create_user(&user).await?;
+        let user = self.store.create_user(&user, require_invite_code).await?;
 
-        Ok(user.id)
+        Ok(user.map(|u| u.id))
+    }
+
+    /// Get the user with the name asserted by a reverse proxy,
+    /// creating it if it does not exist and registration is allowed.
+    /// Banned users are not returned.
+    pub async fn get_or_create_proxy_user(
+        &self,
+        name: &str,
+        allow_registration: bool,
+    ) -> Result<Option<lm::User>, anyhow::Error> {
+        if let Some(user) = self.store.get_user_by_name(name).await? {
+            if user.is_banned {
+                return Ok(None);
+            }
+
+            return Ok(Some(lm::User::from(user)));
+        }
+
+        if !allow_registration || !RE_VALID_USERNAME.is_match(name) {
+            return Ok(None);
+        }
+
+        // Users authenticated by the proxy have no password
+        let user = dbm::NewUser {
+            name: Some(name.to_string()),
+            password_hash: None,
+            invite_code: None,
+        };
+
+        let user = self.store.create_user(&user, false).await?;
+
+        Ok(user.map(lm::User::from))
     }
 
     pub async fn get_user_by_name(&self, name: &str) -> Result<Option<vm::User>, anyhow::Error> {
@@ -48,9 +89,50 @@ impl BlazeBooruCore {
         Ok(user.map(vm::User::from))
     }
 
+    /// Check whether a user is some sort of admin.
+    pub async fn is_user_admin(&self, user_id: i32) -> Result<bool, anyhow::Error> {
+        let user = self.store.get_user(user_id).await?;
+
+        Ok(user.is_some_and(|u| u.rank > 0))
+    }
+
+    /// Lift the ban on a user.
+    /// Only admins can unban users.
+    /// Returns false if the user is not banned.
+    pub async fn unban_user(&self, id: i32, user_id: i32) -> Result<bool, anyhow::Error> {
+        let success = self.store.unban_user(id, user_id).await?;
+
+        Ok(success)
+    }
+
     pub async fn get_user_profile(&self, user_id: i32) -> Result<Option<vm::User>, anyhow::Error> {
         let user = self.store.get_user(user_id).await?;
 
         Ok(user.map(vm::User::from))
     }
+
+    pub async fn get_user_settings(&self, user_id: i32) -> Result<Option<vm::UserSettings>, anyhow::Error> {
+        let user = self.store.get_user(user_id).await?;
+
+        Ok(user.map(vm::UserSettings::from))
+    }
+
+    pub async fn update_user_settings(&self, user_id: i32, settings: vm::UserSettings) -> Result<(), anyhow::Error> {
+        let max_rating = settings.max_rating.map(dbm_rating_from_vm);
+        self.store.update_user_max_rating(user_id, max_rating).await?;
+
+        Ok(())
+    }
+
+    pub async fn get_user_tag_blacklist(&self, user_id: i32) -> Result<vm::TagBlacklist, anyhow::Error> {
+        let tags = self.store.get_user_tag_blacklist(user_id).await?;
+
+        Ok(vm::TagBlacklist { tags })
+    }
+
+    pub async fn set_user_tag_blacklist(&self, user_id: i32, blacklist: vm::TagBlacklist) -> Result<(), anyhow::Error> {
+        self.store.set_user_tag_blacklist(user_id, &blacklist.tags).await?;
+
+        Ok(())
+    }
 }
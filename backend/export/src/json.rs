@@ -3,6 +3,7 @@ use std::{fs, path::Path};
 use anyhow::Context;
 
 use blazebooru_core::BlazeBooruCore;
+use blazebooru_models::export as em;
 
 pub async fn export_json(path: &Path, core: &BlazeBooruCore) -> Result<(), anyhow::Error> {
     let posts = core
@@ -10,8 +11,12 @@ pub async fn export_json(path: &Path, core: &BlazeBooruCore) -> Result<(), anyho
         .await
         .context("Error retrieving posts")?;
 
+    let pools = core.get_export_pools().await.context("Error retrieving pools")?;
+
+    let export = em::Export { posts, pools };
+
     let file = fs::File::create(path).context("Error creating JSON file")?;
-    serde_json::to_writer_pretty(file, &posts).context("Error serializing to JSON file")?;
+    serde_json::to_writer_pretty(file, &export).context("Error serializing to JSON file")?;
 
     Ok(())
 }
@@ -1,10 +1,20 @@
+use std::collections::HashMap;
 use std::{fs, path::Path};
 
 use anyhow::Context;
+use serde::Deserialize;
 
 use blazebooru_core::BlazeBooruCore;
 use blazebooru_models::export as em;
 
+/// Exports used to only contain the posts
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ImportFile {
+    Export(em::Export),
+    Posts(Vec<em::Post>),
+}
+
 pub async fn import_json(core: &BlazeBooruCore, path: &Path, user_name: &str) -> Result<(), anyhow::Error> {
     let user = core
         .get_user_by_name(user_name)
@@ -15,11 +25,33 @@ pub async fn import_json(core: &BlazeBooruCore, path: &Path, user_name: &str) ->
 
     let file = fs::File::open(path).context("Error opening JSON file")?;
 
-    let posts: Vec<em::Post> = serde_json::from_reader(file).context("Error deserializing from JSON file")?;
+    let import: ImportFile = serde_json::from_reader(file).context("Error deserializing from JSON file")?;
+
+    let export = match import {
+        ImportFile::Export(export) => export,
+        ImportFile::Posts(posts) => em::Export { posts, pools: vec![] },
+    };
+
+    // New post IDs by hash, for looking up pool posts
+    let mut post_ids = HashMap::new();
+
+    for post in export.posts.into_iter() {
+        let hash = post.hash.clone();
 
-    for post in posts.into_iter() {
         // Change user ID
-        core.import_post(post, user_id, None).await?;
+        let post_id = core.import_post(post, user_id, None).await?;
+
+        post_ids.insert(hash, post_id);
+    }
+
+    for pool in export.pools.into_iter() {
+        let pool_post_ids = pool
+            .post_hashes
+            .iter()
+            .filter_map(|h| post_ids.get(h).copied())
+            .collect();
+
+        core.import_pool(pool, pool_post_ids, user_id).await?;
     }
 
     Ok(())
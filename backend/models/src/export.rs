@@ -1,6 +1,13 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Export {
+    pub posts: Vec<Post>,
+    #[serde(default)]
+    pub pools: Vec<Pool>,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Post {
     pub created_at: DateTime<Utc>,
@@ -16,4 +23,17 @@ pub struct Post {
     pub ext: String,
     pub tn_ext: String,
     pub tags: Vec<String>,
+    /// Not present in exports from before ratings were added
+    #[serde(default)]
+    pub rating: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Pool {
+    pub created_at: DateTime<Utc>,
+    pub user_name: String,
+    pub name: String,
+    pub description: Option<String>,
+    /// Posts in the pool, identified by their hash
+    pub post_hashes: Vec<String>,
 }
@@ -1,6 +1,7 @@
 use std::{borrow::Cow, path::PathBuf};
 
 use chrono::{DateTime, Utc};
+use serde::Deserialize;
 use uuid::Uuid;
 
 use crate::view as vm;
@@ -22,6 +23,15 @@ pub struct User {
     pub name: String,
 }
 
+/// An identity asserted by an OpenID Connect provider
+#[derive(Debug)]
+pub struct OidcIdentity<'a> {
+    pub issuer: Cow<'a, str>,
+    pub subject: Cow<'a, str>,
+    /// Name to use if a new user is created
+    pub preferred_name: Option<Cow<'a, str>>,
+}
+
 #[derive(Debug)]
 pub struct NewPost<'a> {
     pub user_id: i32,
@@ -31,12 +41,65 @@ pub struct NewPost<'a> {
     pub filename: Cow<'a, str>,
     pub file: HashedFile,
     pub tags: Vec<&'a str>,
+    pub parent_id: Option<i32>,
+    /// If not specified, the default rating is used
+    pub rating: Option<vm::PostRating>,
+}
+
+/// A post search, with any metatags separated from the tags
+#[derive(Debug)]
+pub struct PostSearch<'a> {
+    pub include_tags: Vec<&'a str>,
+    pub exclude_tags: Vec<&'a str>,
+    /// Only include posts favorited by this user
+    pub fav_user_name: Option<&'a str>,
+    pub min_score: Option<i32>,
+    pub max_score: Option<i32>,
+    /// Only include posts in this pool
+    pub pool_id: Option<i32>,
+    /// Filter posts by their parent
+    pub parent: Option<RelationFilter>,
+    /// Filter posts by their children
+    pub child: Option<RelationFilter>,
+    /// Only include posts with these ratings.
+    /// If not specified by the search, the default filter is used.
+    pub ratings: Option<Vec<vm::PostRating>>,
+    /// Exclude posts with these ratings, including those allowed by the default filter
+    pub exclude_ratings: Vec<vm::PostRating>,
+    /// Only include posts newer than this post
+    pub after_id: Option<i32>,
+    pub sort: PostSort,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RelationFilter {
+    /// Has any related post
+    Any,
+    /// Has no related posts
+    None,
+    /// Is related to a specific post
+    Post(i32),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PostSort {
+    Id,
+    FavCount,
+    Score,
 }
 
 #[derive(Debug)]
 pub struct NewUser<'a> {
     pub name: Cow<'a, str>,
     pub password: Cow<'a, str>,
+    pub invite_code: Option<Cow<'a, str>>,
+}
+
+#[derive(Debug)]
+pub struct ApiKey {
+    pub id: i32,
+    pub user_id: i32,
+    pub scopes: Vec<vm::ApiKeyScope>,
 }
 
 #[derive(Debug)]
@@ -46,8 +109,41 @@ pub struct CreateRefreshTokenResult {
 }
 
 #[derive(Debug)]
-pub struct RefreshRefreshTokenResult {
-    pub token: Uuid,
-    pub session: i64,
-    pub user_id: i32,
+pub struct ResolvePostReportResult {
+    pub status: vm::ReportStatus,
+    /// Sessions of the banned uploader that were invalidated
+    pub sessions: Vec<i64>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum UpdatePostResult {
+    Updated,
+    /// The post does not exist, or belongs to another user
+    NotFound,
+    /// The parent post does not exist, or is a descendant of the post
+    InvalidParent,
+}
+
+#[derive(Debug)]
+pub enum RefreshRefreshTokenResult {
+    Refreshed {
+        token: Uuid,
+        session: i64,
+        user_id: i32,
+    },
+    /// The token had already been used, so its session was invalidated
+    Reused {
+        session: i64,
+    },
+}
+
+/// Event received from the database
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Event {
+    /// A session was logged out, possibly on another server instance
+    SessionRevoked { session: i64 },
+    /// Event to relay to clients
+    #[serde(untagged)]
+    Client(vm::Event),
 }
@@ -1,5 +1,7 @@
+use std::net::IpAddr;
+
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Post {
@@ -18,6 +20,39 @@ pub struct Post {
     pub ext: String,
     pub tn_ext: String,
     pub tags: Vec<String>,
+    pub fav_count: i32,
+    pub score: i32,
+    pub parent_id: Option<i32>,
+    pub rating: PostRating,
+    /// Child posts, only included for single posts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub child_ids: Option<Vec<i32>>,
+    /// Pools the post is in, only included for single posts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pools: Option<Vec<PostPool>>,
+}
+
+/// Content rating of a post, ordered from safest to least safe
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PostRating {
+    Safe,
+    Questionable,
+    /// As a maximum rating, this includes all posts
+    Explicit,
+}
+
+impl PostRating {
+    pub const ALL: [PostRating; 3] = [PostRating::Safe, PostRating::Questionable, PostRating::Explicit];
+}
+
+/// A pool a post is in, with the posts before and after it in the pool
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PostPool {
+    pub id: i32,
+    pub name: String,
+    pub previous_post_id: Option<i32>,
+    pub next_post_id: Option<i32>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -28,6 +63,81 @@ pub struct UpdatePost {
 
     pub add_tags: Vec<String>,
     pub remove_tags: Vec<String>,
+
+    /// Left unchanged if not specified, and removed if null
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub parent_id: Option<Option<i32>>,
+
+    /// Left unchanged if not specified
+    #[serde(default)]
+    pub rating: Option<PostRating>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Pool {
+    pub id: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub user_id: i32,
+    pub user_name: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub post_ids: Vec<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewPool {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub post_ids: Vec<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdatePool {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub post_ids: Vec<i32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SavedSearch {
+    pub id: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub name: String,
+    pub include_tags: Vec<String>,
+    pub exclude_tags: Vec<String>,
+    pub last_seen_post_id: i32,
+    /// Number of matching posts added since the search was last seen,
+    /// only included for single saved searches
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_count: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewSavedSearch {
+    pub name: String,
+    #[serde(default)]
+    pub include_tags: Vec<String>,
+    #[serde(default)]
+    pub exclude_tags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateSavedSearch {
+    pub name: String,
+    #[serde(default)]
+    pub include_tags: Vec<String>,
+    #[serde(default)]
+    pub exclude_tags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MarkSavedSearchSeen {
+    /// ID of the newest post the user has seen
+    pub last_seen_post_id: i32,
 }
 
 #[derive(Debug, Serialize)]
@@ -39,7 +149,7 @@ pub struct Tag {
     pub implied_tags: Vec<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct UpdateTag {
     #[serde(default)]
     pub add_aliases: Vec<String>,
@@ -58,12 +168,165 @@ pub struct Comment {
     pub updated_at: DateTime<Utc>,
     pub user_id: Option<i32>,
     pub user_name: Option<String>,
+    pub post_id: i32,
+    /// Replaced by a placeholder if the comment is deleted
     pub comment: String,
+    pub is_edited: bool,
+    pub is_deleted: bool,
+    /// Held comments are hidden until they are approved by an admin
+    pub is_held: bool,
+    /// ID of the comment this is a reply to
+    pub reply_to_id: Option<i32>,
+    /// Nesting level of the comment, 0 for top-level comments
+    pub depth: i32,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct NewPostComment {
     pub comment: String,
+    pub reply_to_id: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateComment {
+    pub comment: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Notification {
+    pub id: i32,
+    pub created_at: DateTime<Utc>,
+    /// What happened: mention, reply, comment or tag_change
+    pub kind: String,
+    /// User that caused the notification, if not anonymous
+    pub actor_user_id: Option<i32>,
+    pub actor_user_name: Option<String>,
+    pub post_id: Option<i32>,
+    pub comment_id: Option<i32>,
+    pub is_read: bool,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportReason {
+    Spam,
+    Illegal,
+    Offensive,
+    WrongRating,
+    Duplicate,
+    Other,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportStatus {
+    Open,
+    Dismissed,
+    /// The post was deleted
+    Deleted,
+    /// The post was deleted and its uploader banned
+    Banned,
+}
+
+/// Decision made by a moderator on a report
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportAction {
+    Dismiss,
+    Delete,
+    Ban,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PostReport {
+    pub id: i32,
+    pub created_at: DateTime<Utc>,
+    pub post_id: i32,
+    pub post_user_id: i32,
+    pub user_id: i32,
+    pub user_name: String,
+    pub reason: ReportReason,
+    pub comment: Option<String>,
+    pub status: ReportStatus,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub resolved_by_user_id: Option<i32>,
+    pub resolved_by_user_name: Option<String>,
+    /// The reported post, if it has not been deleted
+    pub post: Option<Post>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewPostReport {
+    pub reason: ReportReason,
+    pub comment: Option<String>,
+}
+
+/// Moderation action recorded in the audit log
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    DeletePost,
+    UndeletePost,
+    PurgePost,
+    DeleteComment,
+    ApproveComment,
+    UpdateTag,
+    DismissReport,
+    BanUser,
+    UnbanUser,
+}
+
+/// Kind of object an audit log entry is about
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditTargetType {
+    Post,
+    Comment,
+    Tag,
+    Report,
+    User,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditLogEntry {
+    pub id: i32,
+    pub created_at: DateTime<Utc>,
+    /// User that performed the action, if it still exists
+    pub user_id: Option<i32>,
+    pub user_name: Option<String>,
+    /// Kept as text, as entries may outlive the actions they record
+    pub action: String,
+    pub target_type: String,
+    pub target_id: i32,
+    pub details: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditLogFilter {
+    /// User that performed the action
+    pub user_id: Option<i32>,
+    pub action: Option<AuditAction>,
+    pub target_type: Option<AuditTargetType>,
+    pub target_id: Option<i32>,
+}
+
+/// Live update pushed to clients
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Event {
+    /// A post was uploaded
+    Post { post_id: i32 },
+    /// A comment on a post became visible
+    Comment { post_id: i32, comment_id: i32 },
+    /// The tags of a post were changed
+    TagChange { post_id: i32 },
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MarkNotificationsRead {
+    /// Notifications to mark as read.
+    /// All notifications are marked as read if not specified.
+    pub ids: Option<Vec<i32>>,
 }
 
 #[derive(Debug, Serialize)]
@@ -74,6 +337,98 @@ pub struct User {
     pub rank: i16,
 }
 
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiKeyScope {
+    /// View posts, even if login is required
+    Read,
+    /// Upload new posts
+    Upload,
+    /// Edit post information and tags
+    EditTags,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApiKey {
+    pub id: i32,
+    pub created_at: DateTime<Utc>,
+    pub name: String,
+    pub key_prefix: String,
+    pub scopes: Vec<ApiKeyScope>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewApiKey {
+    pub name: String,
+    pub scopes: Vec<ApiKeyScope>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreateApiKeyResult {
+    pub api_key: ApiKey,
+    pub key: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct UserSettings {
+    /// Highest rating of posts shown by default.
+    /// If not set, the configured default is used.
+    pub max_rating: Option<PostRating>,
+}
+
+/// Tags excluded from all searches by a user
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TagBlacklist {
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InviteCode {
+    pub id: i32,
+    pub created_at: DateTime<Utc>,
+    pub code: String,
+    pub max_uses: i32,
+    pub uses: i32,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewInviteCode {
+    /// Number of users that can register using the code
+    pub max_uses: i32,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TotpStatus {
+    pub enabled: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TotpEnrollment {
+    /// Base32 encoded secret, for manual entry
+    pub secret: String,
+    /// otpauth:// URI, for QR codes
+    pub uri: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TotpRecoveryCodes {
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Session {
+    pub session: i64,
+    pub created_at: DateTime<Utc>,
+    pub created_ip: IpAddr,
+    pub last_seen_at: DateTime<Utc>,
+    pub last_seen_ip: IpAddr,
+    pub user_agent: Option<String>,
+    pub is_current: bool,
+}
+
 #[derive(Debug, Serialize)]
 pub struct PageInfo {
     pub no: i32,
@@ -85,4 +440,18 @@ pub struct Config {
     pub max_image_size: usize,
     pub require_login: bool,
     pub allow_registration: bool,
+    pub require_invite_code: bool,
+    pub oidc_enabled: bool,
+    pub proxy_auth_enabled: bool,
+    pub default_max_rating: PostRating,
+}
+
+/// Deserialize a value that is present as Some, even if it is null.
+/// This allows a missing field to be told apart from a null one.
+fn deserialize_some<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
 }
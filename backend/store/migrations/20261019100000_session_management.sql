@@ -0,0 +1,174 @@
+---- DROP OLD ----
+
+DROP FUNCTION create_refresh_token;
+DROP FUNCTION refresh_refresh_token;
+
+---- TABLES ----
+
+-- Add user_agent column to refresh_token
+ALTER TABLE refresh_token
+  ADD COLUMN user_agent text;
+
+---- INDEXES ----
+
+CREATE INDEX refresh_token_user_id_idx ON refresh_token
+  USING btree
+  (user_id ASC NULLS LAST);
+
+---- VIEWS ----
+
+-- Create view_session view
+CREATE VIEW view_session
+AS
+SELECT
+  rt.session,
+  rt.user_id,
+  MIN(rt.created_at) AS created_at,
+  (array_agg(rt.created_ip ORDER BY rt.id ASC))[1] AS created_ip,
+  MAX(rt.created_at) AS last_seen_at,
+  (array_agg(rt.created_ip ORDER BY rt.id DESC))[1] AS last_seen_ip,
+  (array_agg(rt.user_agent ORDER BY rt.id DESC))[1] AS user_agent
+FROM refresh_token AS rt
+GROUP BY rt.session, rt.user_id
+-- Only sessions that still have a usable refresh token are active
+HAVING bool_or(NOT rt.used AND rt.expires_at > CURRENT_TIMESTAMP);
+
+---- FUNCTIONS ----
+
+-- Create create_refresh_token function
+CREATE FUNCTION create_refresh_token(
+  IN p_user_id integer,
+  IN p_ip inet,
+  IN p_user_agent text
+)
+RETURNS create_refresh_token_result
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_new_token uuid;
+  v_session bigint;
+BEGIN
+  v_session := nextval('refresh_token_session_seq');
+
+  -- Generate new refresh token with new session
+  INSERT INTO refresh_token (session, user_id, created_ip, user_agent)
+  VALUES (v_session, p_user_id, p_ip, p_user_agent)
+  RETURNING token INTO v_new_token;
+
+  -- Return new token
+  RETURN (v_new_token, v_session);
+END;
+$BODY$;
+
+-- Create refresh_refresh_token function
+CREATE FUNCTION refresh_refresh_token(
+  IN p_token uuid,
+  IN p_ip inet,
+  IN p_user_agent text
+)
+RETURNS refresh_refresh_token_result
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_refresh_token refresh_token;
+  v_result refresh_refresh_token_result;
+BEGIN
+  SELECT * INTO v_refresh_token
+  FROM refresh_token
+  WHERE token = p_token;
+
+  -- Check if exists
+  IF v_refresh_token IS NULL THEN
+    RETURN NULL;
+  END IF;
+
+  -- Check if already used
+  IF v_refresh_token.used THEN
+    PERFORM invalidate_session(v_refresh_token.session);
+    RETURN NULL;
+  END IF;
+
+  -- Check if expired
+  IF v_refresh_token.expires_at < CURRENT_TIMESTAMP THEN
+    RETURN NULL;
+  END IF;
+
+  -- Mark token as used
+  UPDATE refresh_token
+  SET used = true, used_ip = p_ip
+  WHERE id = v_refresh_token.id;
+
+  -- Generate new refresh token
+  INSERT INTO refresh_token (session, user_id, created_ip, user_agent)
+  VALUES (v_refresh_token.session, v_refresh_token.user_id, p_ip, p_user_agent)
+  RETURNING token, session, user_id INTO v_result;
+
+  -- Return result
+  RETURN v_result;
+END;
+$BODY$;
+
+-- Create invalidate_user_session function
+CREATE FUNCTION invalidate_user_session(
+  IN p_user_id integer,
+  IN p_session bigint
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  -- Only allow users to invalidate their own sessions
+  IF NOT EXISTS(SELECT * FROM view_session WHERE session = p_session AND user_id = p_user_id) THEN
+    RETURN false;
+  END IF;
+
+  PERFORM invalidate_session(p_session);
+
+  RETURN true;
+END;
+$BODY$;
+
+-- Create invalidate_user_sessions function
+CREATE FUNCTION invalidate_user_sessions(
+  IN p_user_id integer
+)
+RETURNS VOID
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  UPDATE refresh_token
+  SET used = true
+  WHERE user_id = p_user_id
+    AND NOT used;
+END;
+$BODY$;
+
+-- Create prune_refresh_tokens function
+CREATE FUNCTION prune_refresh_tokens()
+RETURNS integer
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_count integer;
+BEGIN
+  -- Delete expired tokens, as well as all tokens belonging
+  -- to sessions that no longer have a usable token.
+  -- Used tokens in active sessions are kept, so that
+  -- reuse of a stolen token can still be detected.
+  DELETE FROM refresh_token AS rt
+  WHERE rt.expires_at < CURRENT_TIMESTAMP
+     OR NOT EXISTS(SELECT * FROM refresh_token AS urt
+                   WHERE urt.session = rt.session
+                     AND NOT urt.used
+                     AND urt.expires_at > CURRENT_TIMESTAMP);
+
+  GET DIAGNOSTICS v_count = ROW_COUNT;
+
+  RETURN v_count;
+END;
+$BODY$;
@@ -0,0 +1,116 @@
+---- DROP OLD ----
+
+DROP FUNCTION invalidate_session;
+DROP FUNCTION invalidate_user_sessions;
+DROP FUNCTION refresh_refresh_token;
+
+---- TABLES ----
+
+-- Create revoked_session table
+CREATE TABLE revoked_session
+(
+  session bigint NOT NULL,
+  revoked_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+  PRIMARY KEY (session)
+);
+
+---- INDEXES ----
+
+CREATE INDEX revoked_session_revoked_at_idx ON revoked_session
+  USING btree
+  (revoked_at ASC NULLS LAST);
+
+---- FUNCTIONS ----
+
+-- Create invalidate_session function
+CREATE FUNCTION invalidate_session(
+  IN p_session bigint
+)
+RETURNS VOID
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  UPDATE refresh_token
+  SET used = true
+  WHERE session = p_session;
+
+  -- Record revocation, so that access tokens
+  -- issued for the session can be rejected
+  INSERT INTO revoked_session (session)
+  VALUES (p_session)
+  ON CONFLICT (session)
+  DO NOTHING;
+END;
+$BODY$;
+
+-- Create invalidate_user_sessions function
+CREATE FUNCTION invalidate_user_sessions(
+  IN p_user_id integer
+)
+RETURNS bigint[]
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_sessions bigint[];
+BEGIN
+  v_sessions := array(SELECT session FROM view_session WHERE user_id = p_user_id);
+
+  PERFORM invalidate_session(s) FROM unnest(v_sessions) AS s;
+
+  RETURN v_sessions;
+END;
+$BODY$;
+
+-- Create refresh_refresh_token function
+-- Reusing a token invalidates its session, which is returned without a new token
+-- so that its access tokens can be revoked as well.
+CREATE FUNCTION refresh_refresh_token(
+  IN p_token uuid,
+  IN p_ip inet,
+  IN p_user_agent text
+)
+RETURNS refresh_refresh_token_result
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_refresh_token refresh_token;
+  v_result refresh_refresh_token_result;
+BEGIN
+  SELECT * INTO v_refresh_token
+  FROM refresh_token
+  WHERE token = p_token;
+
+  -- Check if exists
+  IF v_refresh_token IS NULL THEN
+    RETURN NULL;
+  END IF;
+
+  -- Check if already used
+  IF v_refresh_token.used THEN
+    PERFORM invalidate_session(v_refresh_token.session);
+    RETURN ROW(NULL::uuid, v_refresh_token.session, v_refresh_token.user_id);
+  END IF;
+
+  -- Check if expired
+  IF v_refresh_token.expires_at < CURRENT_TIMESTAMP THEN
+    RETURN NULL;
+  END IF;
+
+  -- Mark token as used
+  UPDATE refresh_token
+  SET used = true, used_ip = p_ip
+  WHERE id = v_refresh_token.id;
+
+  -- Generate new refresh token
+  INSERT INTO refresh_token (session, user_id, created_ip, user_agent)
+  VALUES (v_refresh_token.session, v_refresh_token.user_id, p_ip, p_user_agent)
+  RETURNING token, session, user_id INTO v_result;
+
+  -- Return result
+  RETURN v_result;
+END;
+$BODY$;
@@ -0,0 +1,131 @@
+---- TABLES ----
+
+-- Create api_key table
+CREATE TABLE api_key
+(
+  id serial NOT NULL,
+  created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+  user_id integer NOT NULL,
+  name text NOT NULL,
+  key_hash text NOT NULL,
+  key_prefix text NOT NULL,
+  scopes text[] NOT NULL DEFAULT '{}',
+  last_used_at timestamp with time zone,
+
+  PRIMARY KEY (id),
+  UNIQUE (key_hash),
+
+  FOREIGN KEY (user_id)
+    REFERENCES "user" (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID
+);
+
+SELECT manage_updated_at('api_key'); -- Automatically manage updated_at
+
+---- INDEXES ----
+
+CREATE INDEX api_key_user_id_idx ON api_key
+  USING btree
+  (user_id ASC NULLS LAST);
+
+---- TYPES ----
+
+CREATE TYPE new_api_key AS (
+  name text,
+  key_hash text,
+  key_prefix text,
+  scopes text[]
+);
+
+---- FUNCTIONS ----
+
+-- Create create_api_key function
+CREATE FUNCTION create_api_key(
+  IN p_api_key new_api_key,
+  IN p_user_id integer
+)
+RETURNS api_key
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_api_key api_key;
+BEGIN
+  -- Insert API key
+  INSERT INTO api_key (
+    user_id,
+    name,
+    key_hash,
+    key_prefix,
+    scopes
+  )
+  SELECT
+    p_user_id, -- user_id
+    p_api_key.name, -- name
+    p_api_key.key_hash, -- key_hash
+    p_api_key.key_prefix, -- key_prefix
+    p_api_key.scopes -- scopes
+  RETURNING * INTO v_api_key;
+
+  RETURN v_api_key;
+END;
+$BODY$;
+
+-- Create delete_api_key function
+CREATE FUNCTION delete_api_key(
+  IN p_api_key_id integer,
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_success boolean;
+BEGIN
+  -- Only allow users to delete their own API keys
+  DELETE FROM api_key
+  WHERE id = p_api_key_id
+    AND user_id = p_user_id
+  RETURNING true INTO v_success;
+
+  RETURN COALESCE(v_success, false);
+END;
+$BODY$;
+
+-- Create use_api_key function
+-- Get the API key with the specified hash.
+-- The last used time is only updated once a minute,
+-- so that every request made with the key does not write to the database.
+CREATE FUNCTION use_api_key(
+  IN p_key_hash text
+)
+RETURNS SETOF api_key
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_api_key api_key;
+BEGIN
+  SELECT * INTO v_api_key
+  FROM api_key
+  WHERE key_hash = p_key_hash;
+
+  IF v_api_key.id IS NULL THEN
+    RETURN;
+  END IF;
+
+  IF v_api_key.last_used_at IS NULL OR v_api_key.last_used_at < CURRENT_TIMESTAMP - interval '1 minute' THEN
+    UPDATE api_key
+    SET last_used_at = CURRENT_TIMESTAMP
+    WHERE id = v_api_key.id
+    RETURNING * INTO v_api_key;
+  END IF;
+
+  RETURN NEXT v_api_key;
+END;
+$BODY$;
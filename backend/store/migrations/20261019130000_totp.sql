@@ -0,0 +1,107 @@
+---- TABLES ----
+
+-- Create user_totp table
+CREATE TABLE user_totp
+(
+  user_id integer NOT NULL,
+  created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+  secret bytea NOT NULL,
+  confirmed boolean NOT NULL DEFAULT false,
+  last_used_step bigint,
+
+  PRIMARY KEY (user_id),
+
+  FOREIGN KEY (user_id)
+    REFERENCES "user" (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID
+);
+
+SELECT manage_updated_at('user_totp'); -- Automatically manage updated_at
+
+-- Create user_recovery_code table
+CREATE TABLE user_recovery_code
+(
+  id serial NOT NULL,
+  created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+  user_id integer NOT NULL,
+  code_hash text NOT NULL,
+  used_at timestamp with time zone,
+
+  PRIMARY KEY (id),
+
+  FOREIGN KEY (user_id)
+    REFERENCES "user" (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID
+);
+
+---- INDEXES ----
+
+CREATE INDEX user_recovery_code_user_id_idx ON user_recovery_code
+  USING btree
+  (user_id ASC NULLS LAST);
+
+---- FUNCTIONS ----
+
+-- Create confirm_user_totp function
+CREATE FUNCTION confirm_user_totp(
+  IN p_user_id integer,
+  IN p_step bigint,
+  IN p_recovery_code_hashes text[]
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_success boolean;
+BEGIN
+  UPDATE user_totp
+  SET confirmed = true, last_used_step = p_step
+  WHERE user_id = p_user_id
+    AND NOT confirmed
+  RETURNING true INTO v_success;
+
+  -- Check if there was a pending enrollment
+  IF v_success IS NULL THEN
+    RETURN false;
+  END IF;
+
+  -- Replace any previous recovery codes
+  DELETE FROM user_recovery_code
+  WHERE user_id = p_user_id;
+
+  INSERT INTO user_recovery_code (user_id, code_hash)
+  SELECT p_user_id, unnest(p_recovery_code_hashes);
+
+  RETURN true;
+END;
+$BODY$;
+
+-- Create disable_user_totp function
+CREATE FUNCTION disable_user_totp(
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_success boolean;
+BEGIN
+  DELETE FROM user_totp
+  WHERE user_id = p_user_id
+  RETURNING true INTO v_success;
+
+  DELETE FROM user_recovery_code
+  WHERE user_id = p_user_id;
+
+  RETURN COALESCE(v_success, false);
+END;
+$BODY$;
@@ -0,0 +1,185 @@
+---- DROP OLD ----
+
+DROP FUNCTION create_user;
+DROP TYPE new_user;
+
+---- TABLES ----
+
+-- Add invited_by_user_id column to user
+ALTER TABLE "user"
+  ADD COLUMN invited_by_user_id integer;
+
+ALTER TABLE "user"
+  ADD FOREIGN KEY (invited_by_user_id)
+    REFERENCES "user" (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE SET NULL
+    NOT VALID;
+
+-- Create invite_code table
+CREATE TABLE invite_code
+(
+  id serial NOT NULL,
+  created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+  user_id integer NOT NULL,
+  code text NOT NULL,
+  max_uses integer NOT NULL DEFAULT 1,
+  uses integer NOT NULL DEFAULT 0,
+  expires_at timestamp with time zone,
+
+  PRIMARY KEY (id),
+  UNIQUE (code),
+
+  FOREIGN KEY (user_id)
+    REFERENCES "user" (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID
+);
+
+SELECT manage_updated_at('invite_code'); -- Automatically manage updated_at
+
+---- INDEXES ----
+
+CREATE INDEX invite_code_user_id_idx ON invite_code
+  USING btree
+  (user_id ASC NULLS LAST);
+
+---- TYPES ----
+
+CREATE TYPE new_user AS (
+  name text,
+  password_hash text,
+  invite_code text
+);
+
+CREATE TYPE new_invite_code AS (
+  code text,
+  max_uses integer,
+  expires_at timestamp with time zone
+);
+
+---- FUNCTIONS ----
+
+-- Create create_user function
+-- Returns NULL if the invite code is not valid (anymore),
+-- which can happen if it was used up by another registration,
+-- or if an invite code is required but none was specified.
+-- The first user needs no invite code, as there is no one to invite them.
+CREATE FUNCTION create_user(
+  IN p_user new_user,
+  IN p_require_invite_code boolean
+)
+RETURNS "user"
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_user "user";
+  v_is_first_user boolean;
+  v_rank smallint;
+  v_invited_by_user_id integer;
+BEGIN
+  -- Register one user at a time, so that only one user can be the first
+  PERFORM pg_advisory_xact_lock(hashtext('create_user'));
+
+  v_is_first_user := NOT EXISTS(SELECT * FROM "user");
+
+  -- If there are no existing users,
+  -- make the new one a giga-admin.
+  IF v_is_first_user THEN
+    v_rank := 9001;
+  ELSE
+    v_rank := 0;
+  END IF;
+
+  IF p_require_invite_code AND p_user.invite_code IS NULL AND NOT v_is_first_user THEN
+    RETURN NULL;
+  END IF;
+
+  -- If an invite code was specified, use it
+  IF p_user.invite_code IS NOT NULL THEN
+    UPDATE invite_code
+    SET uses = uses + 1
+    WHERE code = p_user.invite_code
+      AND uses < max_uses
+      AND (expires_at IS NULL OR expires_at > CURRENT_TIMESTAMP)
+    RETURNING user_id INTO v_invited_by_user_id;
+
+    IF NOT FOUND THEN
+      RETURN NULL;
+    END IF;
+  END IF;
+
+  -- Insert user
+  INSERT INTO "user" (
+    name,
+    password_hash,
+    rank,
+    invited_by_user_id
+  )
+  SELECT
+    p_user.name, -- name
+    p_user.password_hash, -- password_hash
+    v_rank,
+    v_invited_by_user_id
+  RETURNING * INTO v_user;
+
+  RETURN v_user;
+END;
+$BODY$;
+
+-- Create create_invite_code function
+CREATE FUNCTION create_invite_code(
+  IN p_invite_code new_invite_code,
+  IN p_user_id integer
+)
+RETURNS invite_code
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_invite_code invite_code;
+BEGIN
+  -- Insert invite code
+  INSERT INTO invite_code (
+    user_id,
+    code,
+    max_uses,
+    expires_at
+  )
+  SELECT
+    p_user_id, -- user_id
+    p_invite_code.code, -- code
+    p_invite_code.max_uses, -- max_uses
+    p_invite_code.expires_at -- expires_at
+  RETURNING * INTO v_invite_code;
+
+  RETURN v_invite_code;
+END;
+$BODY$;
+
+-- Create delete_invite_code function
+CREATE FUNCTION delete_invite_code(
+  IN p_invite_code_id integer,
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_success boolean;
+BEGIN
+  -- Users can delete their own invite codes,
+  -- and admins can delete any invite code.
+  DELETE FROM invite_code
+  WHERE id = p_invite_code_id
+    AND (user_id = p_user_id OR (SELECT rank FROM "user" WHERE id = p_user_id) > 0)
+  RETURNING true INTO v_success;
+
+  RETURN COALESCE(v_success, false);
+END;
+$BODY$;
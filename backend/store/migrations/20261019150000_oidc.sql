@@ -0,0 +1,60 @@
+---- TABLES ----
+
+-- Users created through OpenID Connect have no password
+ALTER TABLE "user"
+  ALTER COLUMN password_hash DROP NOT NULL;
+
+-- Create user_oidc_identity table
+CREATE TABLE user_oidc_identity
+(
+  issuer text NOT NULL,
+  subject text NOT NULL,
+  created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+  user_id integer NOT NULL,
+
+  PRIMARY KEY (issuer, subject),
+
+  FOREIGN KEY (user_id)
+    REFERENCES "user" (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID
+);
+
+---- INDEXES ----
+
+CREATE INDEX user_oidc_identity_user_id_idx ON user_oidc_identity
+  USING btree
+  (user_id ASC NULLS LAST);
+
+---- FUNCTIONS ----
+
+-- Create create_oidc_user function
+-- Returns NULL if the name is already taken, or the identity has already been linked to a user,
+-- which can happen if another login created a user at the same time.
+CREATE FUNCTION create_oidc_user(
+  IN p_user new_user,
+  IN p_issuer text,
+  IN p_subject text
+)
+RETURNS "user"
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_user "user";
+BEGIN
+  -- Users of the provider are not invited
+  v_user := create_user(p_user, false);
+
+  -- Link user to the provider identity
+  INSERT INTO user_oidc_identity (issuer, subject, user_id)
+  VALUES (p_issuer, p_subject, v_user.id);
+
+  RETURN v_user;
+EXCEPTION
+  WHEN unique_violation THEN
+    RETURN NULL;
+END;
+$BODY$;
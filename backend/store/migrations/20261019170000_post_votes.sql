@@ -0,0 +1,617 @@
+---- DROP OLD ----
+
+DROP FUNCTION calculate_pages;
+DROP FUNCTION calculate_pages_reverse;
+DROP FUNCTION calculate_last_page;
+DROP FUNCTION get_view_posts;
+DROP FUNCTION search_posts;
+DROP FUNCTION search_sort_key;
+DROP VIEW view_post;
+
+---- TABLES ----
+
+-- Add score column to post
+ALTER TABLE post
+  ADD COLUMN score integer NOT NULL DEFAULT 0;
+
+-- Create post_vote table
+CREATE TABLE post_vote
+(
+  user_id integer NOT NULL,
+  post_id integer NOT NULL,
+  created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  score smallint NOT NULL,
+
+  PRIMARY KEY (user_id, post_id),
+
+  CHECK (score IN (-1, 1)),
+
+  FOREIGN KEY (user_id)
+    REFERENCES "user" (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID,
+
+  FOREIGN KEY (post_id)
+    REFERENCES post (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID
+);
+
+SELECT manage_updated_at('post_vote'); -- Automatically manage updated_at
+
+---- INDEXES ----
+
+CREATE INDEX post_vote_post_id_idx ON post_vote
+  USING btree
+  (post_id ASC NULLS LAST);
+
+CREATE INDEX post_score_idx ON post
+  USING btree
+  (score DESC NULLS LAST, id DESC NULLS LAST);
+
+---- TYPES ----
+
+-- Create search_options type
+-- Options for searches using metatags
+CREATE TYPE search_options AS
+(
+  fav_user_name text,
+  min_score integer,
+  max_score integer,
+  sort text
+);
+
+---- VIEWS ----
+
+-- Create view_post view
+CREATE VIEW view_post
+AS
+SELECT
+  p.id,
+  p.created_at,
+  p.user_id,
+  u.name AS user_name,
+  p.title,
+  p.description,
+  p.source,
+  p.filename,
+  p.size,
+  p.width,
+  p.height,
+  p.hash,
+  p.ext,
+  p.tn_ext,
+  p.tags,
+  p.fav_count,
+  p.score
+FROM post AS p
+JOIN "user" AS u ON u.id = p.user_id
+WHERE NOT is_deleted;
+
+---- FUNCTIONS ----
+
+-- Create vote_post function
+CREATE FUNCTION vote_post(
+  IN p_post_id integer,
+  IN p_user_id integer,
+  IN p_score integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_old_score integer;
+BEGIN
+  -- Lock post, so that concurrent votes are counted correctly
+  PERFORM 1 FROM post WHERE id = p_post_id AND NOT is_deleted FOR UPDATE;
+  IF NOT FOUND THEN
+    RETURN false;
+  END IF;
+
+  SELECT score INTO v_old_score
+  FROM post_vote
+  WHERE user_id = p_user_id
+    AND post_id = p_post_id;
+
+  INSERT INTO post_vote (user_id, post_id, score)
+  VALUES (p_user_id, p_post_id, p_score)
+  ON CONFLICT (user_id, post_id) DO UPDATE
+  SET score = EXCLUDED.score;
+
+  -- Replace any previous vote by the user
+  UPDATE post
+  SET score = score + p_score - COALESCE(v_old_score, 0)
+  WHERE id = p_post_id;
+
+  RETURN true;
+END;
+$BODY$;
+
+-- Create unvote_post function
+CREATE FUNCTION unvote_post(
+  IN p_post_id integer,
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_old_score integer;
+BEGIN
+  -- Lock post, so that concurrent votes are counted correctly
+  PERFORM 1 FROM post WHERE id = p_post_id AND NOT is_deleted FOR UPDATE;
+  IF NOT FOUND THEN
+    RETURN false;
+  END IF;
+
+  DELETE FROM post_vote
+  WHERE user_id = p_user_id
+    AND post_id = p_post_id
+  RETURNING score INTO v_old_score;
+
+  IF v_old_score IS NOT NULL THEN
+    UPDATE post
+    SET score = score - v_old_score
+    WHERE id = p_post_id;
+  END IF;
+
+  RETURN true;
+END;
+$BODY$;
+
+-- Create is_cached_search function
+-- Searches without metatags use the search cache.
+CREATE FUNCTION is_cached_search(
+  IN p_options search_options
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN p_options.fav_user_name IS NULL
+    AND p_options.min_score IS NULL
+    AND p_options.max_score IS NULL
+    AND COALESCE(p_options.sort, 'id') = 'id';
+END;
+$BODY$ IMMUTABLE;
+
+-- Create search_posts function
+-- Returns the posts matching a search, along with the key they are sorted by.
+-- Used for searches with metatags, which are not cached.
+CREATE FUNCTION search_posts(
+  IN p_tag_ids integer[],
+  IN p_exclude_tag_ids integer[],
+  IN p_options search_options
+)
+RETURNS TABLE (
+  post_id integer,
+  sort_key integer
+)
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  SELECT
+    ptic.post_id,
+    (CASE p_options.sort
+      WHEN 'fav_count' THEN p.fav_count
+      WHEN 'score' THEN p.score
+      ELSE 0
+      END) AS sort_key
+  FROM post_tag_id_cache AS ptic
+  JOIN post AS p ON p.id = ptic.post_id
+  WHERE
+    -- Posts with fewer tags than the required tags cannot qualify
+    icount(ptic.tag_ids) >= icount(p_tag_ids)
+    -- Post must have all the included tags
+    AND ptic.tag_ids @> p_tag_ids
+    -- Post must not have any of the excluded tags
+    AND NOT ptic.tag_ids && p_exclude_tag_ids
+    -- Post must be favorited by the user, if specified
+    AND (p_options.fav_user_name IS NULL OR EXISTS (
+      SELECT 1
+      FROM post_favorite AS pf
+      JOIN "user" AS u ON u.id = pf.user_id
+      WHERE pf.post_id = ptic.post_id
+        AND u.name = p_options.fav_user_name
+    ))
+    -- Post score must be within the range, if specified
+    AND (p_options.min_score IS NULL OR p.score >= p_options.min_score)
+    AND (p_options.max_score IS NULL OR p.score <= p_options.max_score);
+END;
+$BODY$ STABLE;
+
+-- Create search_sort_key function
+-- Get the sort key of the post a search page starts at.
+-- If the post does not exist, the search starts from the beginning.
+CREATE FUNCTION search_sort_key(
+  IN p_post_id integer,
+  IN p_sort text
+)
+RETURNS integer
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  IF p_sort = 'fav_count' THEN
+    RETURN COALESCE((SELECT fav_count FROM post WHERE id = p_post_id), 2147483647);
+  END IF;
+
+  IF p_sort = 'score' THEN
+    RETURN COALESCE((SELECT score FROM post WHERE id = p_post_id), 2147483647);
+  END IF;
+
+  RETURN 0;
+END;
+$BODY$ STABLE;
+
+-- Create get_view_posts function
+CREATE FUNCTION get_view_posts(
+  IN p_include_tags text[],
+  IN p_exclude_tags text[],
+  IN p_options search_options,
+  IN p_start_id integer,
+  IN p_limit integer
+)
+RETURNS SETOF view_post
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_tag_ids integer[];
+  v_exclude_tag_ids integer[];
+  v_valid boolean;
+BEGIN
+  SELECT * INTO v_tag_ids, v_exclude_tag_ids, v_valid FROM resolve_search_tags(p_include_tags, p_exclude_tags);
+  IF NOT v_valid THEN
+    RETURN;
+  END IF;
+
+  IF NOT is_cached_search(p_options) THEN
+    RETURN QUERY
+    SELECT p.*
+    FROM search_posts(v_tag_ids, v_exclude_tag_ids, p_options) AS s
+    JOIN view_post AS p ON p.id = s.post_id
+    WHERE
+      -- Only scan forward from the origin
+      (s.sort_key, s.post_id) <= (search_sort_key(p_start_id, p_options.sort), p_start_id)
+    ORDER BY s.sort_key DESC, s.post_id DESC
+    LIMIT p_limit;
+
+    RETURN;
+  END IF;
+
+  RETURN QUERY
+  SELECT p.*
+  FROM post_tag_id_cache AS ptic
+  JOIN view_post AS p ON p.id = ptic.post_id
+  WHERE
+    -- Only scan forward from the origin
+    ptic.post_id <= p_start_id
+    -- Posts with fewer tags than the required tags cannot qualify
+    AND icount(ptic.tag_ids) >= icount(v_tag_ids)
+    -- Post must have all the included tags
+    AND ptic.tag_ids @> v_tag_ids
+    -- Post must not have any of the excluded tags
+    AND NOT ptic.tag_ids && v_exclude_tag_ids
+  ORDER BY ptic.post_id DESC
+  LIMIT p_limit;
+END;
+$BODY$ STABLE;
+
+-- Create calculate_pages function
+-- Calculate the starting IDs of a range of pages,
+-- optionally starting from an already known page.
+CREATE FUNCTION calculate_pages(
+  IN p_include_tags text[],
+  IN p_exclude_tags text[],
+  IN p_options search_options,
+  IN p_posts_per_page integer,
+  IN p_page_count integer,
+  IN p_origin_page page_info
+)
+RETURNS page_info[]
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_pages page_info[];
+  v_tag_ids integer[];
+  v_exclude_tag_ids integer[];
+  v_valid boolean;
+  v_post_count integer;
+  v_start_id integer;
+  v_last_id integer;
+BEGIN
+  SELECT * INTO v_tag_ids, v_exclude_tag_ids, v_valid FROM resolve_search_tags(p_include_tags, p_exclude_tags);
+  IF NOT v_valid THEN
+    RETURN v_pages;
+  END IF;
+
+  IF NOT is_cached_search(p_options) THEN
+    v_start_id := COALESCE(p_origin_page.start_id, 2147483647);
+
+    v_pages := array(
+      SELECT (no, start_id)::page_info
+      FROM (
+        SELECT
+          COALESCE(p_origin_page.no, 1) + ROW_NUMBER() OVER () - 1 AS no,
+          x.id AS start_id
+        FROM (
+          SELECT
+            s.post_id AS id,
+            ROW_NUMBER() OVER (ORDER BY s.sort_key DESC, s.post_id DESC) AS rn
+          FROM search_posts(v_tag_ids, v_exclude_tag_ids, p_options) AS s
+          WHERE
+            -- Only scan forward from start ID
+            (s.sort_key, s.post_id) <= (search_sort_key(v_start_id, p_options.sort), v_start_id)
+          ORDER BY s.sort_key DESC, s.post_id DESC
+          LIMIT p_page_count * p_posts_per_page -- X pages at a time
+        ) AS x
+        WHERE MOD(x.rn - 1, p_posts_per_page) = 0
+      ) AS x
+      WHERE x.no > COALESCE(p_origin_page.no, 0)
+    );
+
+    RETURN v_pages;
+  END IF;
+
+  -- Make sure search cache is initialized
+  PERFORM initialize_search_cache(v_tag_ids, v_exclude_tag_ids);
+
+  SELECT post_count, first_post_id, last_page_post_ids[1]
+  INTO v_post_count, v_start_id, v_last_id
+  FROM search_cache
+  WHERE tag_ids = v_tag_ids
+    AND exclude_tag_ids = v_exclude_tag_ids;
+
+  -- If no posts exist in the search, return.
+  IF v_start_id IS NULL THEN
+    RETURN v_pages;
+  END IF;
+
+  IF p_origin_page.start_id IS NOT NULL THEN
+    v_start_id := p_origin_page.start_id;
+  END IF;
+
+  v_pages := array(
+    SELECT (no, start_id)::page_info
+    FROM (
+      SELECT
+        COALESCE(p_origin_page.no, 1) + ROW_NUMBER() OVER () - 1 AS no,
+        x.id AS start_id
+      FROM (
+        SELECT
+          ptic.post_id AS id,
+          ROW_NUMBER() OVER (ORDER BY ptic.post_id DESC) AS rn
+        FROM post_tag_id_cache AS ptic
+        WHERE
+          -- Only scan forward from start ID
+          ptic.post_id <= v_start_id
+          -- Post must have all the included tags
+          AND ptic.tag_ids @> v_tag_ids
+          -- Post must not have any of the excluded tags
+          AND NOT ptic.tag_ids && v_exclude_tag_ids
+        ORDER BY ptic.post_id DESC
+        LIMIT LEAST(p_page_count * p_posts_per_page, v_post_count) -- X pages at a time
+      ) AS x
+      WHERE MOD(x.rn - 1, p_posts_per_page) = 0
+    ) AS x
+    WHERE x.no > COALESCE(p_origin_page.no, 0)
+  );
+
+  RETURN v_pages;
+END;
+$BODY$;
+
+-- Create calculate_pages_reverse function
+-- Like calculate_pages, but in reverse.
+-- (Calculates previous pages)
+CREATE FUNCTION calculate_pages_reverse(
+  IN p_include_tags text[],
+  IN p_exclude_tags text[],
+  IN p_options search_options,
+  IN p_posts_per_page integer,
+  IN p_page_count integer,
+  IN p_origin_page page_info
+)
+RETURNS page_info[]
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_pages page_info[];
+  v_tag_ids integer[];
+  v_exclude_tag_ids integer[];
+  v_valid boolean;
+  v_start_id integer;
+  v_last_id integer;
+BEGIN
+  SELECT * INTO v_tag_ids, v_exclude_tag_ids, v_valid FROM resolve_search_tags(p_include_tags, p_exclude_tags);
+  IF NOT v_valid THEN
+    RETURN v_pages;
+  END IF;
+
+  IF NOT is_cached_search(p_options) THEN
+    -- Previous pages can only be calculated from a known page
+    IF p_origin_page.start_id IS NULL THEN
+      RETURN v_pages;
+    END IF;
+
+    v_last_id := p_origin_page.start_id;
+
+    v_pages := array(
+      SELECT (no, start_id)::page_info
+      FROM (
+        SELECT
+          p_origin_page.no - ROW_NUMBER() OVER () + 1 AS no,
+          x.id AS start_id
+        FROM (
+          SELECT
+            s.post_id AS id,
+            ROW_NUMBER() OVER (ORDER BY s.sort_key ASC, s.post_id ASC) AS rn
+          FROM search_posts(v_tag_ids, v_exclude_tag_ids, p_options) AS s
+          WHERE
+            -- Only scan backwards from the origin
+            (s.sort_key, s.post_id) >= (search_sort_key(v_last_id, p_options.sort), v_last_id)
+          ORDER BY s.sort_key ASC, s.post_id ASC
+          LIMIT ((p_page_count + 1) * p_posts_per_page) -- X pages at a time
+        ) AS x
+        WHERE MOD(x.rn - 1, p_posts_per_page) = 0
+      ) AS x
+      WHERE x.no < p_origin_page.no
+    );
+
+    RETURN v_pages;
+  END IF;
+
+  -- Make sure search cache is initialized
+  PERFORM initialize_search_cache(v_tag_ids, v_exclude_tag_ids);
+
+  SELECT first_post_id, last_page_post_ids[1]
+  INTO v_start_id, v_last_id
+  FROM search_cache
+  WHERE tag_ids = v_tag_ids
+    AND exclude_tag_ids = v_exclude_tag_ids;
+
+  -- If no posts exist in the search, return.
+  IF v_start_id IS NULL THEN
+    RETURN v_pages;
+  END IF;
+
+  IF p_origin_page.start_id IS NOT NULL THEN
+    v_last_id := p_origin_page.start_id;
+  END IF;
+
+  v_pages := array(
+    SELECT (no, start_id)::page_info
+    FROM (
+      SELECT
+        COALESCE(p_origin_page.no, 0) - ROW_NUMBER() OVER () + 1 AS no,
+        x.id AS start_id
+      FROM (
+        SELECT
+          ptic.post_id AS id,
+          ROW_NUMBER() OVER (ORDER BY ptic.post_id ASC) AS rn
+        FROM post_tag_id_cache AS ptic
+        WHERE
+          -- Only scan backwards from the origin
+          ptic.post_id >= v_last_id
+          -- Post must have all the included tags
+          AND ptic.tag_ids @> v_tag_ids
+          -- Post must not have any of the excluded tags
+          AND NOT ptic.tag_ids && v_exclude_tag_ids
+        ORDER BY ptic.post_id ASC
+        LIMIT ((p_page_count + 1) * p_posts_per_page) -- X pages at a time
+      ) AS x
+      WHERE MOD(x.rn - 1, p_posts_per_page) = 0
+    ) AS x
+    WHERE x.no < p_origin_page.no
+  );
+
+  RETURN v_pages;
+END;
+$BODY$;
+
+-- Create calculate_last_page function
+CREATE FUNCTION calculate_last_page(
+  IN p_include_tags text[],
+  IN p_exclude_tags text[],
+  IN p_options search_options,
+  IN p_posts_per_page integer
+)
+RETURNS page_info
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_tag_ids integer[];
+  v_exclude_tag_ids integer[];
+  v_valid boolean;
+  v_post_count integer;
+  v_page_count integer;
+  v_last_page_start_id integer;
+  v_last_page_post_ids integer[];
+BEGIN
+  SELECT * INTO v_tag_ids, v_exclude_tag_ids, v_valid FROM resolve_search_tags(p_include_tags, p_exclude_tags);
+  IF NOT v_valid THEN
+    RETURN (1, 0)::page_info;
+  END IF;
+
+  IF NOT is_cached_search(p_options) THEN
+    SELECT COUNT(*)::integer INTO v_post_count
+    FROM search_posts(v_tag_ids, v_exclude_tag_ids, p_options);
+
+    IF v_post_count = 0 THEN
+      RETURN (1, 0)::page_info;
+    END IF;
+
+    v_page_count := CEIL(v_post_count::real / p_posts_per_page);
+
+    SELECT s.post_id INTO v_last_page_start_id
+    FROM search_posts(v_tag_ids, v_exclude_tag_ids, p_options) AS s
+    ORDER BY s.sort_key DESC, s.post_id DESC
+    OFFSET (v_page_count - 1) * p_posts_per_page
+    LIMIT 1;
+
+    RETURN (v_page_count, v_last_page_start_id)::page_info;
+  END IF;
+
+  -- Make sure search cache is initialized
+  PERFORM initialize_search_cache(v_tag_ids, v_exclude_tag_ids);
+
+  -- Try to get cached search info
+  SELECT post_count, last_page_post_ids
+  INTO v_post_count, v_last_page_post_ids
+  FROM search_cache
+  WHERE tag_ids = v_tag_ids
+    AND exclude_tag_ids = v_exclude_tag_ids;
+
+  -- If no posts exist in the search, return.
+  IF v_post_count IS NULL THEN
+    RETURN (1, 0)::page_info;
+  END IF;
+
+  -- Calculate page count
+  v_page_count := CEIL(v_post_count::real / p_posts_per_page);
+
+  -- Calculate number of posts currently on last page
+  v_post_count := MOD(v_post_count, p_posts_per_page);
+
+  -- If necessary, get additional last page posts
+  IF icount(v_last_page_post_ids) < v_post_count THEN
+    v_last_page_post_ids := v_last_page_post_ids | array(
+      SELECT ptic.post_id
+      FROM post_tag_id_cache AS ptic
+      WHERE
+        ptic.post_id > (SELECT COALESCE(MAX(id), 0) FROM unnest(v_last_page_post_ids) AS id)
+        -- Posts with fewer tags than the required tags cannot qualify
+        AND icount(ptic.tag_ids) >= icount(v_tag_ids)
+        -- Post must have all the included tags
+        AND ptic.tag_ids @> v_tag_ids
+        -- Post must not have any of the excluded tags
+        AND NOT ptic.tag_ids && v_exclude_tag_ids
+      ORDER BY ptic.post_id ASC
+      LIMIT p_posts_per_page - icount(v_last_page_post_ids)
+    );
+
+    -- Update search cache with posts
+    UPDATE search_cache
+    SET last_page_post_ids = v_last_page_post_ids
+    WHERE tag_ids = v_tag_ids
+      AND exclude_tag_ids = v_exclude_tag_ids;
+  END IF;
+
+  -- Get last page start ID
+  v_last_page_start_id := v_last_page_post_ids[v_post_count];
+
+  RETURN (v_page_count, v_last_page_start_id)::page_info;
+END;
+$BODY$;
@@ -0,0 +1,369 @@
+---- DROP OLD ----
+
+DROP FUNCTION search_posts;
+DROP FUNCTION is_cached_search;
+
+---- TABLES ----
+
+-- Create pool table
+CREATE TABLE pool
+(
+  id serial NOT NULL,
+  created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  user_id integer NOT NULL,
+  name text NOT NULL,
+  description text,
+
+  PRIMARY KEY (id),
+
+  FOREIGN KEY (user_id)
+    REFERENCES "user" (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID
+);
+
+SELECT manage_updated_at('pool'); -- Automatically manage updated_at
+
+-- Create pool_post table
+CREATE TABLE pool_post
+(
+  pool_id integer NOT NULL,
+  post_id integer NOT NULL,
+  position integer NOT NULL,
+
+  PRIMARY KEY (pool_id, post_id),
+
+  FOREIGN KEY (pool_id)
+    REFERENCES pool (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID,
+
+  FOREIGN KEY (post_id)
+    REFERENCES post (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID
+);
+
+---- INDEXES ----
+
+CREATE INDEX pool_post_post_id_idx ON pool_post
+  USING btree
+  (post_id ASC NULLS LAST);
+
+CREATE INDEX pool_post_position_idx ON pool_post
+  USING btree
+  (pool_id ASC NULLS LAST, position ASC NULLS LAST);
+
+---- TYPES ----
+
+-- Add pool to search options
+ALTER TYPE search_options
+  ADD ATTRIBUTE pool_id integer;
+
+-- Create new_pool type
+-- created_at is used to preserve the creation time of imported pools.
+CREATE TYPE new_pool AS (
+  user_id integer,
+  name text,
+  description text,
+  post_ids integer[],
+  created_at timestamp with time zone
+);
+
+-- Create update_pool type
+CREATE TYPE update_pool AS (
+  name text,
+  description text,
+  post_ids integer[]
+);
+
+---- VIEWS ----
+
+-- Create view_pool view
+CREATE VIEW view_pool
+AS
+SELECT
+  pl.id,
+  pl.created_at,
+  pl.updated_at,
+  pl.user_id,
+  u.name AS user_name,
+  pl.name,
+  pl.description,
+  array(
+    SELECT pp.post_id
+    FROM pool_post AS pp
+    JOIN post AS p ON p.id = pp.post_id
+    WHERE pp.pool_id = pl.id
+      AND NOT p.is_deleted
+    ORDER BY pp.position ASC
+  ) AS post_ids
+FROM pool AS pl
+JOIN "user" AS u ON u.id = pl.user_id;
+
+-- Create view_export_pool view
+-- Posts are identified by their hash, as post IDs are not preserved by export and import.
+CREATE VIEW view_export_pool
+AS
+SELECT
+  vp.id,
+  vp.created_at,
+  vp.user_name,
+  vp.name,
+  vp.description,
+  array(
+    SELECT p.hash
+    FROM unnest(vp.post_ids) WITH ORDINALITY AS pid(post_id, position)
+    JOIN post AS p ON p.id = pid.post_id
+    ORDER BY pid.position ASC
+  ) AS post_hashes
+FROM view_pool AS vp;
+
+---- FUNCTIONS ----
+
+-- Create can_user_edit_pool function
+CREATE FUNCTION can_user_edit_pool(
+  IN p_pool_id integer,
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  -- If user is some sort of admin, allow edit
+  IF (SELECT rank FROM "user" WHERE id = p_user_id) > 0 THEN
+    RETURN true;
+  END IF;
+
+  -- If user is the creator of the pool, allow edit
+  IF p_user_id = (SELECT user_id FROM pool WHERE id = p_pool_id) THEN
+    RETURN true;
+  END IF;
+
+  RETURN false;
+END;
+$BODY$;
+
+-- Create set_pool_posts function
+-- Replace the posts in a pool, keeping the order they are specified in.
+-- Duplicate and non-existing posts are ignored.
+CREATE FUNCTION set_pool_posts(
+  IN p_pool_id integer,
+  IN p_post_ids integer[]
+)
+RETURNS VOID
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  DELETE FROM pool_post
+  WHERE pool_id = p_pool_id;
+
+  INSERT INTO pool_post (pool_id, post_id, position)
+  SELECT p_pool_id, x.post_id, ROW_NUMBER() OVER (ORDER BY x.position)
+  FROM (
+    SELECT DISTINCT ON (pid.post_id) pid.post_id, pid.position
+    FROM unnest(p_post_ids) WITH ORDINALITY AS pid(post_id, position)
+    ORDER BY pid.post_id, pid.position
+  ) AS x
+  WHERE EXISTS (SELECT 1 FROM post WHERE id = x.post_id AND NOT is_deleted);
+END;
+$BODY$;
+
+-- Create create_pool function
+CREATE FUNCTION create_pool(
+  IN p_pool new_pool
+)
+RETURNS integer
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_pool_id integer;
+BEGIN
+  INSERT INTO pool (user_id, name, description, created_at)
+  VALUES (p_pool.user_id, p_pool.name, p_pool.description, COALESCE(p_pool.created_at, CURRENT_TIMESTAMP))
+  RETURNING id INTO v_pool_id;
+
+  PERFORM set_pool_posts(v_pool_id, p_pool.post_ids);
+
+  RETURN v_pool_id;
+END;
+$BODY$;
+
+-- Create update_pool function
+-- Returns false if the pool does not exist,
+-- as admins are allowed to edit any pool.
+CREATE FUNCTION update_pool(
+  IN p_pool_id integer,
+  IN p_update_pool update_pool,
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  IF NOT can_user_edit_pool(p_pool_id, p_user_id) THEN
+    RETURN false;
+  END IF;
+
+  UPDATE pool
+  SET name = p_update_pool.name,
+      description = p_update_pool.description
+  WHERE id = p_pool_id;
+
+  IF NOT FOUND THEN
+    RETURN false;
+  END IF;
+
+  PERFORM set_pool_posts(p_pool_id, p_update_pool.post_ids);
+
+  RETURN true;
+END;
+$BODY$;
+
+-- Create delete_pool function
+-- Returns false if the pool does not exist.
+CREATE FUNCTION delete_pool(
+  IN p_pool_id integer,
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  IF NOT can_user_edit_pool(p_pool_id, p_user_id) THEN
+    RETURN false;
+  END IF;
+
+  DELETE FROM pool
+  WHERE id = p_pool_id;
+
+  RETURN FOUND;
+END;
+$BODY$;
+
+-- Create get_post_pools function
+-- Get the pools a post is in, along with the previous and next posts in each pool.
+CREATE FUNCTION get_post_pools(
+  IN p_post_id integer
+)
+RETURNS TABLE (
+  id integer,
+  name text,
+  previous_post_id integer,
+  next_post_id integer
+)
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  SELECT
+    pl.id,
+    pl.name,
+    (
+      SELECT ppp.post_id
+      FROM pool_post AS ppp
+      JOIN post AS p ON p.id = ppp.post_id
+      WHERE ppp.pool_id = pl.id
+        AND ppp.position < pp.position
+        AND NOT p.is_deleted
+      ORDER BY ppp.position DESC
+      LIMIT 1
+    ) AS previous_post_id,
+    (
+      SELECT npp.post_id
+      FROM pool_post AS npp
+      JOIN post AS p ON p.id = npp.post_id
+      WHERE npp.pool_id = pl.id
+        AND npp.position > pp.position
+        AND NOT p.is_deleted
+      ORDER BY npp.position ASC
+      LIMIT 1
+    ) AS next_post_id
+  FROM pool_post AS pp
+  JOIN pool AS pl ON pl.id = pp.pool_id
+  WHERE pp.post_id = p_post_id
+  ORDER BY pl.id ASC;
+END;
+$BODY$ STABLE;
+
+-- Create is_cached_search function
+-- Searches without metatags use the search cache.
+CREATE FUNCTION is_cached_search(
+  IN p_options search_options
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN p_options.fav_user_name IS NULL
+    AND p_options.min_score IS NULL
+    AND p_options.max_score IS NULL
+    AND p_options.pool_id IS NULL
+    AND COALESCE(p_options.sort, 'id') = 'id';
+END;
+$BODY$ IMMUTABLE;
+
+-- Create search_posts function
+-- Returns the posts matching a search, along with the key they are sorted by.
+-- Used for searches with metatags, which are not cached.
+CREATE FUNCTION search_posts(
+  IN p_tag_ids integer[],
+  IN p_exclude_tag_ids integer[],
+  IN p_options search_options
+)
+RETURNS TABLE (
+  post_id integer,
+  sort_key integer
+)
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  SELECT
+    ptic.post_id,
+    (CASE p_options.sort
+      WHEN 'fav_count' THEN p.fav_count
+      WHEN 'score' THEN p.score
+      ELSE 0
+      END) AS sort_key
+  FROM post_tag_id_cache AS ptic
+  JOIN post AS p ON p.id = ptic.post_id
+  WHERE
+    -- Posts with fewer tags than the required tags cannot qualify
+    icount(ptic.tag_ids) >= icount(p_tag_ids)
+    -- Post must have all the included tags
+    AND ptic.tag_ids @> p_tag_ids
+    -- Post must not have any of the excluded tags
+    AND NOT ptic.tag_ids && p_exclude_tag_ids
+    -- Post must be favorited by the user, if specified
+    AND (p_options.fav_user_name IS NULL OR EXISTS (
+      SELECT 1
+      FROM post_favorite AS pf
+      JOIN "user" AS u ON u.id = pf.user_id
+      WHERE pf.post_id = ptic.post_id
+        AND u.name = p_options.fav_user_name
+    ))
+    -- Post score must be within the range, if specified
+    AND (p_options.min_score IS NULL OR p.score >= p_options.min_score)
+    AND (p_options.max_score IS NULL OR p.score <= p_options.max_score)
+    -- Post must be in the pool, if specified
+    AND (p_options.pool_id IS NULL OR EXISTS (
+      SELECT 1
+      FROM pool_post AS pp
+      WHERE pp.post_id = ptic.post_id
+        AND pp.pool_id = p_options.pool_id
+    ));
+END;
+$BODY$ STABLE;
@@ -0,0 +1,359 @@
+---- DROP OLD ----
+
+DROP FUNCTION get_view_posts;
+DROP FUNCTION search_posts;
+DROP FUNCTION is_cached_search;
+DROP FUNCTION create_post;
+DROP FUNCTION update_post;
+DROP VIEW view_post;
+
+---- TABLES ----
+
+-- Add parent_id column to post
+ALTER TABLE post
+  ADD COLUMN parent_id integer,
+  ADD FOREIGN KEY (parent_id)
+    REFERENCES post (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE SET NULL
+    NOT VALID;
+
+---- INDEXES ----
+
+CREATE INDEX post_parent_id_idx ON post
+  USING btree
+  (parent_id ASC NULLS LAST);
+
+---- TYPES ----
+
+-- Add parent to new_post
+ALTER TYPE new_post
+  ADD ATTRIBUTE parent_id integer;
+
+-- Add parent to update_post.
+-- The parent is only changed if update_parent is true.
+ALTER TYPE update_post
+  ADD ATTRIBUTE parent_id integer,
+  ADD ATTRIBUTE update_parent boolean;
+
+-- Add parent and child filters to search options
+ALTER TYPE search_options
+  ADD ATTRIBUTE parent_id integer,
+  ADD ATTRIBUTE has_parent boolean,
+  ADD ATTRIBUTE child_id integer,
+  ADD ATTRIBUTE has_children boolean;
+
+---- VIEWS ----
+
+-- Create view_post view
+CREATE VIEW view_post
+AS
+SELECT
+  p.id,
+  p.created_at,
+  p.user_id,
+  u.name AS user_name,
+  p.title,
+  p.description,
+  p.source,
+  p.filename,
+  p.size,
+  p.width,
+  p.height,
+  p.hash,
+  p.ext,
+  p.tn_ext,
+  p.tags,
+  p.fav_count,
+  p.score,
+  p.parent_id
+FROM post AS p
+JOIN "user" AS u ON u.id = p.user_id
+WHERE NOT is_deleted;
+
+---- FUNCTIONS ----
+
+-- Create is_valid_post_parent function
+-- Check whether a post can be made the parent of another post.
+-- The parent must exist, and must not be the post itself or one of its descendants.
+-- The ancestors of the parent are locked, so that concurrent parent changes
+-- can not create a cycle between them.
+CREATE FUNCTION is_valid_post_parent(
+  IN p_post_id integer,
+  IN p_parent_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_ancestor_id integer := p_parent_id;
+  v_visited_ids integer[] := '{}';
+BEGIN
+  -- Having no parent is always valid
+  IF p_parent_id IS NULL THEN
+    RETURN true;
+  END IF;
+
+  IF NOT EXISTS (SELECT 1 FROM post WHERE id = p_parent_id AND NOT is_deleted FOR UPDATE) THEN
+    RETURN false;
+  END IF;
+
+  -- Walk up from the new parent, to make sure the post is not one of its ancestors
+  WHILE v_ancestor_id IS NOT NULL AND NOT v_ancestor_id = ANY(v_visited_ids) LOOP
+    IF v_ancestor_id = p_post_id THEN
+      RETURN false;
+    END IF;
+
+    v_visited_ids := v_visited_ids || v_ancestor_id;
+
+    SELECT parent_id INTO v_ancestor_id
+    FROM post
+    WHERE id = v_ancestor_id
+    FOR UPDATE;
+  END LOOP;
+
+  RETURN true;
+END;
+$BODY$;
+
+-- Create create_post function
+-- Returns NULL if the parent post is not valid.
+CREATE FUNCTION create_post(
+  IN p_post new_post,
+  IN p_tags text[]
+)
+RETURNS integer
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_post_id integer;
+BEGIN
+  IF NOT is_valid_post_parent(NULL, p_post.parent_id) THEN
+    RETURN NULL;
+  END IF;
+
+  -- Insert post
+  INSERT INTO post (
+    user_id,
+    title,
+    description,
+    source,
+    filename,
+    size,
+    width,
+    height,
+    hash,
+    ext,
+    tn_ext,
+    parent_id
+  )
+  SELECT
+    p_post.user_id, -- user_id
+    p_post.title, -- title
+    p_post.description, -- description
+    p_post.source, -- source
+    p_post.filename, -- filename
+    p_post.size, -- size
+    p_post.width, -- width
+    p_post.height, -- height
+    p_post.hash, -- hash
+    p_post.ext, -- ext
+    p_post.tn_ext, -- tn_ext
+    p_post.parent_id -- parent_id
+  RETURNING id INTO v_post_id;
+
+  -- Create post_tag_id_cache
+  INSERT INTO post_tag_id_cache (post_id) VALUES (v_post_id);
+
+  -- Add post tags
+  PERFORM update_post_tags(v_post_id, p_tags, '{}', p_post.user_id, true);
+
+  RETURN v_post_id;
+END;
+$BODY$;
+
+-- Create update_post function
+-- Returns false if the post does not exist or belongs to another user,
+-- and NULL if the parent post is not valid.
+CREATE FUNCTION update_post(
+  IN p_update_post update_post,
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  -- Lock the post, so that its parent can not be changed concurrently
+  PERFORM 1
+  FROM post
+  WHERE id = p_update_post.id
+    AND user_id = p_user_id
+  FOR UPDATE;
+
+  IF NOT FOUND THEN
+    RETURN false;
+  END IF;
+
+  IF p_update_post.update_parent AND NOT is_valid_post_parent(p_update_post.id, p_update_post.parent_id) THEN
+    RETURN NULL;
+  END IF;
+
+  -- Update post
+  UPDATE post
+  SET
+    title = p_update_post.title,
+    description = p_update_post.description,
+    source = p_update_post.source,
+    parent_id = (CASE WHEN p_update_post.update_parent THEN p_update_post.parent_id ELSE parent_id END)
+  WHERE id = p_update_post.id;
+
+  -- Update post tags
+  PERFORM update_post_tags(p_update_post.id, p_update_post.add_tags, p_update_post.remove_tags, p_user_id, false);
+
+  RETURN true;
+END;
+$BODY$;
+
+-- Create is_cached_search function
+-- Searches without metatags use the search cache.
+CREATE FUNCTION is_cached_search(
+  IN p_options search_options
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN p_options.fav_user_name IS NULL
+    AND p_options.min_score IS NULL
+    AND p_options.max_score IS NULL
+    AND p_options.pool_id IS NULL
+    AND p_options.parent_id IS NULL
+    AND p_options.has_parent IS NULL
+    AND p_options.child_id IS NULL
+    AND p_options.has_children IS NULL
+    AND COALESCE(p_options.sort, 'id') = 'id';
+END;
+$BODY$ IMMUTABLE;
+
+-- Create search_posts function
+-- Returns the posts matching a search, along with the key they are sorted by.
+-- Used for searches with metatags, which are not cached.
+CREATE FUNCTION search_posts(
+  IN p_tag_ids integer[],
+  IN p_exclude_tag_ids integer[],
+  IN p_options search_options
+)
+RETURNS TABLE (
+  post_id integer,
+  sort_key integer
+)
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  SELECT
+    ptic.post_id,
+    (CASE p_options.sort
+      WHEN 'fav_count' THEN p.fav_count
+      WHEN 'score' THEN p.score
+      ELSE 0
+      END) AS sort_key
+  FROM post_tag_id_cache AS ptic
+  JOIN post AS p ON p.id = ptic.post_id
+  WHERE
+    -- Posts with fewer tags than the required tags cannot qualify
+    icount(ptic.tag_ids) >= icount(p_tag_ids)
+    -- Post must have all the included tags
+    AND ptic.tag_ids @> p_tag_ids
+    -- Post must not have any of the excluded tags
+    AND NOT ptic.tag_ids && p_exclude_tag_ids
+    -- Post must be favorited by the user, if specified
+    AND (p_options.fav_user_name IS NULL OR EXISTS (
+      SELECT 1
+      FROM post_favorite AS pf
+      JOIN "user" AS u ON u.id = pf.user_id
+      WHERE pf.post_id = ptic.post_id
+        AND u.name = p_options.fav_user_name
+    ))
+    -- Post score must be within the range, if specified
+    AND (p_options.min_score IS NULL OR p.score >= p_options.min_score)
+    AND (p_options.max_score IS NULL OR p.score <= p_options.max_score)
+    -- Post must be in the pool, if specified
+    AND (p_options.pool_id IS NULL OR EXISTS (
+      SELECT 1
+      FROM pool_post AS pp
+      WHERE pp.post_id = ptic.post_id
+        AND pp.pool_id = p_options.pool_id
+    ))
+    -- Post must be a child of the post, if specified
+    AND (p_options.parent_id IS NULL OR p.parent_id = p_options.parent_id)
+    AND (p_options.has_parent IS NULL OR (p.parent_id IS NOT NULL) = p_options.has_parent)
+    -- Post must be the parent of the post, if specified
+    AND (p_options.child_id IS NULL OR p.id = (SELECT c.parent_id FROM post AS c WHERE c.id = p_options.child_id))
+    AND (p_options.has_children IS NULL OR EXISTS (
+      SELECT 1
+      FROM post AS c
+      WHERE c.parent_id = p.id
+        AND NOT c.is_deleted
+    ) = p_options.has_children);
+END;
+$BODY$ STABLE;
+
+-- Create get_view_posts function
+CREATE FUNCTION get_view_posts(
+  IN p_include_tags text[],
+  IN p_exclude_tags text[],
+  IN p_options search_options,
+  IN p_start_id integer,
+  IN p_limit integer
+)
+RETURNS SETOF view_post
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_tag_ids integer[];
+  v_exclude_tag_ids integer[];
+  v_valid boolean;
+BEGIN
+  SELECT * INTO v_tag_ids, v_exclude_tag_ids, v_valid FROM resolve_search_tags(p_include_tags, p_exclude_tags);
+  IF NOT v_valid THEN
+    RETURN;
+  END IF;
+
+  IF NOT is_cached_search(p_options) THEN
+    RETURN QUERY
+    SELECT p.*
+    FROM search_posts(v_tag_ids, v_exclude_tag_ids, p_options) AS s
+    JOIN view_post AS p ON p.id = s.post_id
+    WHERE
+      -- Only scan forward from the origin
+      (s.sort_key, s.post_id) <= (search_sort_key(p_start_id, p_options.sort), p_start_id)
+    ORDER BY s.sort_key DESC, s.post_id DESC
+    LIMIT p_limit;
+
+    RETURN;
+  END IF;
+
+  RETURN QUERY
+  SELECT p.*
+  FROM post_tag_id_cache AS ptic
+  JOIN view_post AS p ON p.id = ptic.post_id
+  WHERE
+    -- Only scan forward from the origin
+    ptic.post_id <= p_start_id
+    -- Posts with fewer tags than the required tags cannot qualify
+    AND icount(ptic.tag_ids) >= icount(v_tag_ids)
+    -- Post must have all the included tags
+    AND ptic.tag_ids @> v_tag_ids
+    -- Post must not have any of the excluded tags
+    AND NOT ptic.tag_ids && v_exclude_tag_ids
+  ORDER BY ptic.post_id DESC
+  LIMIT p_limit;
+END;
+$BODY$ STABLE;
@@ -0,0 +1,904 @@
+---- DROP OLD ----
+
+DROP FUNCTION get_view_posts;
+DROP FUNCTION search_posts;
+DROP FUNCTION is_cached_search;
+DROP FUNCTION create_post;
+DROP FUNCTION update_post;
+DROP VIEW view_post;
+DROP FUNCTION resolve_search_tags;
+DROP FUNCTION calculate_pages;
+DROP FUNCTION calculate_pages_reverse;
+DROP FUNCTION calculate_last_page;
+DROP FUNCTION update_post_tags;
+DROP FUNCTION update_tag;
+
+---- TABLES ----
+
+-- Add rating column to post.
+-- Existing posts are rated questionable, as their content is not known.
+ALTER TABLE post
+  ADD COLUMN rating text NOT NULL DEFAULT 'questionable',
+  ADD CHECK (rating IN ('safe', 'questionable', 'explicit'));
+
+-- Add max_rating column to user.
+-- If not set, the configured default is used.
+ALTER TABLE "user"
+  ADD COLUMN max_rating text,
+  ADD CHECK (max_rating IN ('safe', 'questionable', 'explicit'));
+
+---- TYPES ----
+
+-- Add rating to new_post
+ALTER TYPE new_post
+  ADD ATTRIBUTE rating text;
+
+-- Add rating to update_post.
+-- The rating is only changed if specified.
+ALTER TYPE update_post
+  ADD ATTRIBUTE rating text;
+
+-- Add rating filter to search options
+ALTER TYPE search_options
+  ADD ATTRIBUTE ratings text[];
+
+---- VIEWS ----
+
+-- Create view_post view
+CREATE VIEW view_post
+AS
+SELECT
+  p.id,
+  p.created_at,
+  p.user_id,
+  u.name AS user_name,
+  p.title,
+  p.description,
+  p.source,
+  p.filename,
+  p.size,
+  p.width,
+  p.height,
+  p.hash,
+  p.ext,
+  p.tn_ext,
+  p.tags,
+  p.fav_count,
+  p.score,
+  p.parent_id,
+  p.rating
+FROM post AS p
+JOIN "user" AS u ON u.id = p.user_id
+WHERE NOT is_deleted;
+
+---- FUNCTIONS ----
+
+-- Create create_post function
+-- Returns NULL if the parent post is not valid.
+CREATE FUNCTION create_post(
+  IN p_post new_post,
+  IN p_tags text[]
+)
+RETURNS integer
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_post_id integer;
+BEGIN
+  IF NOT is_valid_post_parent(NULL, p_post.parent_id) THEN
+    RETURN NULL;
+  END IF;
+
+  -- Insert post
+  INSERT INTO post (
+    user_id,
+    title,
+    description,
+    source,
+    filename,
+    size,
+    width,
+    height,
+    hash,
+    ext,
+    tn_ext,
+    parent_id
+  )
+  SELECT
+    p_post.user_id, -- user_id
+    p_post.title, -- title
+    p_post.description, -- description
+    p_post.source, -- source
+    p_post.filename, -- filename
+    p_post.size, -- size
+    p_post.width, -- width
+    p_post.height, -- height
+    p_post.hash, -- hash
+    p_post.ext, -- ext
+    p_post.tn_ext, -- tn_ext
+    p_post.parent_id -- parent_id
+  RETURNING id INTO v_post_id;
+
+  -- If no rating is specified, the column default is used
+  IF p_post.rating IS NOT NULL THEN
+    UPDATE post
+    SET rating = p_post.rating
+    WHERE id = v_post_id;
+  END IF;
+
+  -- Create post_tag_id_cache
+  INSERT INTO post_tag_id_cache (post_id) VALUES (v_post_id);
+
+  -- Add post tags
+  PERFORM update_post_tags(v_post_id, p_tags, '{}', p_post.user_id, true);
+
+  RETURN v_post_id;
+END;
+$BODY$;
+
+-- Create update_post function
+-- Returns false if the post does not exist or belongs to another user,
+-- and NULL if the parent post is not valid.
+CREATE FUNCTION update_post(
+  IN p_update_post update_post,
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  -- Lock the post, so that its parent can not be changed concurrently
+  PERFORM 1
+  FROM post
+  WHERE id = p_update_post.id
+    AND user_id = p_user_id
+  FOR UPDATE;
+
+  IF NOT FOUND THEN
+    RETURN false;
+  END IF;
+
+  IF p_update_post.update_parent AND NOT is_valid_post_parent(p_update_post.id, p_update_post.parent_id) THEN
+    RETURN NULL;
+  END IF;
+
+  -- Update post
+  UPDATE post
+  SET
+    title = p_update_post.title,
+    description = p_update_post.description,
+    source = p_update_post.source,
+    parent_id = (CASE WHEN p_update_post.update_parent THEN p_update_post.parent_id ELSE parent_id END),
+    rating = COALESCE(p_update_post.rating, rating)
+  WHERE id = p_update_post.id;
+
+  -- Update post tags
+  PERFORM update_post_tags(p_update_post.id, p_update_post.add_tags, p_update_post.remove_tags, p_user_id, false);
+
+  RETURN true;
+END;
+$BODY$;
+
+-- Create is_cached_search function
+-- Searches without metatags use the search cache.
+-- Ratings are searched by their tag IDs, so they do not prevent caching.
+CREATE FUNCTION is_cached_search(
+  IN p_options search_options
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN p_options.fav_user_name IS NULL
+    AND p_options.min_score IS NULL
+    AND p_options.max_score IS NULL
+    AND p_options.pool_id IS NULL
+    AND p_options.parent_id IS NULL
+    AND p_options.has_parent IS NULL
+    AND p_options.child_id IS NULL
+    AND p_options.has_children IS NULL
+    AND COALESCE(p_options.sort, 'id') = 'id';
+END;
+$BODY$ IMMUTABLE;
+
+-- Create search_posts function
+-- Returns the posts matching a search, along with the key they are sorted by.
+-- Used for searches with metatags, which are not cached.
+CREATE FUNCTION search_posts(
+  IN p_tag_ids integer[],
+  IN p_exclude_tag_ids integer[],
+  IN p_options search_options
+)
+RETURNS TABLE (
+  post_id integer,
+  sort_key integer
+)
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  SELECT
+    ptic.post_id,
+    (CASE p_options.sort
+      WHEN 'fav_count' THEN p.fav_count
+      WHEN 'score' THEN p.score
+      ELSE 0
+      END) AS sort_key
+  FROM post_tag_id_cache AS ptic
+  JOIN post AS p ON p.id = ptic.post_id
+  WHERE
+    -- Posts with fewer tags than the required tags cannot qualify
+    icount(ptic.tag_ids) >= icount(p_tag_ids)
+    -- Post must have all the included tags
+    AND ptic.tag_ids @> p_tag_ids
+    -- Post must not have any of the excluded tags
+    AND NOT ptic.tag_ids && p_exclude_tag_ids
+    -- Post must be favorited by the user, if specified
+    AND (p_options.fav_user_name IS NULL OR EXISTS (
+      SELECT 1
+      FROM post_favorite AS pf
+      JOIN "user" AS u ON u.id = pf.user_id
+      WHERE pf.post_id = ptic.post_id
+        AND u.name = p_options.fav_user_name
+    ))
+    -- Post score must be within the range, if specified
+    AND (p_options.min_score IS NULL OR p.score >= p_options.min_score)
+    AND (p_options.max_score IS NULL OR p.score <= p_options.max_score)
+    -- Post must be in the pool, if specified
+    AND (p_options.pool_id IS NULL OR EXISTS (
+      SELECT 1
+      FROM pool_post AS pp
+      WHERE pp.post_id = ptic.post_id
+        AND pp.pool_id = p_options.pool_id
+    ))
+    -- Post must be a child of the post, if specified
+    AND (p_options.parent_id IS NULL OR p.parent_id = p_options.parent_id)
+    AND (p_options.has_parent IS NULL OR (p.parent_id IS NOT NULL) = p_options.has_parent)
+    -- Post must be the parent of the post, if specified
+    AND (p_options.child_id IS NULL OR p.id = (SELECT c.parent_id FROM post AS c WHERE c.id = p_options.child_id))
+    AND (p_options.has_children IS NULL OR EXISTS (
+      SELECT 1
+      FROM post AS c
+      WHERE c.parent_id = p.id
+        AND NOT c.is_deleted
+    ) = p_options.has_children);
+END;
+$BODY$ STABLE;
+
+-- Create get_view_posts function
+CREATE FUNCTION get_view_posts(
+  IN p_include_tags text[],
+  IN p_exclude_tags text[],
+  IN p_options search_options,
+  IN p_start_id integer,
+  IN p_limit integer
+)
+RETURNS SETOF view_post
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_tag_ids integer[];
+  v_exclude_tag_ids integer[];
+  v_valid boolean;
+BEGIN
+  SELECT * INTO v_tag_ids, v_exclude_tag_ids, v_valid FROM resolve_search_tags(p_include_tags, p_exclude_tags, p_options.ratings);
+  IF NOT v_valid THEN
+    RETURN;
+  END IF;
+
+  IF NOT is_cached_search(p_options) THEN
+    RETURN QUERY
+    SELECT p.*
+    FROM search_posts(v_tag_ids, v_exclude_tag_ids, p_options) AS s
+    JOIN view_post AS p ON p.id = s.post_id
+    WHERE
+      -- Only scan forward from the origin
+      (s.sort_key, s.post_id) <= (search_sort_key(p_start_id, p_options.sort), p_start_id)
+    ORDER BY s.sort_key DESC, s.post_id DESC
+    LIMIT p_limit;
+
+    RETURN;
+  END IF;
+
+  RETURN QUERY
+  SELECT p.*
+  FROM post_tag_id_cache AS ptic
+  JOIN view_post AS p ON p.id = ptic.post_id
+  WHERE
+    -- Only scan forward from the origin
+    ptic.post_id <= p_start_id
+    -- Posts with fewer tags than the required tags cannot qualify
+    AND icount(ptic.tag_ids) >= icount(v_tag_ids)
+    -- Post must have all the included tags
+    AND ptic.tag_ids @> v_tag_ids
+    -- Post must not have any of the excluded tags
+    AND NOT ptic.tag_ids && v_exclude_tag_ids
+  ORDER BY ptic.post_id DESC
+  LIMIT p_limit;
+END;
+$BODY$ STABLE;
+
+-- Create rating_tag_id function
+-- Ratings are cached as negative IDs along with the tag IDs of posts,
+-- so that searches by rating can use the search cache.
+CREATE FUNCTION rating_tag_id(
+  IN p_rating text
+)
+RETURNS integer
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN (CASE p_rating
+    WHEN 'safe' THEN -1
+    WHEN 'questionable' THEN -2
+    WHEN 'explicit' THEN -3
+    END);
+END;
+$BODY$ IMMUTABLE;
+
+-- Create resolve_search_tags function
+-- Posts without one of the ratings are excluded by their rating tag IDs, if ratings are specified.
+CREATE FUNCTION resolve_search_tags(
+  IN p_include_tags text[],
+  IN p_exclude_tags text[],
+  IN p_ratings text[],
+  OUT p_include_tag_ids integer[],
+  OUT p_exclude_tag_ids integer[],
+  OUT p_valid boolean
+)
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  p_include_tag_ids := get_tag_ids(p_include_tags);
+  p_exclude_tag_ids := get_tag_ids(p_exclude_tags);
+
+  p_valid := NOT (icount(p_include_tag_ids) < cardinality(p_include_tags) OR p_include_tag_ids && p_exclude_tag_ids);
+
+  p_include_tag_ids := compute_search_tag_ids(p_include_tag_ids);
+  p_exclude_tag_ids := compute_search_tag_ids(p_exclude_tag_ids);
+
+  IF p_ratings IS NOT NULL THEN
+    p_exclude_tag_ids := p_exclude_tag_ids | array(
+      SELECT rating_tag_id(r)
+      FROM unnest(ARRAY['safe', 'questionable', 'explicit']) AS r
+      WHERE r <> ALL(p_ratings)
+    );
+  END IF;
+END;
+$BODY$ STABLE;
+
+-- Create calculate_pages function
+-- Calculate the starting IDs of a range of pages,
+-- optionally starting from an already known page.
+CREATE FUNCTION calculate_pages(
+  IN p_include_tags text[],
+  IN p_exclude_tags text[],
+  IN p_options search_options,
+  IN p_posts_per_page integer,
+  IN p_page_count integer,
+  IN p_origin_page page_info
+)
+RETURNS page_info[]
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_pages page_info[];
+  v_tag_ids integer[];
+  v_exclude_tag_ids integer[];
+  v_valid boolean;
+  v_post_count integer;
+  v_start_id integer;
+  v_last_id integer;
+BEGIN
+  SELECT * INTO v_tag_ids, v_exclude_tag_ids, v_valid FROM resolve_search_tags(p_include_tags, p_exclude_tags, p_options.ratings);
+  IF NOT v_valid THEN
+    RETURN v_pages;
+  END IF;
+
+  IF NOT is_cached_search(p_options) THEN
+    v_start_id := COALESCE(p_origin_page.start_id, 2147483647);
+
+    v_pages := array(
+      SELECT (no, start_id)::page_info
+      FROM (
+        SELECT
+          COALESCE(p_origin_page.no, 1) + ROW_NUMBER() OVER () - 1 AS no,
+          x.id AS start_id
+        FROM (
+          SELECT
+            s.post_id AS id,
+            ROW_NUMBER() OVER (ORDER BY s.sort_key DESC, s.post_id DESC) AS rn
+          FROM search_posts(v_tag_ids, v_exclude_tag_ids, p_options) AS s
+          WHERE
+            -- Only scan forward from start ID
+            (s.sort_key, s.post_id) <= (search_sort_key(v_start_id, p_options.sort), v_start_id)
+          ORDER BY s.sort_key DESC, s.post_id DESC
+          LIMIT p_page_count * p_posts_per_page -- X pages at a time
+        ) AS x
+        WHERE MOD(x.rn - 1, p_posts_per_page) = 0
+      ) AS x
+      WHERE x.no > COALESCE(p_origin_page.no, 0)
+    );
+
+    RETURN v_pages;
+  END IF;
+
+  -- Make sure search cache is initialized
+  PERFORM initialize_search_cache(v_tag_ids, v_exclude_tag_ids);
+
+  SELECT post_count, first_post_id, last_page_post_ids[1]
+  INTO v_post_count, v_start_id, v_last_id
+  FROM search_cache
+  WHERE tag_ids = v_tag_ids
+    AND exclude_tag_ids = v_exclude_tag_ids;
+
+  -- If no posts exist in the search, return.
+  IF v_start_id IS NULL THEN
+    RETURN v_pages;
+  END IF;
+
+  IF p_origin_page.start_id IS NOT NULL THEN
+    v_start_id := p_origin_page.start_id;
+  END IF;
+
+  v_pages := array(
+    SELECT (no, start_id)::page_info
+    FROM (
+      SELECT
+        COALESCE(p_origin_page.no, 1) + ROW_NUMBER() OVER () - 1 AS no,
+        x.id AS start_id
+      FROM (
+        SELECT
+          ptic.post_id AS id,
+          ROW_NUMBER() OVER (ORDER BY ptic.post_id DESC) AS rn
+        FROM post_tag_id_cache AS ptic
+        WHERE
+          -- Only scan forward from start ID
+          ptic.post_id <= v_start_id
+          -- Post must have all the included tags
+          AND ptic.tag_ids @> v_tag_ids
+          -- Post must not have any of the excluded tags
+          AND NOT ptic.tag_ids && v_exclude_tag_ids
+        ORDER BY ptic.post_id DESC
+        LIMIT LEAST(p_page_count * p_posts_per_page, v_post_count) -- X pages at a time
+      ) AS x
+      WHERE MOD(x.rn - 1, p_posts_per_page) = 0
+    ) AS x
+    WHERE x.no > COALESCE(p_origin_page.no, 0)
+  );
+
+  RETURN v_pages;
+END;
+$BODY$;
+
+-- Create calculate_pages_reverse function
+-- Like calculate_pages, but in reverse.
+-- (Calculates previous pages)
+CREATE FUNCTION calculate_pages_reverse(
+  IN p_include_tags text[],
+  IN p_exclude_tags text[],
+  IN p_options search_options,
+  IN p_posts_per_page integer,
+  IN p_page_count integer,
+  IN p_origin_page page_info
+)
+RETURNS page_info[]
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_pages page_info[];
+  v_tag_ids integer[];
+  v_exclude_tag_ids integer[];
+  v_valid boolean;
+  v_start_id integer;
+  v_last_id integer;
+BEGIN
+  SELECT * INTO v_tag_ids, v_exclude_tag_ids, v_valid FROM resolve_search_tags(p_include_tags, p_exclude_tags, p_options.ratings);
+  IF NOT v_valid THEN
+    RETURN v_pages;
+  END IF;
+
+  IF NOT is_cached_search(p_options) THEN
+    -- Previous pages can only be calculated from a known page
+    IF p_origin_page.start_id IS NULL THEN
+      RETURN v_pages;
+    END IF;
+
+    v_last_id := p_origin_page.start_id;
+
+    v_pages := array(
+      SELECT (no, start_id)::page_info
+      FROM (
+        SELECT
+          p_origin_page.no - ROW_NUMBER() OVER () + 1 AS no,
+          x.id AS start_id
+        FROM (
+          SELECT
+            s.post_id AS id,
+            ROW_NUMBER() OVER (ORDER BY s.sort_key ASC, s.post_id ASC) AS rn
+          FROM search_posts(v_tag_ids, v_exclude_tag_ids, p_options) AS s
+          WHERE
+            -- Only scan backwards from the origin
+            (s.sort_key, s.post_id) >= (search_sort_key(v_last_id, p_options.sort), v_last_id)
+          ORDER BY s.sort_key ASC, s.post_id ASC
+          LIMIT ((p_page_count + 1) * p_posts_per_page) -- X pages at a time
+        ) AS x
+        WHERE MOD(x.rn - 1, p_posts_per_page) = 0
+      ) AS x
+      WHERE x.no < p_origin_page.no
+    );
+
+    RETURN v_pages;
+  END IF;
+
+  -- Make sure search cache is initialized
+  PERFORM initialize_search_cache(v_tag_ids, v_exclude_tag_ids);
+
+  SELECT first_post_id, last_page_post_ids[1]
+  INTO v_start_id, v_last_id
+  FROM search_cache
+  WHERE tag_ids = v_tag_ids
+    AND exclude_tag_ids = v_exclude_tag_ids;
+
+  -- If no posts exist in the search, return.
+  IF v_start_id IS NULL THEN
+    RETURN v_pages;
+  END IF;
+
+  IF p_origin_page.start_id IS NOT NULL THEN
+    v_last_id := p_origin_page.start_id;
+  END IF;
+
+  v_pages := array(
+    SELECT (no, start_id)::page_info
+    FROM (
+      SELECT
+        COALESCE(p_origin_page.no, 0) - ROW_NUMBER() OVER () + 1 AS no,
+        x.id AS start_id
+      FROM (
+        SELECT
+          ptic.post_id AS id,
+          ROW_NUMBER() OVER (ORDER BY ptic.post_id ASC) AS rn
+        FROM post_tag_id_cache AS ptic
+        WHERE
+          -- Only scan backwards from the origin
+          ptic.post_id >= v_last_id
+          -- Post must have all the included tags
+          AND ptic.tag_ids @> v_tag_ids
+          -- Post must not have any of the excluded tags
+          AND NOT ptic.tag_ids && v_exclude_tag_ids
+        ORDER BY ptic.post_id ASC
+        LIMIT ((p_page_count + 1) * p_posts_per_page) -- X pages at a time
+      ) AS x
+      WHERE MOD(x.rn - 1, p_posts_per_page) = 0
+    ) AS x
+    WHERE x.no < p_origin_page.no
+  );
+
+  RETURN v_pages;
+END;
+$BODY$;
+
+-- Create calculate_last_page function
+CREATE FUNCTION calculate_last_page(
+  IN p_include_tags text[],
+  IN p_exclude_tags text[],
+  IN p_options search_options,
+  IN p_posts_per_page integer
+)
+RETURNS page_info
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_tag_ids integer[];
+  v_exclude_tag_ids integer[];
+  v_valid boolean;
+  v_post_count integer;
+  v_page_count integer;
+  v_last_page_start_id integer;
+  v_last_page_post_ids integer[];
+BEGIN
+  SELECT * INTO v_tag_ids, v_exclude_tag_ids, v_valid FROM resolve_search_tags(p_include_tags, p_exclude_tags, p_options.ratings);
+  IF NOT v_valid THEN
+    RETURN (1, 0)::page_info;
+  END IF;
+
+  IF NOT is_cached_search(p_options) THEN
+    SELECT COUNT(*)::integer INTO v_post_count
+    FROM search_posts(v_tag_ids, v_exclude_tag_ids, p_options);
+
+    IF v_post_count = 0 THEN
+      RETURN (1, 0)::page_info;
+    END IF;
+
+    v_page_count := CEIL(v_post_count::real / p_posts_per_page);
+
+    SELECT s.post_id INTO v_last_page_start_id
+    FROM search_posts(v_tag_ids, v_exclude_tag_ids, p_options) AS s
+    ORDER BY s.sort_key DESC, s.post_id DESC
+    OFFSET (v_page_count - 1) * p_posts_per_page
+    LIMIT 1;
+
+    RETURN (v_page_count, v_last_page_start_id)::page_info;
+  END IF;
+
+  -- Make sure search cache is initialized
+  PERFORM initialize_search_cache(v_tag_ids, v_exclude_tag_ids);
+
+  -- Try to get cached search info
+  SELECT post_count, last_page_post_ids
+  INTO v_post_count, v_last_page_post_ids
+  FROM search_cache
+  WHERE tag_ids = v_tag_ids
+    AND exclude_tag_ids = v_exclude_tag_ids;
+
+  -- If no posts exist in the search, return.
+  IF v_post_count IS NULL THEN
+    RETURN (1, 0)::page_info;
+  END IF;
+
+  -- Calculate page count
+  v_page_count := CEIL(v_post_count::real / p_posts_per_page);
+
+  -- Calculate number of posts currently on last page
+  v_post_count := MOD(v_post_count, p_posts_per_page);
+
+  -- If necessary, get additional last page posts
+  IF icount(v_last_page_post_ids) < v_post_count THEN
+    v_last_page_post_ids := v_last_page_post_ids | array(
+      SELECT ptic.post_id
+      FROM post_tag_id_cache AS ptic
+      WHERE
+        ptic.post_id > (SELECT COALESCE(MAX(id), 0) FROM unnest(v_last_page_post_ids) AS id)
+        -- Posts with fewer tags than the required tags cannot qualify
+        AND icount(ptic.tag_ids) >= icount(v_tag_ids)
+        -- Post must have all the included tags
+        AND ptic.tag_ids @> v_tag_ids
+        -- Post must not have any of the excluded tags
+        AND NOT ptic.tag_ids && v_exclude_tag_ids
+      ORDER BY ptic.post_id ASC
+      LIMIT p_posts_per_page - icount(v_last_page_post_ids)
+    );
+
+    -- Update search cache with posts
+    UPDATE search_cache
+    SET last_page_post_ids = v_last_page_post_ids
+    WHERE tag_ids = v_tag_ids
+      AND exclude_tag_ids = v_exclude_tag_ids;
+  END IF;
+
+  -- Get last page start ID
+  v_last_page_start_id := v_last_page_post_ids[v_post_count];
+
+  RETURN (v_page_count, v_last_page_start_id)::page_info;
+END;
+$BODY$;
+
+-- Create update_post_tags function
+CREATE FUNCTION update_post_tags(
+  IN p_post_id integer,
+  IN p_add_tags text[],
+  IN p_remove_tags text[],
+  IN p_user_id integer,
+  IN p_new_post boolean
+)
+RETURNS VOID
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_add_tag_ids integer[];
+  v_remove_tag_ids integer[];
+  v_old_tag_ids integer[];
+  v_new_tag_ids integer[];
+BEGIN
+  -- Create missing tags
+  PERFORM create_missing_tags(p_add_tags);
+
+  v_add_tag_ids := get_tag_ids(p_add_tags);
+  v_remove_tag_ids := get_tag_ids(p_remove_tags);
+
+  -- Retrieve old tags
+  v_old_tag_ids := array(SELECT tag_id FROM post_tag AS pt WHERE pt.post_id = p_post_id ORDER BY tag_id ASC);
+
+  -- Compute new tags
+  v_new_tag_ids := (v_old_tag_ids | v_add_tag_ids) - v_remove_tag_ids;
+
+  -- Add links for added tags to post
+  INSERT INTO post_tag (post_id, tag_id)
+    SELECT p_post_id, tag_id
+    FROM unnest(v_add_tag_ids) AS tag_id
+    ON CONFLICT(post_id, tag_id)
+    DO NOTHING;
+
+  -- Remove removed tag links for post
+  DELETE FROM post_tag AS pt
+  USING unnest(v_remove_tag_ids) AS rtid
+  WHERE pt.post_id = p_post_id AND pt.tag_id = rtid;
+
+  -- Update post tags
+  UPDATE post
+  SET tags = array(SELECT tag
+                   FROM tag
+                   WHERE id = ANY(v_new_tag_ids)
+                   ORDER BY tag ASC)
+  WHERE id = p_post_id;
+
+  -- The cached tag IDs include the rating of the post, which may have changed as well
+  v_old_tag_ids := (SELECT tag_ids FROM post_tag_id_cache WHERE post_id = p_post_id);
+  v_new_tag_ids := compute_post_tag_ids(v_new_tag_ids) | (SELECT rating_tag_id(rating) FROM post WHERE id = p_post_id);
+
+  -- Update post_tag_id_cache
+  UPDATE post_tag_id_cache
+  SET tag_ids = v_new_tag_ids
+  WHERE post_id = p_post_id;
+
+  -- Update search cache to reflect added post
+  UPDATE search_cache
+  SET post_count = post_count + 1,
+      first_post_id = (CASE WHEN p_post_id > first_post_id THEN p_post_id ELSE first_post_id END),
+      last_page_post_ids = (CASE WHEN p_post_id < (SELECT MAX(id) FROM unnest(last_page_post_ids) AS id)
+                            THEN last_page_post_ids | p_post_id
+                            ELSE last_page_post_ids
+                            END)
+  WHERE v_new_tag_ids @> tag_ids
+    AND NOT v_new_tag_ids && exclude_tag_ids
+    AND (p_new_post OR (NOT v_old_tag_ids @> tag_ids) OR v_old_tag_ids && exclude_tag_ids);
+
+  -- Update search cache to reflect removed post
+  UPDATE search_cache AS sc
+  SET post_count = post_count - 1,
+      first_post_id = (CASE WHEN p_post_id = first_post_id
+                       THEN (SELECT COALESCE(MAX(post_id), 0)
+                             FROM post_tag_id_cache AS ptic
+                             WHERE ptic.tag_ids @> sc.tag_ids
+                               AND NOT ptic.tag_ids && sc.exclude_tag_ids)
+                       ELSE first_post_id
+                       END),
+      last_page_post_ids = (CASE WHEN (SELECT p_post_id BETWEEN MIN(id) AND MAX(id) FROM unnest(last_page_post_ids) AS id)
+                            THEN last_page_post_ids - p_post_id
+                            ELSE last_page_post_ids
+                            END)
+  WHERE NOT p_new_post
+    AND ((NOT v_new_tag_ids @> tag_ids) OR v_new_tag_ids && exclude_tag_ids)
+    AND v_old_tag_ids @> tag_ids
+    AND NOT v_old_tag_ids && exclude_tag_ids;
+
+  -- Track tag changes
+  INSERT INTO post_tag_change (
+    post_id,
+    user_id,
+    tag_ids_added,
+    tag_ids_removed
+  ) VALUES (
+    p_post_id,
+    p_user_id,
+    v_add_tag_ids,
+    v_remove_tag_ids
+  );
+END;
+$BODY$;
+
+-- Create update_tag function
+CREATE FUNCTION update_tag(
+  IN p_tag_id integer,
+  IN p_update_tag update_tag,
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_add_alias_ids integer[];
+  v_remove_alias_ids integer[];
+  v_old_alias_ids integer[];
+  v_add_implied_tag_ids integer[];
+  v_remove_implied_tag_ids integer[];
+  v_old_implied_tag_ids integer[];
+  v_new_implied_tag_ids integer[];
+  v_affected_tag_ids integer[];
+BEGIN
+  IF NOT can_user_edit_tag(p_tag_id, p_user_id) THEN
+    RETURN false;
+  END IF;
+
+  v_add_implied_tag_ids := get_tag_ids(p_update_tag.add_implied_tags);
+  v_remove_implied_tag_ids := get_tag_ids(p_update_tag.remove_implied_tags);
+
+  -- Retrieve implied tag ids
+  SELECT implied_tag_ids
+  INTO v_old_implied_tag_ids
+  FROM tag
+  WHERE id = p_tag_id;
+
+  -- Compute new implied tag ids
+  v_new_implied_tag_ids := (v_old_implied_tag_ids | v_add_implied_tag_ids) - v_remove_implied_tag_ids;
+
+  -- Update tag
+  UPDATE tag
+  SET implied_tag_ids = v_new_implied_tag_ids
+  WHERE id = p_tag_id;
+
+  -- Get ids of removed aliases
+  v_remove_alias_ids := get_tag_ids(p_update_tag.remove_aliases);
+
+  IF cardinality(p_update_tag.add_aliases) > 0 THEN
+    -- Create missing tags for added aliases
+    PERFORM create_missing_tags(p_update_tag.add_aliases);
+
+    -- Retrieve old alias ids
+    SELECT COALESCE(array_agg(id), '{}')
+    INTO v_old_alias_ids
+    FROM tag
+    WHERE alias_of_tag_id = p_tag_id;
+
+    -- Get ids of added aliases
+    v_add_alias_ids := get_tag_ids(p_update_tag.add_aliases) - v_old_alias_ids - v_remove_alias_ids;
+
+    -- Set alias_of_tag_id for added aliases
+    UPDATE tag
+    SET alias_of_tag_id = p_tag_id
+    WHERE id = ANY(v_add_alias_ids);
+
+    -- Set any aliases of added aliases to be aliases of this tag
+    UPDATE tag
+    SET alias_of_tag_id = p_tag_id
+    WHERE alias_of_tag_id = ANY(v_add_alias_ids);
+  END IF;
+
+  IF icount(v_remove_alias_ids) > 0 THEN
+    -- Get actual alias ids that will be removed
+    SELECT array_agg(id)
+    INTO v_remove_alias_ids
+    FROM tag
+    WHERE alias_of_tag_id = p_tag_id AND id = ANY(v_remove_alias_ids);
+
+    -- Clear alias_of_tag_id of removed aliases
+    UPDATE tag
+    SET alias_of_tag_id = NULL
+    WHERE id = ANY(v_remove_alias_ids);
+  END IF;
+
+  v_affected_tag_ids := v_add_alias_ids || v_remove_alias_ids;
+
+  IF v_new_implied_tag_ids <> v_old_implied_tag_ids THEN
+    v_affected_tag_ids := v_affected_tag_ids + p_tag_id | v_old_implied_tag_ids | v_new_implied_tag_ids;
+  END IF;
+
+  IF icount(v_affected_tag_ids) > 0 THEN
+    v_affected_tag_ids := v_affected_tag_ids | compute_post_tag_ids(v_affected_tag_ids);
+
+    -- Update pre-calculated post tag ID cache
+    UPDATE post_tag_id_cache AS ptic
+    SET tag_ids = compute_post_tag_ids(array(SELECT tag_id FROM post_tag AS pt WHERE pt.post_id = ptic.post_id))
+      | (SELECT rating_tag_id(p.rating) FROM post AS p WHERE p.id = ptic.post_id)
+    WHERE tag_ids && v_affected_tag_ids;
+
+    -- Delete cached searches affected by alias change
+    DELETE FROM search_cache
+    WHERE tag_ids && v_affected_tag_ids
+       OR exclude_tag_ids && v_affected_tag_ids;
+  END IF;
+
+  RETURN true;
+END;
+$BODY$;
+
+---- MIGRATE ----
+
+-- Add the ratings of existing posts to their cached tag IDs
+UPDATE post_tag_id_cache AS ptic
+SET tag_ids = ptic.tag_ids | rating_tag_id(p.rating)
+FROM post AS p
+WHERE p.id = ptic.post_id;
@@ -0,0 +1,40 @@
+---- TABLES ----
+
+-- Create user_tag_blacklist table
+CREATE TABLE user_tag_blacklist
+(
+  user_id integer NOT NULL,
+  tag text NOT NULL,
+
+  PRIMARY KEY (user_id, tag),
+
+  FOREIGN KEY (user_id)
+    REFERENCES "user" (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID
+);
+
+---- FUNCTIONS ----
+
+-- Create set_user_tag_blacklist function
+-- Replace the blacklisted tags of a user.
+-- Blank and duplicate tags are ignored.
+CREATE FUNCTION set_user_tag_blacklist(
+  IN p_user_id integer,
+  IN p_tags text[]
+)
+RETURNS VOID
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  DELETE FROM user_tag_blacklist
+  WHERE user_id = p_user_id;
+
+  INSERT INTO user_tag_blacklist (user_id, tag)
+  SELECT DISTINCT p_user_id, trim(t.tag)
+  FROM unnest(p_tags) AS t(tag)
+  WHERE trim(t.tag) <> '';
+END;
+$BODY$;
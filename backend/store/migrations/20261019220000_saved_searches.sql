@@ -0,0 +1,271 @@
+---- DROP OLD ----
+
+DROP FUNCTION search_posts;
+DROP FUNCTION is_cached_search;
+
+---- TABLES ----
+
+-- Create saved_search table
+CREATE TABLE saved_search
+(
+  id serial NOT NULL,
+  created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  user_id integer NOT NULL,
+  name text NOT NULL,
+  include_tags text[] NOT NULL DEFAULT '{}',
+  exclude_tags text[] NOT NULL DEFAULT '{}',
+  last_seen_post_id integer NOT NULL DEFAULT 0,
+
+  PRIMARY KEY (id),
+
+  FOREIGN KEY (user_id)
+    REFERENCES "user" (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID
+);
+
+SELECT manage_updated_at('saved_search'); -- Automatically manage updated_at
+
+---- INDEXES ----
+
+CREATE INDEX saved_search_user_id_idx ON saved_search
+  USING btree
+  (user_id ASC NULLS LAST);
+
+---- TYPES ----
+
+-- Add lower bound on post ID to search options
+ALTER TYPE search_options
+  ADD ATTRIBUTE after_id integer;
+
+-- Create new_saved_search type
+CREATE TYPE new_saved_search AS (
+  user_id integer,
+  name text,
+  include_tags text[],
+  exclude_tags text[]
+);
+
+-- Create update_saved_search type
+CREATE TYPE update_saved_search AS (
+  name text,
+  include_tags text[],
+  exclude_tags text[]
+);
+
+---- FUNCTIONS ----
+
+-- Create create_saved_search function
+-- Posts that already exist when the search is saved are considered seen.
+CREATE FUNCTION create_saved_search(
+  IN p_saved_search new_saved_search
+)
+RETURNS integer
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_saved_search_id integer;
+BEGIN
+  INSERT INTO saved_search (user_id, name, include_tags, exclude_tags, last_seen_post_id)
+  VALUES (
+    p_saved_search.user_id,
+    p_saved_search.name,
+    p_saved_search.include_tags,
+    p_saved_search.exclude_tags,
+    (SELECT COALESCE(max(id), 0) FROM post)
+  )
+  RETURNING id INTO v_saved_search_id;
+
+  RETURN v_saved_search_id;
+END;
+$BODY$;
+
+-- Create update_saved_search function
+CREATE FUNCTION update_saved_search(
+  IN p_saved_search_id integer,
+  IN p_update_saved_search update_saved_search,
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_success boolean;
+BEGIN
+  UPDATE saved_search
+  SET name = p_update_saved_search.name,
+      include_tags = p_update_saved_search.include_tags,
+      exclude_tags = p_update_saved_search.exclude_tags
+  WHERE id = p_saved_search_id
+    AND user_id = p_user_id
+  RETURNING true INTO v_success;
+
+  RETURN COALESCE(v_success, false);
+END;
+$BODY$;
+
+-- Create delete_saved_search function
+CREATE FUNCTION delete_saved_search(
+  IN p_saved_search_id integer,
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_success boolean;
+BEGIN
+  DELETE FROM saved_search
+  WHERE id = p_saved_search_id
+    AND user_id = p_user_id
+  RETURNING true INTO v_success;
+
+  RETURN COALESCE(v_success, false);
+END;
+$BODY$;
+
+-- Create mark_saved_search_seen function
+-- Mark posts up to the specified post as seen.
+-- The last seen post never moves backwards, in case of requests arriving out of order.
+CREATE FUNCTION mark_saved_search_seen(
+  IN p_saved_search_id integer,
+  IN p_last_seen_post_id integer,
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_success boolean;
+BEGIN
+  UPDATE saved_search
+  SET last_seen_post_id = GREATEST(last_seen_post_id, p_last_seen_post_id)
+  WHERE id = p_saved_search_id
+    AND user_id = p_user_id
+  RETURNING true INTO v_success;
+
+  RETURN COALESCE(v_success, false);
+END;
+$BODY$;
+
+-- Create is_cached_search function
+-- Searches without metatags use the search cache.
+-- Ratings are searched by their tag IDs, so they do not prevent caching.
+CREATE FUNCTION is_cached_search(
+  IN p_options search_options
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN p_options.fav_user_name IS NULL
+    AND p_options.min_score IS NULL
+    AND p_options.max_score IS NULL
+    AND p_options.pool_id IS NULL
+    AND p_options.parent_id IS NULL
+    AND p_options.has_parent IS NULL
+    AND p_options.child_id IS NULL
+    AND p_options.has_children IS NULL
+    AND p_options.after_id IS NULL
+    AND COALESCE(p_options.sort, 'id') = 'id';
+END;
+$BODY$ IMMUTABLE;
+
+-- Create search_posts function
+-- Returns the posts matching a search, along with the key they are sorted by.
+-- Used for searches with metatags, which are not cached.
+CREATE FUNCTION search_posts(
+  IN p_tag_ids integer[],
+  IN p_exclude_tag_ids integer[],
+  IN p_options search_options
+)
+RETURNS TABLE (
+  post_id integer,
+  sort_key integer
+)
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  SELECT
+    ptic.post_id,
+    (CASE p_options.sort
+      WHEN 'fav_count' THEN p.fav_count
+      WHEN 'score' THEN p.score
+      ELSE 0
+      END) AS sort_key
+  FROM post_tag_id_cache AS ptic
+  JOIN post AS p ON p.id = ptic.post_id
+  WHERE
+    -- Only include posts after the ID, if specified
+    (p_options.after_id IS NULL OR ptic.post_id > p_options.after_id)
+    -- Posts with fewer tags than the required tags cannot qualify
+    AND icount(ptic.tag_ids) >= icount(p_tag_ids)
+    -- Post must have all the included tags
+    AND ptic.tag_ids @> p_tag_ids
+    -- Post must not have any of the excluded tags
+    AND NOT ptic.tag_ids && p_exclude_tag_ids
+    -- Post must be favorited by the user, if specified
+    AND (p_options.fav_user_name IS NULL OR EXISTS (
+      SELECT 1
+      FROM post_favorite AS pf
+      JOIN "user" AS u ON u.id = pf.user_id
+      WHERE pf.post_id = ptic.post_id
+        AND u.name = p_options.fav_user_name
+    ))
+    -- Post score must be within the range, if specified
+    AND (p_options.min_score IS NULL OR p.score >= p_options.min_score)
+    AND (p_options.max_score IS NULL OR p.score <= p_options.max_score)
+    -- Post must be in the pool, if specified
+    AND (p_options.pool_id IS NULL OR EXISTS (
+      SELECT 1
+      FROM pool_post AS pp
+      WHERE pp.post_id = ptic.post_id
+        AND pp.pool_id = p_options.pool_id
+    ))
+    -- Post must be a child of the post, if specified
+    AND (p_options.parent_id IS NULL OR p.parent_id = p_options.parent_id)
+    AND (p_options.has_parent IS NULL OR (p.parent_id IS NOT NULL) = p_options.has_parent)
+    -- Post must be the parent of the post, if specified
+    AND (p_options.child_id IS NULL OR p.id = (SELECT c.parent_id FROM post AS c WHERE c.id = p_options.child_id))
+    AND (p_options.has_children IS NULL OR EXISTS (
+      SELECT 1
+      FROM post AS c
+      WHERE c.parent_id = p.id
+        AND NOT c.is_deleted
+    ) = p_options.has_children);
+END;
+$BODY$ STABLE;
+
+-- Create count_posts function
+-- Count the posts matching a search.
+CREATE FUNCTION count_posts(
+  IN p_include_tags text[],
+  IN p_exclude_tags text[],
+  IN p_options search_options
+)
+RETURNS integer
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_tag_ids integer[];
+  v_exclude_tag_ids integer[];
+  v_valid boolean;
+BEGIN
+  SELECT * INTO v_tag_ids, v_exclude_tag_ids, v_valid FROM resolve_search_tags(p_include_tags, p_exclude_tags, p_options.ratings);
+  IF NOT v_valid THEN
+    RETURN 0;
+  END IF;
+
+  RETURN (SELECT count(*) FROM search_posts(v_tag_ids, v_exclude_tag_ids, p_options));
+END;
+$BODY$ STABLE;
@@ -0,0 +1,83 @@
+---- TABLES ----
+
+-- Add is_deleted column to comment.
+-- Deleted comments are kept, so that replies still make sense.
+ALTER TABLE comment
+  ADD COLUMN is_deleted boolean NOT NULL DEFAULT false;
+
+---- VIEWS ----
+
+-- Create view_post_comment view
+-- The content and author of deleted comments are hidden.
+CREATE VIEW view_post_comment
+AS
+SELECT
+  pc.id,
+  pc.created_at,
+  pc.updated_at,
+  (CASE WHEN pc.is_deleted THEN NULL ELSE pc.user_id END) AS user_id,
+  (CASE WHEN pc.is_deleted THEN NULL ELSE pc.user_name END) AS user_name,
+  pc.post_id,
+  (CASE WHEN pc.is_deleted THEN '[removed]' ELSE pc.comment END) AS comment,
+  pc.is_deleted,
+  pc.updated_at > pc.created_at AS is_edited
+FROM post_comment AS pc;
+
+---- FUNCTIONS ----
+
+-- Create update_comment function
+-- Users can only edit their own comments, and only if they were created after the specified time.
+CREATE FUNCTION update_comment(
+  IN p_comment_id integer,
+  IN p_comment text,
+  IN p_user_id integer,
+  IN p_created_after timestamp with time zone
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_success boolean;
+BEGIN
+  UPDATE comment
+  SET comment = p_comment,
+      updated_at = CURRENT_TIMESTAMP
+  WHERE id = p_comment_id
+    AND user_id = p_user_id
+    AND created_at > p_created_after
+    AND NOT is_deleted
+  RETURNING true INTO v_success;
+
+  RETURN COALESCE(v_success, false);
+END;
+$BODY$;
+
+-- Create delete_comment function
+-- Users can only delete their own comments, and only if they were created after the specified time.
+-- Admins can delete any comment.
+CREATE FUNCTION delete_comment(
+  IN p_comment_id integer,
+  IN p_user_id integer,
+  IN p_created_after timestamp with time zone
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_is_admin boolean;
+  v_success boolean;
+BEGIN
+  v_is_admin := COALESCE((SELECT rank FROM "user" WHERE id = p_user_id) > 0, false);
+
+  UPDATE comment
+  SET is_deleted = true
+  WHERE id = p_comment_id
+    AND NOT is_deleted
+    AND (v_is_admin OR (user_id = p_user_id AND created_at > p_created_after))
+  RETURNING true INTO v_success;
+
+  RETURN COALESCE(v_success, false);
+END;
+$BODY$;
@@ -0,0 +1,162 @@
+---- DROP OLD ----
+
+DROP FUNCTION create_post_comment;
+DROP VIEW view_post_comment;
+DROP FUNCTION update_comment;
+
+---- TABLES ----
+
+-- Add reply columns to comment.
+-- The depth is stored, as a comment can never be moved to another thread.
+ALTER TABLE comment
+  ADD COLUMN reply_to_id integer,
+  ADD COLUMN depth integer NOT NULL DEFAULT 0;
+
+-- Create comment_mention table
+-- Users mentioned in a comment, so that they can be notified of it.
+CREATE TABLE comment_mention
+(
+  comment_id integer NOT NULL,
+  user_id integer NOT NULL,
+
+  PRIMARY KEY (comment_id, user_id),
+
+  FOREIGN KEY (user_id)
+    REFERENCES "user" (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID
+);
+
+---- TYPES ----
+
+-- Add reply_to_id to new_post_comment
+ALTER TYPE new_post_comment
+  ADD ATTRIBUTE reply_to_id integer;
+
+---- VIEWS ----
+
+-- Create view_post_comment view
+-- The content and author of deleted comments are hidden.
+CREATE VIEW view_post_comment
+AS
+SELECT
+  pc.id,
+  pc.created_at,
+  pc.updated_at,
+  (CASE WHEN pc.is_deleted THEN NULL ELSE pc.user_id END) AS user_id,
+  (CASE WHEN pc.is_deleted THEN NULL ELSE pc.user_name END) AS user_name,
+  pc.post_id,
+  (CASE WHEN pc.is_deleted THEN '[removed]' ELSE pc.comment END) AS comment,
+  pc.is_deleted,
+  pc.updated_at > pc.created_at AS is_edited,
+  pc.reply_to_id,
+  pc.depth
+FROM post_comment AS pc;
+
+---- FUNCTIONS ----
+
+-- Create create_post_comment function
+-- Replies must be to a comment on the same post.
+-- Mentioned users that exist are recorded, except for the author.
+CREATE FUNCTION create_post_comment(
+  IN p_comment new_post_comment,
+  IN p_user_id integer,
+  IN p_mentions text[]
+)
+RETURNS post_comment
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_comment post_comment;
+  v_depth integer;
+BEGIN
+  IF p_comment.reply_to_id IS NOT NULL THEN
+    SELECT depth + 1 INTO v_depth
+    FROM post_comment
+    WHERE id = p_comment.reply_to_id
+      AND post_id = p_comment.post_id;
+
+    IF NOT FOUND THEN
+      RAISE EXCEPTION 'Invalid reply comment';
+    END IF;
+  END IF;
+
+  -- Insert comment
+  INSERT INTO post_comment (
+    user_id,
+    user_name,
+    post_id,
+    comment,
+    reply_to_id,
+    depth
+  )
+  SELECT
+    p_user_id, -- user_id
+    (SELECT name FROM "user" WHERE id = p_user_id), -- user_name
+    p_comment.post_id, -- post_id
+    p_comment.comment, -- comment
+    p_comment.reply_to_id, -- reply_to_id
+    COALESCE(v_depth, 0) -- depth
+  RETURNING * INTO v_comment;
+
+  -- Record mentioned users
+  INSERT INTO comment_mention (comment_id, user_id)
+  SELECT v_comment.id, u.id
+  FROM "user" AS u
+  WHERE u.name = ANY(p_mentions)
+    AND u.id IS DISTINCT FROM p_user_id;
+
+  RETURN v_comment;
+END;
+$BODY$;
+
+-- Create update_comment function
+-- Users can only edit their own comments, and only if they were created after the specified time.
+-- The mentioned users are replaced, and newly mentioned users are notified unless the comment is held.
+CREATE FUNCTION update_comment(
+  IN p_comment_id integer,
+  IN p_comment text,
+  IN p_user_id integer,
+  IN p_created_after timestamp with time zone,
+  IN p_mentions text[]
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_is_held boolean;
+BEGIN
+  UPDATE comment
+  SET comment = p_comment,
+      updated_at = CURRENT_TIMESTAMP
+  WHERE id = p_comment_id
+    AND user_id = p_user_id
+    AND created_at > p_created_after
+    AND NOT is_deleted
+  RETURNING is_held INTO v_is_held;
+
+  IF NOT FOUND THEN
+    RETURN false;
+  END IF;
+
+  -- Replace mentioned users
+  DELETE FROM comment_mention
+  WHERE comment_id = p_comment_id;
+
+  INSERT INTO comment_mention (comment_id, user_id)
+  SELECT p_comment_id, u.id
+  FROM "user" AS u
+  WHERE u.name = ANY(p_mentions)
+    AND u.id IS DISTINCT FROM p_user_id;
+
+  -- Users that have already been notified of the comment are not notified again
+  IF NOT v_is_held THEN
+    PERFORM create_comment_notifications(p_comment_id);
+  END IF;
+
+  RETURN true;
+END;
+$BODY$;
@@ -0,0 +1,181 @@
+---- DROP OLD ----
+
+DROP FUNCTION create_post_comment;
+DROP VIEW view_post_comment;
+DROP FUNCTION update_comment;
+
+---- TABLES ----
+
+-- Add is_held column to comment.
+-- Held comments are hidden until they are approved by an admin.
+ALTER TABLE comment
+  ADD COLUMN is_held boolean NOT NULL DEFAULT false;
+
+---- TYPES ----
+
+-- Add is_held to new_post_comment
+ALTER TYPE new_post_comment
+  ADD ATTRIBUTE is_held boolean;
+
+---- VIEWS ----
+
+-- Create view_post_comment view
+-- The content and author of deleted comments are hidden.
+-- Held comments are not included.
+CREATE VIEW view_post_comment
+AS
+SELECT
+  pc.id,
+  pc.created_at,
+  pc.updated_at,
+  (CASE WHEN pc.is_deleted THEN NULL ELSE pc.user_id END) AS user_id,
+  (CASE WHEN pc.is_deleted THEN NULL ELSE pc.user_name END) AS user_name,
+  pc.post_id,
+  (CASE WHEN pc.is_deleted THEN '[removed]' ELSE pc.comment END) AS comment,
+  pc.is_deleted,
+  pc.updated_at > pc.created_at AS is_edited,
+  pc.reply_to_id,
+  pc.depth
+FROM post_comment AS pc
+WHERE NOT pc.is_held;
+
+---- FUNCTIONS ----
+
+-- Create create_post_comment function
+-- Replies must be to a visible comment on the same post.
+-- Mentioned users that exist are recorded, except for the author.
+CREATE FUNCTION create_post_comment(
+  IN p_comment new_post_comment,
+  IN p_user_id integer,
+  IN p_mentions text[]
+)
+RETURNS post_comment
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_comment post_comment;
+  v_depth integer;
+BEGIN
+  IF p_comment.reply_to_id IS NOT NULL THEN
+    SELECT depth + 1 INTO v_depth
+    FROM post_comment
+    WHERE id = p_comment.reply_to_id
+      AND post_id = p_comment.post_id
+      AND NOT is_held;
+
+    IF NOT FOUND THEN
+      RAISE EXCEPTION 'Invalid reply comment';
+    END IF;
+  END IF;
+
+  -- Insert comment
+  INSERT INTO post_comment (
+    user_id,
+    user_name,
+    post_id,
+    comment,
+    reply_to_id,
+    depth,
+    is_held
+  )
+  SELECT
+    p_user_id, -- user_id
+    (SELECT name FROM "user" WHERE id = p_user_id), -- user_name
+    p_comment.post_id, -- post_id
+    p_comment.comment, -- comment
+    p_comment.reply_to_id, -- reply_to_id
+    COALESCE(v_depth, 0), -- depth
+    COALESCE(p_comment.is_held, false) -- is_held
+  RETURNING * INTO v_comment;
+
+  -- Record mentioned users
+  INSERT INTO comment_mention (comment_id, user_id)
+  SELECT v_comment.id, u.id
+  FROM "user" AS u
+  WHERE u.name = ANY(p_mentions)
+    AND u.id IS DISTINCT FROM p_user_id;
+
+  RETURN v_comment;
+END;
+$BODY$;
+
+-- Create approve_comment function
+-- Only admins can approve held comments.
+CREATE FUNCTION approve_comment(
+  IN p_comment_id integer,
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_success boolean;
+BEGIN
+  IF NOT COALESCE((SELECT rank FROM "user" WHERE id = p_user_id) > 0, false) THEN
+    RETURN false;
+  END IF;
+
+  UPDATE post_comment
+  SET is_held = false
+  WHERE id = p_comment_id
+    AND is_held
+    AND NOT is_deleted
+  RETURNING true INTO v_success;
+
+  RETURN COALESCE(v_success, false);
+END;
+$BODY$;
+
+-- Create update_comment function
+-- Users can only edit their own comments, and only if they were created after the specified time.
+-- If the edited comment matches the filter, it is held until approved again.
+-- The mentioned users are replaced, and newly mentioned users are notified unless the comment is held.
+CREATE FUNCTION update_comment(
+  IN p_comment_id integer,
+  IN p_comment text,
+  IN p_user_id integer,
+  IN p_created_after timestamp with time zone,
+  IN p_mentions text[],
+  IN p_is_held boolean
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_is_held boolean;
+BEGIN
+  UPDATE comment
+  SET comment = p_comment,
+      updated_at = CURRENT_TIMESTAMP,
+      is_held = is_held OR p_is_held
+  WHERE id = p_comment_id
+    AND user_id = p_user_id
+    AND created_at > p_created_after
+    AND NOT is_deleted
+  RETURNING is_held INTO v_is_held;
+
+  IF NOT FOUND THEN
+    RETURN false;
+  END IF;
+
+  -- Replace mentioned users
+  DELETE FROM comment_mention
+  WHERE comment_id = p_comment_id;
+
+  INSERT INTO comment_mention (comment_id, user_id)
+  SELECT p_comment_id, u.id
+  FROM "user" AS u
+  WHERE u.name = ANY(p_mentions)
+    AND u.id IS DISTINCT FROM p_user_id;
+
+  -- Users that have already been notified of the comment are not notified again
+  IF NOT v_is_held THEN
+    PERFORM create_comment_notifications(p_comment_id);
+  END IF;
+
+  RETURN true;
+END;
+$BODY$;
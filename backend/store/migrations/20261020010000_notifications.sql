@@ -0,0 +1,357 @@
+---- DROP OLD ----
+
+DROP FUNCTION create_post_comment;
+DROP FUNCTION approve_comment;
+DROP FUNCTION update_post_tags;
+
+---- TABLES ----
+
+-- Create notification table
+CREATE TABLE notification
+(
+  id serial NOT NULL,
+  created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  user_id integer NOT NULL,
+  kind text NOT NULL,
+  actor_user_id integer,
+  post_id integer,
+  comment_id integer,
+  is_read boolean NOT NULL DEFAULT false,
+
+  PRIMARY KEY (id),
+
+  FOREIGN KEY (user_id)
+    REFERENCES "user" (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID,
+
+  FOREIGN KEY (actor_user_id)
+    REFERENCES "user" (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE SET NULL
+    NOT VALID,
+
+  FOREIGN KEY (post_id)
+    REFERENCES post (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID
+);
+
+---- INDEXES ----
+
+CREATE INDEX notification_user_id_idx ON notification
+  USING btree
+  (user_id ASC NULLS LAST);
+
+---- VIEWS ----
+
+-- Create view_notification view
+CREATE VIEW view_notification
+AS
+SELECT
+  n.id,
+  n.created_at,
+  n.user_id,
+  n.kind,
+  n.actor_user_id,
+  u.name AS actor_user_name,
+  n.post_id,
+  n.comment_id,
+  n.is_read
+FROM notification AS n
+LEFT JOIN "user" AS u ON u.id = n.actor_user_id;
+
+---- FUNCTIONS ----
+
+-- Create create_comment_notifications function
+-- Notify users mentioned in a comment, the author of the comment it is a reply to,
+-- and the uploader of the post.
+-- Each user is only notified once per comment, and never of their own comments.
+CREATE FUNCTION create_comment_notifications(
+  IN p_comment_id integer
+)
+RETURNS VOID
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  -- Notify mentioned users
+  INSERT INTO notification (user_id, kind, actor_user_id, post_id, comment_id)
+  SELECT cm.user_id, 'mention', pc.user_id, pc.post_id, pc.id
+  FROM post_comment AS pc
+  JOIN comment_mention AS cm ON cm.comment_id = pc.id
+  WHERE pc.id = p_comment_id
+    AND NOT EXISTS (
+      SELECT 1
+      FROM notification AS n
+      WHERE n.comment_id = pc.id
+        AND n.user_id = cm.user_id
+    );
+
+  -- Notify the author of the comment replied to
+  INSERT INTO notification (user_id, kind, actor_user_id, post_id, comment_id)
+  SELECT rc.user_id, 'reply', pc.user_id, pc.post_id, pc.id
+  FROM post_comment AS pc
+  JOIN post_comment AS rc ON rc.id = pc.reply_to_id
+  WHERE pc.id = p_comment_id
+    AND rc.user_id IS NOT NULL
+    AND NOT rc.is_deleted
+    AND rc.user_id IS DISTINCT FROM pc.user_id
+    AND NOT EXISTS (
+      SELECT 1
+      FROM notification AS n
+      WHERE n.comment_id = pc.id
+        AND n.user_id = rc.user_id
+    );
+
+  -- Notify the uploader of the post
+  INSERT INTO notification (user_id, kind, actor_user_id, post_id, comment_id)
+  SELECT p.user_id, 'comment', pc.user_id, pc.post_id, pc.id
+  FROM post_comment AS pc
+  JOIN post AS p ON p.id = pc.post_id
+  WHERE pc.id = p_comment_id
+    AND p.user_id IS DISTINCT FROM pc.user_id
+    AND NOT EXISTS (
+      SELECT 1
+      FROM notification AS n
+      WHERE n.comment_id = pc.id
+        AND n.user_id = p.user_id
+    );
+END;
+$BODY$;
+
+-- Create create_post_comment function
+-- Replies must be to a visible comment on the same post.
+-- Mentioned users that exist are recorded, except for the author.
+-- Users are notified of the comment, unless it is held.
+CREATE FUNCTION create_post_comment(
+  IN p_comment new_post_comment,
+  IN p_user_id integer,
+  IN p_mentions text[]
+)
+RETURNS post_comment
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_comment post_comment;
+  v_depth integer;
+BEGIN
+  IF p_comment.reply_to_id IS NOT NULL THEN
+    SELECT depth + 1 INTO v_depth
+    FROM post_comment
+    WHERE id = p_comment.reply_to_id
+      AND post_id = p_comment.post_id
+      AND NOT is_held;
+
+    IF NOT FOUND THEN
+      RAISE EXCEPTION 'Invalid reply comment';
+    END IF;
+  END IF;
+
+  -- Insert comment
+  INSERT INTO post_comment (
+    user_id,
+    user_name,
+    post_id,
+    comment,
+    reply_to_id,
+    depth,
+    is_held
+  )
+  SELECT
+    p_user_id, -- user_id
+    (SELECT name FROM "user" WHERE id = p_user_id), -- user_name
+    p_comment.post_id, -- post_id
+    p_comment.comment, -- comment
+    p_comment.reply_to_id, -- reply_to_id
+    COALESCE(v_depth, 0), -- depth
+    COALESCE(p_comment.is_held, false) -- is_held
+  RETURNING * INTO v_comment;
+
+  -- Record mentioned users
+  INSERT INTO comment_mention (comment_id, user_id)
+  SELECT v_comment.id, u.id
+  FROM "user" AS u
+  WHERE u.name = ANY(p_mentions)
+    AND u.id IS DISTINCT FROM p_user_id;
+
+  IF NOT v_comment.is_held THEN
+    PERFORM create_comment_notifications(v_comment.id);
+  END IF;
+
+  RETURN v_comment;
+END;
+$BODY$;
+
+-- Create approve_comment function
+-- Only admins can approve held comments.
+-- Users are notified of the comment once it is approved.
+CREATE FUNCTION approve_comment(
+  IN p_comment_id integer,
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_success boolean;
+BEGIN
+  IF NOT COALESCE((SELECT rank FROM "user" WHERE id = p_user_id) > 0, false) THEN
+    RETURN false;
+  END IF;
+
+  UPDATE post_comment
+  SET is_held = false
+  WHERE id = p_comment_id
+    AND is_held
+    AND NOT is_deleted
+  RETURNING true INTO v_success;
+
+  IF NOT COALESCE(v_success, false) THEN
+    RETURN false;
+  END IF;
+
+  PERFORM create_comment_notifications(p_comment_id);
+
+  RETURN true;
+END;
+$BODY$;
+
+-- Create update_post_tags function
+CREATE FUNCTION update_post_tags(
+  IN p_post_id integer,
+  IN p_add_tags text[],
+  IN p_remove_tags text[],
+  IN p_user_id integer,
+  IN p_new_post boolean
+)
+RETURNS VOID
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_add_tag_ids integer[];
+  v_remove_tag_ids integer[];
+  v_old_tag_ids integer[];
+  v_new_tag_ids integer[];
+  v_changed boolean;
+BEGIN
+  -- Create missing tags
+  PERFORM create_missing_tags(p_add_tags);
+
+  v_add_tag_ids := get_tag_ids(p_add_tags);
+  v_remove_tag_ids := get_tag_ids(p_remove_tags);
+
+  -- Retrieve old tags
+  v_old_tag_ids := array(SELECT tag_id FROM post_tag AS pt WHERE pt.post_id = p_post_id ORDER BY tag_id ASC);
+
+  -- Compute new tags
+  v_new_tag_ids := (v_old_tag_ids | v_add_tag_ids) - v_remove_tag_ids;
+  v_changed := v_new_tag_ids <> v_old_tag_ids;
+
+  -- Add links for added tags to post
+  INSERT INTO post_tag (post_id, tag_id)
+    SELECT p_post_id, tag_id
+    FROM unnest(v_add_tag_ids) AS tag_id
+    ON CONFLICT(post_id, tag_id)
+    DO NOTHING;
+
+  -- Remove removed tag links for post
+  DELETE FROM post_tag AS pt
+  USING unnest(v_remove_tag_ids) AS rtid
+  WHERE pt.post_id = p_post_id AND pt.tag_id = rtid;
+
+  -- Update post tags
+  UPDATE post
+  SET tags = array(SELECT tag
+                   FROM tag
+                   WHERE id = ANY(v_new_tag_ids)
+                   ORDER BY tag ASC)
+  WHERE id = p_post_id;
+
+  -- The cached tag IDs include the rating of the post, which may have changed as well
+  v_old_tag_ids := (SELECT tag_ids FROM post_tag_id_cache WHERE post_id = p_post_id);
+  v_new_tag_ids := compute_post_tag_ids(v_new_tag_ids) | (SELECT rating_tag_id(rating) FROM post WHERE id = p_post_id);
+
+  -- Update post_tag_id_cache
+  UPDATE post_tag_id_cache
+  SET tag_ids = v_new_tag_ids
+  WHERE post_id = p_post_id;
+
+  -- Update search cache to reflect added post
+  UPDATE search_cache
+  SET post_count = post_count + 1,
+      first_post_id = (CASE WHEN p_post_id > first_post_id THEN p_post_id ELSE first_post_id END),
+      last_page_post_ids = (CASE WHEN p_post_id < (SELECT MAX(id) FROM unnest(last_page_post_ids) AS id)
+                            THEN last_page_post_ids | p_post_id
+                            ELSE last_page_post_ids
+                            END)
+  WHERE v_new_tag_ids @> tag_ids
+    AND NOT v_new_tag_ids && exclude_tag_ids
+    AND (p_new_post OR (NOT v_old_tag_ids @> tag_ids) OR v_old_tag_ids && exclude_tag_ids);
+
+  -- Update search cache to reflect removed post
+  UPDATE search_cache AS sc
+  SET post_count = post_count - 1,
+      first_post_id = (CASE WHEN p_post_id = first_post_id
+                       THEN (SELECT COALESCE(MAX(post_id), 0)
+                             FROM post_tag_id_cache AS ptic
+                             WHERE ptic.tag_ids @> sc.tag_ids
+                               AND NOT ptic.tag_ids && sc.exclude_tag_ids)
+                       ELSE first_post_id
+                       END),
+      last_page_post_ids = (CASE WHEN (SELECT p_post_id BETWEEN MIN(id) AND MAX(id) FROM unnest(last_page_post_ids) AS id)
+                            THEN last_page_post_ids - p_post_id
+                            ELSE last_page_post_ids
+                            END)
+  WHERE NOT p_new_post
+    AND ((NOT v_new_tag_ids @> tag_ids) OR v_new_tag_ids && exclude_tag_ids)
+    AND v_old_tag_ids @> tag_ids
+    AND NOT v_old_tag_ids && exclude_tag_ids;
+
+  -- Track tag changes
+  INSERT INTO post_tag_change (
+    post_id,
+    user_id,
+    tag_ids_added,
+    tag_ids_removed
+  ) VALUES (
+    p_post_id,
+    p_user_id,
+    v_add_tag_ids,
+    v_remove_tag_ids
+  );
+
+  -- Notify the uploader of changes made by others
+  IF NOT p_new_post AND v_changed THEN
+    INSERT INTO notification (user_id, kind, actor_user_id, post_id)
+    SELECT p.user_id, 'tag_change', p_user_id, p.id
+    FROM post AS p
+    WHERE p.id = p_post_id
+      AND p.user_id <> p_user_id;
+  END IF;
+END;
+$BODY$;
+
+-- Create mark_notifications_read function
+-- Marks all the notifications of the user as read if no IDs are specified.
+CREATE FUNCTION mark_notifications_read(
+  IN p_user_id integer,
+  IN p_ids integer[]
+)
+RETURNS VOID
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  UPDATE notification
+  SET is_read = true
+  WHERE user_id = p_user_id
+    AND NOT is_read
+    AND (p_ids IS NULL OR id = ANY(p_ids));
+END;
+$BODY$;
@@ -0,0 +1,179 @@
+---- DROP OLD ----
+
+DROP FUNCTION update_post_tags;
+
+---- FUNCTIONS ----
+
+-- Create notify_post_event function
+-- Announce new posts on the blazebooru_event channel.
+CREATE FUNCTION notify_post_event()
+RETURNS trigger
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  PERFORM pg_notify('blazebooru_event', json_build_object(
+    'kind', 'post',
+    'post_id', NEW.id
+  )::text);
+
+  RETURN NULL;
+END;
+$BODY$;
+
+-- Create notify_post_comment_event function
+-- Announce comments on the blazebooru_event channel once they are visible,
+-- either when created or when approved.
+CREATE FUNCTION notify_post_comment_event()
+RETURNS trigger
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  IF NEW.is_held OR (TG_OP = 'UPDATE' AND NOT OLD.is_held) THEN
+    RETURN NULL;
+  END IF;
+
+  PERFORM pg_notify('blazebooru_event', json_build_object(
+    'kind', 'comment',
+    'post_id', NEW.post_id,
+    'comment_id', NEW.id
+  )::text);
+
+  RETURN NULL;
+END;
+$BODY$;
+
+-- Create update_post_tags function
+-- Tag changes are announced on the blazebooru_event channel, except for the tags
+-- set when a post is created, which are covered by the post event.
+CREATE FUNCTION update_post_tags(
+  IN p_post_id integer,
+  IN p_add_tags text[],
+  IN p_remove_tags text[],
+  IN p_user_id integer,
+  IN p_new_post boolean
+)
+RETURNS VOID
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_add_tag_ids integer[];
+  v_remove_tag_ids integer[];
+  v_old_tag_ids integer[];
+  v_new_tag_ids integer[];
+  v_changed boolean;
+BEGIN
+  -- Create missing tags
+  PERFORM create_missing_tags(p_add_tags);
+
+  v_add_tag_ids := get_tag_ids(p_add_tags);
+  v_remove_tag_ids := get_tag_ids(p_remove_tags);
+
+  -- Retrieve old tags
+  v_old_tag_ids := array(SELECT tag_id FROM post_tag AS pt WHERE pt.post_id = p_post_id ORDER BY tag_id ASC);
+
+  -- Compute new tags
+  v_new_tag_ids := (v_old_tag_ids | v_add_tag_ids) - v_remove_tag_ids;
+  v_changed := v_new_tag_ids <> v_old_tag_ids;
+
+  -- Add links for added tags to post
+  INSERT INTO post_tag (post_id, tag_id)
+    SELECT p_post_id, tag_id
+    FROM unnest(v_add_tag_ids) AS tag_id
+    ON CONFLICT(post_id, tag_id)
+    DO NOTHING;
+
+  -- Remove removed tag links for post
+  DELETE FROM post_tag AS pt
+  USING unnest(v_remove_tag_ids) AS rtid
+  WHERE pt.post_id = p_post_id AND pt.tag_id = rtid;
+
+  -- Update post tags
+  UPDATE post
+  SET tags = array(SELECT tag
+                   FROM tag
+                   WHERE id = ANY(v_new_tag_ids)
+                   ORDER BY tag ASC)
+  WHERE id = p_post_id;
+
+  -- The cached tag IDs include the rating of the post, which may have changed as well
+  v_old_tag_ids := (SELECT tag_ids FROM post_tag_id_cache WHERE post_id = p_post_id);
+  v_new_tag_ids := compute_post_tag_ids(v_new_tag_ids) | (SELECT rating_tag_id(rating) FROM post WHERE id = p_post_id);
+
+  -- Update post_tag_id_cache
+  UPDATE post_tag_id_cache
+  SET tag_ids = v_new_tag_ids
+  WHERE post_id = p_post_id;
+
+  -- Update search cache to reflect added post
+  UPDATE search_cache
+  SET post_count = post_count + 1,
+      first_post_id = (CASE WHEN p_post_id > first_post_id THEN p_post_id ELSE first_post_id END),
+      last_page_post_ids = (CASE WHEN p_post_id < (SELECT MAX(id) FROM unnest(last_page_post_ids) AS id)
+                            THEN last_page_post_ids | p_post_id
+                            ELSE last_page_post_ids
+                            END)
+  WHERE v_new_tag_ids @> tag_ids
+    AND NOT v_new_tag_ids && exclude_tag_ids
+    AND (p_new_post OR (NOT v_old_tag_ids @> tag_ids) OR v_old_tag_ids && exclude_tag_ids);
+
+  -- Update search cache to reflect removed post
+  UPDATE search_cache AS sc
+  SET post_count = post_count - 1,
+      first_post_id = (CASE WHEN p_post_id = first_post_id
+                       THEN (SELECT COALESCE(MAX(post_id), 0)
+                             FROM post_tag_id_cache AS ptic
+                             WHERE ptic.tag_ids @> sc.tag_ids
+                               AND NOT ptic.tag_ids && sc.exclude_tag_ids)
+                       ELSE first_post_id
+                       END),
+      last_page_post_ids = (CASE WHEN (SELECT p_post_id BETWEEN MIN(id) AND MAX(id) FROM unnest(last_page_post_ids) AS id)
+                            THEN last_page_post_ids - p_post_id
+                            ELSE last_page_post_ids
+                            END)
+  WHERE NOT p_new_post
+    AND ((NOT v_new_tag_ids @> tag_ids) OR v_new_tag_ids && exclude_tag_ids)
+    AND v_old_tag_ids @> tag_ids
+    AND NOT v_old_tag_ids && exclude_tag_ids;
+
+  -- Track tag changes
+  INSERT INTO post_tag_change (
+    post_id,
+    user_id,
+    tag_ids_added,
+    tag_ids_removed
+  ) VALUES (
+    p_post_id,
+    p_user_id,
+    v_add_tag_ids,
+    v_remove_tag_ids
+  );
+
+  -- Notify the uploader of changes made by others
+  IF NOT p_new_post AND v_changed THEN
+    INSERT INTO notification (user_id, kind, actor_user_id, post_id)
+    SELECT p.user_id, 'tag_change', p_user_id, p.id
+    FROM post AS p
+    WHERE p.id = p_post_id
+      AND p.user_id <> p_user_id;
+  END IF;
+
+  -- Announce the change to connected clients
+  IF NOT p_new_post THEN
+    PERFORM pg_notify('blazebooru_event', json_build_object(
+      'kind', 'tag_change',
+      'post_id', p_post_id
+    )::text);
+  END IF;
+END;
+$BODY$;
+
+---- TRIGGERS ----
+
+CREATE TRIGGER notify_event AFTER INSERT ON post
+  FOR EACH ROW EXECUTE FUNCTION notify_post_event();
+
+CREATE TRIGGER notify_event AFTER INSERT OR UPDATE OF is_held ON post_comment
+  FOR EACH ROW EXECUTE FUNCTION notify_post_comment_event();
@@ -0,0 +1,383 @@
+---- DROP OLD ----
+
+DROP FUNCTION delete_post;
+DROP FUNCTION use_api_key;
+DROP FUNCTION refresh_refresh_token;
+
+---- TABLES ----
+
+-- Add is_banned column to user.
+-- Banned users can not log in or use their API keys.
+ALTER TABLE "user"
+  ADD COLUMN is_banned boolean NOT NULL DEFAULT false;
+
+-- Create post_report table
+-- Resolved reports are kept, as a record of the decisions made.
+CREATE TABLE post_report
+(
+  id serial NOT NULL,
+  created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  post_id integer NOT NULL,
+  user_id integer NOT NULL,
+  reason text NOT NULL,
+  comment text,
+  status text NOT NULL DEFAULT 'open',
+  resolved_at timestamp with time zone,
+  resolved_by_user_id integer,
+
+  PRIMARY KEY (id),
+  CHECK (reason IN ('spam', 'illegal', 'offensive', 'wrong_rating', 'duplicate', 'other')),
+  CHECK (status IN ('open', 'dismissed', 'deleted', 'banned')),
+
+  FOREIGN KEY (post_id)
+    REFERENCES post (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID,
+
+  FOREIGN KEY (user_id)
+    REFERENCES "user" (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID,
+
+  FOREIGN KEY (resolved_by_user_id)
+    REFERENCES "user" (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE SET NULL
+    NOT VALID
+);
+
+---- INDEXES ----
+
+-- Users can only have one open report per post
+CREATE UNIQUE INDEX post_report_open_idx ON post_report
+  USING btree
+  (post_id ASC NULLS LAST, user_id ASC NULLS LAST)
+  WHERE status = 'open';
+
+---- TYPES ----
+
+-- Create new_post_report type
+CREATE TYPE new_post_report AS (
+  post_id integer,
+  reason text,
+  comment text
+);
+
+-- Create resolve_post_report_result type
+CREATE TYPE resolve_post_report_result AS (status text, sessions bigint[]);
+
+---- VIEWS ----
+
+-- Create view_post_report view
+CREATE VIEW view_post_report
+AS
+SELECT
+  r.id,
+  r.created_at,
+  r.post_id,
+  p.user_id AS post_user_id,
+  r.user_id,
+  u.name AS user_name,
+  r.reason,
+  r.comment,
+  r.status,
+  r.resolved_at,
+  r.resolved_by_user_id,
+  ru.name AS resolved_by_user_name
+FROM post_report AS r
+JOIN post AS p ON p.id = r.post_id
+JOIN "user" AS u ON u.id = r.user_id
+LEFT JOIN "user" AS ru ON ru.id = r.resolved_by_user_id;
+
+---- FUNCTIONS ----
+
+-- Create delete_post function
+-- Users can delete their own posts, and admins can delete any post.
+CREATE FUNCTION delete_post(
+  IN p_post_id integer,
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_is_admin boolean;
+  v_success boolean;
+  v_tag_ids integer[];
+BEGIN
+  v_is_admin := COALESCE((SELECT rank FROM "user" WHERE id = p_user_id) > 0, false);
+
+  -- Update post
+  UPDATE post
+  SET is_deleted = true
+  WHERE id = p_post_id
+    AND NOT is_deleted
+    AND (v_is_admin OR user_id = p_user_id)
+  RETURNING true INTO v_success;
+
+  IF NOT COALESCE(v_success, false) THEN
+    RETURN false;
+  END IF;
+
+  -- Get post tag IDs for later use
+  SELECT tag_ids INTO v_tag_ids FROM post_tag_id_cache WHERE post_id = p_post_id;
+
+  -- Delete post_tag_id_cache so that the post
+  -- will no longer be scanned for tag matches
+  DELETE FROM post_tag_id_cache
+  WHERE post_id = p_post_id;
+
+  -- Update search cache to reflect deleted post
+  UPDATE search_cache AS sc
+  SET post_count = post_count - 1,
+      first_post_id = (CASE WHEN p_post_id = first_post_id
+                       THEN (SELECT COALESCE(MAX(post_id), 0)
+                             FROM post_tag_id_cache AS ptic
+                             WHERE ptic.tag_ids @> sc.tag_ids
+                               AND NOT ptic.tag_ids && sc.exclude_tag_ids)
+                       ELSE first_post_id
+                       END),
+      last_page_post_ids = (CASE WHEN (SELECT p_post_id BETWEEN MIN(id) AND MAX(id) FROM unnest(last_page_post_ids) AS id)
+                            THEN last_page_post_ids - p_post_id
+                            ELSE last_page_post_ids
+                            END)
+  WHERE v_tag_ids @> tag_ids
+    AND NOT v_tag_ids && exclude_tag_ids;
+
+  RETURN v_success;
+END;
+$BODY$;
+
+-- Create create_post_report function
+-- Reporting a post that the user already has an open report on does nothing.
+-- Returns false if the post does not exist.
+CREATE FUNCTION create_post_report(
+  IN p_report new_post_report,
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  IF NOT EXISTS (SELECT 1 FROM post WHERE id = p_report.post_id AND NOT is_deleted) THEN
+    RETURN false;
+  END IF;
+
+  INSERT INTO post_report (post_id, user_id, reason, comment)
+  VALUES (p_report.post_id, p_user_id, p_report.reason, p_report.comment)
+  ON CONFLICT (post_id, user_id) WHERE status = 'open'
+  DO NOTHING;
+
+  RETURN true;
+END;
+$BODY$;
+
+-- Create resolve_post_report function
+-- Only admins can resolve reports.
+-- Dismissing only resolves the report itself, while deleting the post
+-- or banning its uploader resolves all open reports on the post.
+-- Admins and users who are already banned are not banned again,
+-- in which case the reports are recorded as only having deleted the post.
+-- Banned users are logged out everywhere in the same transaction.
+-- Returns the status the reports were resolved with and the sessions that were invalidated,
+-- or NULL if the report could not be resolved.
+CREATE FUNCTION resolve_post_report(
+  IN p_report_id integer,
+  IN p_status text,
+  IN p_user_id integer
+)
+RETURNS resolve_post_report_result
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_post_id integer;
+  v_status text := p_status;
+  v_post_user_id integer;
+  v_sessions bigint[] := '{}';
+BEGIN
+  IF NOT COALESCE((SELECT rank FROM "user" WHERE id = p_user_id) > 0, false) THEN
+    RETURN NULL;
+  END IF;
+
+  SELECT post_id INTO v_post_id
+  FROM post_report
+  WHERE id = p_report_id
+    AND status = 'open';
+
+  IF NOT FOUND THEN
+    RETURN NULL;
+  END IF;
+
+  IF p_status IN ('deleted', 'banned') THEN
+    PERFORM delete_post(v_post_id, p_user_id);
+  END IF;
+
+  IF p_status = 'banned' THEN
+    UPDATE "user"
+    SET is_banned = true
+    WHERE id = (SELECT user_id FROM post WHERE id = v_post_id)
+      AND rank = 0
+      AND NOT is_banned
+    RETURNING id INTO v_post_user_id;
+
+    IF FOUND THEN
+      v_sessions := invalidate_user_sessions(v_post_user_id);
+    ELSE
+      v_status := 'deleted';
+    END IF;
+  END IF;
+
+  UPDATE post_report
+  SET status = v_status,
+      resolved_at = CURRENT_TIMESTAMP,
+      resolved_by_user_id = p_user_id
+  WHERE status = 'open'
+    AND (id = p_report_id OR (p_status <> 'dismissed' AND post_id = v_post_id));
+
+  RETURN ROW(v_status, v_sessions);
+END;
+$BODY$;
+
+-- Create use_api_key function
+-- Get the API key with the specified hash, unless its user is banned.
+-- The last used time is only updated once a minute,
+-- so that every request made with the key does not write to the database.
+CREATE FUNCTION use_api_key(
+  IN p_key_hash text
+)
+RETURNS SETOF api_key
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_api_key api_key;
+BEGIN
+  SELECT * INTO v_api_key
+  FROM api_key
+  WHERE key_hash = p_key_hash
+    AND NOT (SELECT is_banned FROM "user" WHERE id = user_id);
+
+  IF v_api_key.id IS NULL THEN
+    RETURN;
+  END IF;
+
+  IF v_api_key.last_used_at IS NULL OR v_api_key.last_used_at < CURRENT_TIMESTAMP - interval '1 minute' THEN
+    UPDATE api_key
+    SET last_used_at = CURRENT_TIMESTAMP
+    WHERE id = v_api_key.id
+    RETURNING * INTO v_api_key;
+  END IF;
+
+  RETURN NEXT v_api_key;
+END;
+$BODY$;
+
+-- Create unban_user function
+-- Only admins can unban users.
+-- Returns false if the user does not exist or is not banned.
+CREATE FUNCTION unban_user(
+  IN p_user_id integer,
+  IN p_admin_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  IF NOT COALESCE((SELECT rank FROM "user" WHERE id = p_admin_user_id) > 0, false) THEN
+    RETURN false;
+  END IF;
+
+  UPDATE "user"
+  SET is_banned = false
+  WHERE id = p_user_id
+    AND is_banned;
+
+  RETURN FOUND;
+END;
+$BODY$;
+
+-- Create notify_revoked_session_event function
+-- Announce revoked sessions on the blazebooru_event channel,
+-- so that every server instance rejects their access tokens.
+CREATE FUNCTION notify_revoked_session_event()
+RETURNS trigger
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  PERFORM pg_notify('blazebooru_event', json_build_object(
+    'kind', 'session_revoked',
+    'session', NEW.session
+  )::text);
+
+  RETURN NULL;
+END;
+$BODY$;
+
+-- Create refresh_refresh_token function
+-- Reusing a token invalidates its session, which is returned without a new token
+-- so that its access tokens can be revoked as well.
+-- Banned users cannot refresh their tokens.
+CREATE FUNCTION refresh_refresh_token(
+  IN p_token uuid,
+  IN p_ip inet,
+  IN p_user_agent text
+)
+RETURNS refresh_refresh_token_result
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_refresh_token refresh_token;
+  v_result refresh_refresh_token_result;
+BEGIN
+  SELECT * INTO v_refresh_token
+  FROM refresh_token
+  WHERE token = p_token;
+
+  -- Check if exists
+  IF v_refresh_token IS NULL THEN
+    RETURN NULL;
+  END IF;
+
+  -- Check if already used
+  IF v_refresh_token.used THEN
+    PERFORM invalidate_session(v_refresh_token.session);
+    RETURN ROW(NULL::uuid, v_refresh_token.session, v_refresh_token.user_id);
+  END IF;
+
+  -- Check if expired
+  IF v_refresh_token.expires_at < CURRENT_TIMESTAMP THEN
+    RETURN NULL;
+  END IF;
+
+  -- Check if banned
+  IF (SELECT is_banned FROM "user" WHERE id = v_refresh_token.user_id) THEN
+    RETURN NULL;
+  END IF;
+
+  -- Mark token as used
+  UPDATE refresh_token
+  SET used = true, used_ip = p_ip
+  WHERE id = v_refresh_token.id;
+
+  -- Generate new refresh token
+  INSERT INTO refresh_token (session, user_id, created_ip, user_agent)
+  VALUES (v_refresh_token.session, v_refresh_token.user_id, p_ip, p_user_agent)
+  RETURNING token, session, user_id INTO v_result;
+
+  -- Return result
+  RETURN v_result;
+END;
+$BODY$;
+
+---- TRIGGERS ----
+
+CREATE TRIGGER notify_event AFTER INSERT ON revoked_session
+  FOR EACH ROW EXECUTE FUNCTION notify_revoked_session_event();
@@ -0,0 +1,134 @@
+---- TYPES ----
+
+-- Create purge_post_result type
+CREATE TYPE purge_post_result AS (
+  hash text,
+  ext text,
+  tn_ext text,
+  is_file_shared boolean
+);
+
+---- VIEWS ----
+
+-- Create view_deleted_post view
+-- Deleted posts, in the same shape as view_post.
+CREATE VIEW view_deleted_post
+AS
+SELECT
+  p.id,
+  p.created_at,
+  p.user_id,
+  u.name AS user_name,
+  p.title,
+  p.description,
+  p.source,
+  p.filename,
+  p.size,
+  p.width,
+  p.height,
+  p.hash,
+  p.ext,
+  p.tn_ext,
+  p.tags,
+  p.fav_count,
+  p.score,
+  p.parent_id,
+  p.rating
+FROM post AS p
+JOIN "user" AS u ON u.id = p.user_id
+WHERE is_deleted;
+
+---- FUNCTIONS ----
+
+-- Create undelete_post function
+-- Only admins can undelete posts.
+CREATE FUNCTION undelete_post(
+  IN p_post_id integer,
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_success boolean;
+  v_tag_ids integer[];
+BEGIN
+  IF NOT COALESCE((SELECT rank FROM "user" WHERE id = p_user_id) > 0, false) THEN
+    RETURN false;
+  END IF;
+
+  -- Update post
+  UPDATE post
+  SET is_deleted = false
+  WHERE id = p_post_id
+    AND is_deleted
+  RETURNING true INTO v_success;
+
+  IF NOT COALESCE(v_success, false) THEN
+    RETURN false;
+  END IF;
+
+  v_tag_ids := compute_post_tag_ids(array(SELECT tag_id FROM post_tag AS pt WHERE pt.post_id = p_post_id ORDER BY tag_id ASC))
+    | (SELECT rating_tag_id(rating) FROM post WHERE id = p_post_id);
+
+  -- Recreate post_tag_id_cache so that the post
+  -- will be scanned for tag matches again
+  INSERT INTO post_tag_id_cache (post_id, tag_ids) VALUES (p_post_id, v_tag_ids);
+
+  -- Update search cache to reflect restored post
+  UPDATE search_cache
+  SET post_count = post_count + 1,
+      first_post_id = (CASE WHEN p_post_id > first_post_id THEN p_post_id ELSE first_post_id END),
+      last_page_post_ids = (CASE WHEN p_post_id < (SELECT MAX(id) FROM unnest(last_page_post_ids) AS id)
+                            THEN last_page_post_ids | p_post_id
+                            ELSE last_page_post_ids
+                            END)
+  WHERE v_tag_ids @> tag_ids
+    AND NOT v_tag_ids && exclude_tag_ids;
+
+  RETURN v_success;
+END;
+$BODY$;
+
+-- Create purge_post function
+-- Permanently remove a deleted post.
+-- Only admins can purge posts.
+-- Returns the files of the post, and whether another post still uses them.
+CREATE FUNCTION purge_post(
+  IN p_post_id integer,
+  IN p_user_id integer
+)
+RETURNS SETOF purge_post_result
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_post post;
+BEGIN
+  IF NOT COALESCE((SELECT rank FROM "user" WHERE id = p_user_id) > 0, false) THEN
+    RETURN;
+  END IF;
+
+  DELETE FROM post
+  WHERE id = p_post_id
+    AND is_deleted
+  RETURNING * INTO v_post;
+
+  IF v_post.id IS NULL THEN
+    RETURN;
+  END IF;
+
+  -- Tag changes are not linked to the post, so they are removed explicitly
+  DELETE FROM post_tag_change
+  WHERE post_id = p_post_id;
+
+  -- Deleted posts still count as using the files, as they can be undeleted
+  RETURN QUERY
+  SELECT
+    v_post.hash,
+    v_post.ext,
+    v_post.tn_ext,
+    EXISTS (SELECT 1 FROM post WHERE hash = v_post.hash);
+END;
+$BODY$;
@@ -0,0 +1,541 @@
+---- DROP OLD ----
+
+DROP FUNCTION resolve_post_report;
+DROP FUNCTION delete_post;
+DROP FUNCTION undelete_post;
+DROP FUNCTION purge_post;
+DROP FUNCTION approve_comment;
+DROP FUNCTION delete_comment;
+DROP FUNCTION update_tag;
+DROP FUNCTION unban_user;
+
+---- TABLES ----
+
+-- Create audit_log table
+-- The target is not a foreign key, so that entries are kept
+-- when what they are about is removed.
+CREATE TABLE audit_log
+(
+  id serial NOT NULL,
+  created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  user_id integer,
+  action text NOT NULL,
+  target_type text NOT NULL,
+  target_id integer NOT NULL,
+  details text,
+
+  PRIMARY KEY (id),
+
+  FOREIGN KEY (user_id)
+    REFERENCES "user" (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE SET NULL
+    NOT VALID
+);
+
+---- INDEXES ----
+
+CREATE INDEX audit_log_user_id_idx ON audit_log
+  USING btree
+  (user_id ASC NULLS LAST);
+
+CREATE INDEX audit_log_action_idx ON audit_log
+  USING btree
+  (action ASC NULLS LAST);
+
+CREATE INDEX audit_log_target_idx ON audit_log
+  USING btree
+  (target_type ASC NULLS LAST, target_id ASC NULLS LAST);
+
+---- TYPES ----
+
+-- Create new_audit_log_entry type
+CREATE TYPE new_audit_log_entry AS (
+  action text,
+  target_type text,
+  target_id integer,
+  details text
+);
+
+---- VIEWS ----
+
+-- Create view_audit_log view
+CREATE VIEW view_audit_log
+AS
+SELECT
+  a.id,
+  a.created_at,
+  a.user_id,
+  u.name AS user_name,
+  a.action,
+  a.target_type,
+  a.target_id,
+  a.details
+FROM audit_log AS a
+LEFT JOIN "user" AS u ON u.id = a.user_id;
+
+---- FUNCTIONS ----
+
+-- Create create_audit_log_entry function
+CREATE FUNCTION create_audit_log_entry(
+  IN p_entry new_audit_log_entry,
+  IN p_user_id integer
+)
+RETURNS VOID
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  INSERT INTO audit_log (user_id, action, target_type, target_id, details)
+  VALUES (p_user_id, p_entry.action, p_entry.target_type, p_entry.target_id, p_entry.details);
+END;
+$BODY$;
+
+-- Create delete_post function
+-- Users can delete their own posts, and admins can delete any post.
+-- Deleting the post of another user is recorded in the audit log with the specified details.
+CREATE FUNCTION delete_post(
+  IN p_post_id integer,
+  IN p_user_id integer,
+  IN p_audit_details text
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_is_admin boolean;
+  v_success boolean;
+  v_post_user_id integer;
+  v_tag_ids integer[];
+BEGIN
+  v_is_admin := COALESCE((SELECT rank FROM "user" WHERE id = p_user_id) > 0, false);
+
+  -- Update post
+  UPDATE post
+  SET is_deleted = true
+  WHERE id = p_post_id
+    AND NOT is_deleted
+    AND (v_is_admin OR user_id = p_user_id)
+  RETURNING true, user_id INTO v_success, v_post_user_id;
+
+  IF NOT COALESCE(v_success, false) THEN
+    RETURN false;
+  END IF;
+
+  -- Get post tag IDs for later use
+  SELECT tag_ids INTO v_tag_ids FROM post_tag_id_cache WHERE post_id = p_post_id;
+
+  -- Delete post_tag_id_cache so that the post
+  -- will no longer be scanned for tag matches
+  DELETE FROM post_tag_id_cache
+  WHERE post_id = p_post_id;
+
+  -- Update search cache to reflect deleted post
+  UPDATE search_cache AS sc
+  SET post_count = post_count - 1,
+      first_post_id = (CASE WHEN p_post_id = first_post_id
+                       THEN (SELECT COALESCE(MAX(post_id), 0)
+                             FROM post_tag_id_cache AS ptic
+                             WHERE ptic.tag_ids @> sc.tag_ids
+                               AND NOT ptic.tag_ids && sc.exclude_tag_ids)
+                       ELSE first_post_id
+                       END),
+      last_page_post_ids = (CASE WHEN (SELECT p_post_id BETWEEN MIN(id) AND MAX(id) FROM unnest(last_page_post_ids) AS id)
+                            THEN last_page_post_ids - p_post_id
+                            ELSE last_page_post_ids
+                            END)
+  WHERE v_tag_ids @> tag_ids
+    AND NOT v_tag_ids && exclude_tag_ids;
+
+  IF v_post_user_id <> p_user_id THEN
+    PERFORM create_audit_log_entry(ROW('delete_post', 'post', p_post_id, p_audit_details)::new_audit_log_entry, p_user_id);
+  END IF;
+
+  RETURN v_success;
+END;
+$BODY$;
+
+-- Create undelete_post function
+-- Only admins can undelete posts.
+CREATE FUNCTION undelete_post(
+  IN p_post_id integer,
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_success boolean;
+  v_tag_ids integer[];
+BEGIN
+  IF NOT COALESCE((SELECT rank FROM "user" WHERE id = p_user_id) > 0, false) THEN
+    RETURN false;
+  END IF;
+
+  -- Update post
+  UPDATE post
+  SET is_deleted = false
+  WHERE id = p_post_id
+    AND is_deleted
+  RETURNING true INTO v_success;
+
+  IF NOT COALESCE(v_success, false) THEN
+    RETURN false;
+  END IF;
+
+  v_tag_ids := compute_post_tag_ids(array(SELECT tag_id FROM post_tag AS pt WHERE pt.post_id = p_post_id ORDER BY tag_id ASC))
+    | (SELECT rating_tag_id(rating) FROM post WHERE id = p_post_id);
+
+  -- Recreate post_tag_id_cache so that the post
+  -- will be scanned for tag matches again
+  INSERT INTO post_tag_id_cache (post_id, tag_ids) VALUES (p_post_id, v_tag_ids);
+
+  -- Update search cache to reflect restored post
+  UPDATE search_cache
+  SET post_count = post_count + 1,
+      first_post_id = (CASE WHEN p_post_id > first_post_id THEN p_post_id ELSE first_post_id END),
+      last_page_post_ids = (CASE WHEN p_post_id < (SELECT MAX(id) FROM unnest(last_page_post_ids) AS id)
+                            THEN last_page_post_ids | p_post_id
+                            ELSE last_page_post_ids
+                            END)
+  WHERE v_tag_ids @> tag_ids
+    AND NOT v_tag_ids && exclude_tag_ids;
+
+  PERFORM create_audit_log_entry(ROW('undelete_post', 'post', p_post_id, NULL)::new_audit_log_entry, p_user_id);
+
+  RETURN v_success;
+END;
+$BODY$;
+
+-- Create purge_post function
+-- Permanently remove a deleted post.
+-- Only admins can purge posts.
+-- Returns the files of the post, and whether another post still uses them.
+CREATE FUNCTION purge_post(
+  IN p_post_id integer,
+  IN p_user_id integer
+)
+RETURNS SETOF purge_post_result
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_post post;
+BEGIN
+  IF NOT COALESCE((SELECT rank FROM "user" WHERE id = p_user_id) > 0, false) THEN
+    RETURN;
+  END IF;
+
+  DELETE FROM post
+  WHERE id = p_post_id
+    AND is_deleted
+  RETURNING * INTO v_post;
+
+  IF v_post.id IS NULL THEN
+    RETURN;
+  END IF;
+
+  -- Tag changes are not linked to the post, so they are removed explicitly
+  DELETE FROM post_tag_change
+  WHERE post_id = p_post_id;
+
+  -- The hash identifies the files of the post, which may still exist elsewhere
+  PERFORM create_audit_log_entry(ROW('purge_post', 'post', p_post_id, v_post.hash)::new_audit_log_entry, p_user_id);
+
+  -- Deleted posts still count as using the files, as they can be undeleted
+  RETURN QUERY
+  SELECT
+    v_post.hash,
+    v_post.ext,
+    v_post.tn_ext,
+    EXISTS (SELECT 1 FROM post WHERE hash = v_post.hash);
+END;
+$BODY$;
+
+-- Create approve_comment function
+-- Only admins can approve held comments.
+-- Users are notified of the comment once it is approved.
+CREATE FUNCTION approve_comment(
+  IN p_comment_id integer,
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_success boolean;
+BEGIN
+  IF NOT COALESCE((SELECT rank FROM "user" WHERE id = p_user_id) > 0, false) THEN
+    RETURN false;
+  END IF;
+
+  UPDATE post_comment
+  SET is_held = false
+  WHERE id = p_comment_id
+    AND is_held
+    AND NOT is_deleted
+  RETURNING true INTO v_success;
+
+  IF NOT COALESCE(v_success, false) THEN
+    RETURN false;
+  END IF;
+
+  PERFORM create_comment_notifications(p_comment_id);
+
+  PERFORM create_audit_log_entry(ROW('approve_comment', 'comment', p_comment_id, NULL)::new_audit_log_entry, p_user_id);
+
+  RETURN true;
+END;
+$BODY$;
+
+-- Create delete_comment function
+-- Users can only delete their own comments, and only if they were created after the specified time.
+-- Admins can delete any comment, which is recorded in the audit log unless it is their own,
+-- including anonymous comments.
+CREATE FUNCTION delete_comment(
+  IN p_comment_id integer,
+  IN p_user_id integer,
+  IN p_created_after timestamp with time zone
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_is_admin boolean;
+  v_success boolean;
+  v_comment_user_id integer;
+BEGIN
+  v_is_admin := COALESCE((SELECT rank FROM "user" WHERE id = p_user_id) > 0, false);
+
+  UPDATE comment
+  SET is_deleted = true
+  WHERE id = p_comment_id
+    AND NOT is_deleted
+    AND (v_is_admin OR (user_id = p_user_id AND created_at > p_created_after))
+  RETURNING true, user_id INTO v_success, v_comment_user_id;
+
+  IF v_success AND v_comment_user_id IS DISTINCT FROM p_user_id THEN
+    PERFORM create_audit_log_entry(ROW('delete_comment', 'comment', p_comment_id, NULL)::new_audit_log_entry, p_user_id);
+  END IF;
+
+  RETURN COALESCE(v_success, false);
+END;
+$BODY$;
+
+-- Create update_tag function
+-- The requested changes are recorded in the audit log.
+CREATE FUNCTION update_tag(
+  IN p_tag_id integer,
+  IN p_update_tag update_tag,
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_add_alias_ids integer[];
+  v_remove_alias_ids integer[];
+  v_old_alias_ids integer[];
+  v_add_implied_tag_ids integer[];
+  v_remove_implied_tag_ids integer[];
+  v_old_implied_tag_ids integer[];
+  v_new_implied_tag_ids integer[];
+  v_affected_tag_ids integer[];
+BEGIN
+  IF NOT can_user_edit_tag(p_tag_id, p_user_id) THEN
+    RETURN false;
+  END IF;
+
+  v_add_implied_tag_ids := get_tag_ids(p_update_tag.add_implied_tags);
+  v_remove_implied_tag_ids := get_tag_ids(p_update_tag.remove_implied_tags);
+
+  -- Retrieve implied tag ids
+  SELECT implied_tag_ids
+  INTO v_old_implied_tag_ids
+  FROM tag
+  WHERE id = p_tag_id;
+
+  -- Compute new implied tag ids
+  v_new_implied_tag_ids := (v_old_implied_tag_ids | v_add_implied_tag_ids) - v_remove_implied_tag_ids;
+
+  -- Update tag
+  UPDATE tag
+  SET implied_tag_ids = v_new_implied_tag_ids
+  WHERE id = p_tag_id;
+
+  -- Get ids of removed aliases
+  v_remove_alias_ids := get_tag_ids(p_update_tag.remove_aliases);
+
+  IF cardinality(p_update_tag.add_aliases) > 0 THEN
+    -- Create missing tags for added aliases
+    PERFORM create_missing_tags(p_update_tag.add_aliases);
+
+    -- Retrieve old alias ids
+    SELECT COALESCE(array_agg(id), '{}')
+    INTO v_old_alias_ids
+    FROM tag
+    WHERE alias_of_tag_id = p_tag_id;
+
+    -- Get ids of added aliases
+    v_add_alias_ids := get_tag_ids(p_update_tag.add_aliases) - v_old_alias_ids - v_remove_alias_ids;
+
+    -- Set alias_of_tag_id for added aliases
+    UPDATE tag
+    SET alias_of_tag_id = p_tag_id
+    WHERE id = ANY(v_add_alias_ids);
+
+    -- Set any aliases of added aliases to be aliases of this tag
+    UPDATE tag
+    SET alias_of_tag_id = p_tag_id
+    WHERE alias_of_tag_id = ANY(v_add_alias_ids);
+  END IF;
+
+  IF icount(v_remove_alias_ids) > 0 THEN
+    -- Get actual alias ids that will be removed
+    SELECT array_agg(id)
+    INTO v_remove_alias_ids
+    FROM tag
+    WHERE alias_of_tag_id = p_tag_id AND id = ANY(v_remove_alias_ids);
+
+    -- Clear alias_of_tag_id of removed aliases
+    UPDATE tag
+    SET alias_of_tag_id = NULL
+    WHERE id = ANY(v_remove_alias_ids);
+  END IF;
+
+  v_affected_tag_ids := v_add_alias_ids || v_remove_alias_ids;
+
+  IF v_new_implied_tag_ids <> v_old_implied_tag_ids THEN
+    v_affected_tag_ids := v_affected_tag_ids + p_tag_id | v_old_implied_tag_ids | v_new_implied_tag_ids;
+  END IF;
+
+  IF icount(v_affected_tag_ids) > 0 THEN
+    v_affected_tag_ids := v_affected_tag_ids | compute_post_tag_ids(v_affected_tag_ids);
+
+    -- Update pre-calculated post tag ID cache
+    UPDATE post_tag_id_cache AS ptic
+    SET tag_ids = compute_post_tag_ids(array(SELECT tag_id FROM post_tag AS pt WHERE pt.post_id = ptic.post_id))
+      | (SELECT rating_tag_id(p.rating) FROM post AS p WHERE p.id = ptic.post_id)
+    WHERE tag_ids && v_affected_tag_ids;
+
+    -- Delete cached searches affected by alias change
+    DELETE FROM search_cache
+    WHERE tag_ids && v_affected_tag_ids
+       OR exclude_tag_ids && v_affected_tag_ids;
+  END IF;
+
+  PERFORM create_audit_log_entry(ROW('update_tag', 'tag', p_tag_id, row_to_json(p_update_tag)::text)::new_audit_log_entry, p_user_id);
+
+  RETURN true;
+END;
+$BODY$;
+
+-- Create resolve_post_report function
+-- Only admins can resolve reports.
+-- Dismissing only resolves the report itself, while deleting the post
+-- or banning its uploader resolves all open reports on the post.
+-- Admins and users who are already banned are not banned again,
+-- in which case the reports are recorded as only having deleted the post.
+-- Banned users are logged out everywhere in the same transaction.
+-- The actions actually taken are recorded in the audit log.
+-- Returns the status the reports were resolved with and the sessions that were invalidated,
+-- or NULL if the report could not be resolved.
+CREATE FUNCTION resolve_post_report(
+  IN p_report_id integer,
+  IN p_status text,
+  IN p_user_id integer
+)
+RETURNS resolve_post_report_result
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_post_id integer;
+  v_status text := p_status;
+  v_post_user_id integer;
+  v_details text := 'report ' || p_report_id;
+  v_sessions bigint[] := '{}';
+BEGIN
+  IF NOT COALESCE((SELECT rank FROM "user" WHERE id = p_user_id) > 0, false) THEN
+    RETURN NULL;
+  END IF;
+
+  SELECT post_id INTO v_post_id
+  FROM post_report
+  WHERE id = p_report_id
+    AND status = 'open';
+
+  IF NOT FOUND THEN
+    RETURN NULL;
+  END IF;
+
+  IF p_status = 'dismissed' THEN
+    PERFORM create_audit_log_entry(ROW('dismiss_report', 'report', p_report_id, NULL)::new_audit_log_entry, p_user_id);
+  END IF;
+
+  IF p_status IN ('deleted', 'banned') THEN
+    PERFORM delete_post(v_post_id, p_user_id, v_details);
+  END IF;
+
+  IF p_status = 'banned' THEN
+    UPDATE "user"
+    SET is_banned = true
+    WHERE id = (SELECT user_id FROM post WHERE id = v_post_id)
+      AND rank = 0
+      AND NOT is_banned
+    RETURNING id INTO v_post_user_id;
+
+    IF FOUND THEN
+      PERFORM create_audit_log_entry(ROW('ban_user', 'user', v_post_user_id, v_details)::new_audit_log_entry, p_user_id);
+      v_sessions := invalidate_user_sessions(v_post_user_id);
+    ELSE
+      v_status := 'deleted';
+    END IF;
+  END IF;
+
+  UPDATE post_report
+  SET status = v_status,
+      resolved_at = CURRENT_TIMESTAMP,
+      resolved_by_user_id = p_user_id
+  WHERE status = 'open'
+    AND (id = p_report_id OR (p_status <> 'dismissed' AND post_id = v_post_id));
+
+  RETURN ROW(v_status, v_sessions);
+END;
+$BODY$;
+
+-- Create unban_user function
+-- Only admins can unban users.
+-- Returns false if the user does not exist or is not banned.
+CREATE FUNCTION unban_user(
+  IN p_user_id integer,
+  IN p_admin_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  IF NOT COALESCE((SELECT rank FROM "user" WHERE id = p_admin_user_id) > 0, false) THEN
+    RETURN false;
+  END IF;
+
+  UPDATE "user"
+  SET is_banned = false
+  WHERE id = p_user_id
+    AND is_banned;
+
+  IF NOT FOUND THEN
+    RETURN false;
+  END IF;
+
+  PERFORM create_audit_log_entry(ROW('unban_user', 'user', p_user_id, NULL)::new_audit_log_entry, p_admin_user_id);
+
+  RETURN true;
+END;
+$BODY$;
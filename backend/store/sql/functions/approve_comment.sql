@@ -0,0 +1,35 @@
+-- Only admins can approve held comments.
+-- Users are notified of the comment once it is approved.
+CREATE FUNCTION approve_comment(
+  IN p_comment_id integer,
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_success boolean;
+BEGIN
+  IF NOT COALESCE((SELECT rank FROM "user" WHERE id = p_user_id) > 0, false) THEN
+    RETURN false;
+  END IF;
+
+  UPDATE post_comment
+  SET is_held = false
+  WHERE id = p_comment_id
+    AND is_held
+    AND NOT is_deleted
+  RETURNING true INTO v_success;
+
+  IF NOT COALESCE(v_success, false) THEN
+    RETURN false;
+  END IF;
+
+  PERFORM create_comment_notifications(p_comment_id);
+
+  PERFORM create_audit_log_entry(ROW('approve_comment', 'comment', p_comment_id, NULL)::new_audit_log_entry, p_user_id);
+
+  RETURN true;
+END;
+$BODY$;
@@ -1,6 +1,7 @@
 CREATE FUNCTION calculate_last_page(
   IN p_include_tags text[],
   IN p_exclude_tags text[],
+  IN p_options search_options,
   IN p_posts_per_page integer
 )
 RETURNS page_info
@@ -16,11 +17,30 @@ DECLARE
   v_last_page_start_id integer;
   v_last_page_post_ids integer[];
 BEGIN
-  SELECT * INTO v_tag_ids, v_exclude_tag_ids, v_valid FROM resolve_search_tags(p_include_tags, p_exclude_tags);
+  SELECT * INTO v_tag_ids, v_exclude_tag_ids, v_valid FROM resolve_search_tags(p_include_tags, p_exclude_tags, p_options.ratings);
   IF NOT v_valid THEN
     RETURN (1, 0)::page_info;
   END IF;
 
+  IF NOT is_cached_search(p_options) THEN
+    SELECT COUNT(*)::integer INTO v_post_count
+    FROM search_posts(v_tag_ids, v_exclude_tag_ids, p_options);
+
+    IF v_post_count = 0 THEN
+      RETURN (1, 0)::page_info;
+    END IF;
+
+    v_page_count := CEIL(v_post_count::real / p_posts_per_page);
+
+    SELECT s.post_id INTO v_last_page_start_id
+    FROM search_posts(v_tag_ids, v_exclude_tag_ids, p_options) AS s
+    ORDER BY s.sort_key DESC, s.post_id DESC
+    OFFSET (v_page_count - 1) * p_posts_per_page
+    LIMIT 1;
+
+    RETURN (v_page_count, v_last_page_start_id)::page_info;
+  END IF;
+
   -- Make sure search cache is initialized
   PERFORM initialize_search_cache(v_tag_ids, v_exclude_tag_ids);
 
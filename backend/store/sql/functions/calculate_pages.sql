@@ -3,6 +3,7 @@
 CREATE FUNCTION calculate_pages(
   IN p_include_tags text[],
   IN p_exclude_tags text[],
+  IN p_options search_options,
   IN p_posts_per_page integer,
   IN p_page_count integer,
   IN p_origin_page page_info
@@ -20,11 +21,39 @@ DECLARE
   v_start_id integer;
   v_last_id integer;
 BEGIN
-  SELECT * INTO v_tag_ids, v_exclude_tag_ids, v_valid FROM resolve_search_tags(p_include_tags, p_exclude_tags);
+  SELECT * INTO v_tag_ids, v_exclude_tag_ids, v_valid FROM resolve_search_tags(p_include_tags, p_exclude_tags, p_options.ratings);
   IF NOT v_valid THEN
     RETURN v_pages;
   END IF;
 
+  IF NOT is_cached_search(p_options) THEN
+    v_start_id := COALESCE(p_origin_page.start_id, 2147483647);
+
+    v_pages := array(
+      SELECT (no, start_id)::page_info
+      FROM (
+        SELECT
+          COALESCE(p_origin_page.no, 1) + ROW_NUMBER() OVER () - 1 AS no,
+          x.id AS start_id
+        FROM (
+          SELECT
+            s.post_id AS id,
+            ROW_NUMBER() OVER (ORDER BY s.sort_key DESC, s.post_id DESC) AS rn
+          FROM search_posts(v_tag_ids, v_exclude_tag_ids, p_options) AS s
+          WHERE
+            -- Only scan forward from start ID
+            (s.sort_key, s.post_id) <= (search_sort_key(v_start_id, p_options.sort), v_start_id)
+          ORDER BY s.sort_key DESC, s.post_id DESC
+          LIMIT p_page_count * p_posts_per_page -- X pages at a time
+        ) AS x
+        WHERE MOD(x.rn - 1, p_posts_per_page) = 0
+      ) AS x
+      WHERE x.no > COALESCE(p_origin_page.no, 0)
+    );
+
+    RETURN v_pages;
+  END IF;
+
   -- Make sure search cache is initialized
   PERFORM initialize_search_cache(v_tag_ids, v_exclude_tag_ids);
 
@@ -3,6 +3,7 @@
 CREATE FUNCTION calculate_pages_reverse(
   IN p_include_tags text[],
   IN p_exclude_tags text[],
+  IN p_options search_options,
   IN p_posts_per_page integer,
   IN p_page_count integer,
   IN p_origin_page page_info
@@ -19,11 +20,44 @@ DECLARE
   v_start_id integer;
   v_last_id integer;
 BEGIN
-  SELECT * INTO v_tag_ids, v_exclude_tag_ids, v_valid FROM resolve_search_tags(p_include_tags, p_exclude_tags);
+  SELECT * INTO v_tag_ids, v_exclude_tag_ids, v_valid FROM resolve_search_tags(p_include_tags, p_exclude_tags, p_options.ratings);
   IF NOT v_valid THEN
     RETURN v_pages;
   END IF;
 
+  IF NOT is_cached_search(p_options) THEN
+    -- Previous pages can only be calculated from a known page
+    IF p_origin_page.start_id IS NULL THEN
+      RETURN v_pages;
+    END IF;
+
+    v_last_id := p_origin_page.start_id;
+
+    v_pages := array(
+      SELECT (no, start_id)::page_info
+      FROM (
+        SELECT
+          p_origin_page.no - ROW_NUMBER() OVER () + 1 AS no,
+          x.id AS start_id
+        FROM (
+          SELECT
+            s.post_id AS id,
+            ROW_NUMBER() OVER (ORDER BY s.sort_key ASC, s.post_id ASC) AS rn
+          FROM search_posts(v_tag_ids, v_exclude_tag_ids, p_options) AS s
+          WHERE
+            -- Only scan backwards from the origin
+            (s.sort_key, s.post_id) >= (search_sort_key(v_last_id, p_options.sort), v_last_id)
+          ORDER BY s.sort_key ASC, s.post_id ASC
+          LIMIT ((p_page_count + 1) * p_posts_per_page) -- X pages at a time
+        ) AS x
+        WHERE MOD(x.rn - 1, p_posts_per_page) = 0
+      ) AS x
+      WHERE x.no < p_origin_page.no
+    );
+
+    RETURN v_pages;
+  END IF;
+
   -- Make sure search cache is initialized
   PERFORM initialize_search_cache(v_tag_ids, v_exclude_tag_ids);
 
@@ -0,0 +1,22 @@
+CREATE FUNCTION can_user_edit_pool(
+  IN p_pool_id integer,
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  -- If user is some sort of admin, allow edit
+  IF (SELECT rank FROM "user" WHERE id = p_user_id) > 0 THEN
+    RETURN true;
+  END IF;
+
+  -- If user is the creator of the pool, allow edit
+  IF p_user_id = (SELECT user_id FROM pool WHERE id = p_pool_id) THEN
+    RETURN true;
+  END IF;
+
+  RETURN false;
+END;
+$BODY$;
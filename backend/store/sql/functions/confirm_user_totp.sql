@@ -0,0 +1,33 @@
+CREATE FUNCTION confirm_user_totp(
+  IN p_user_id integer,
+  IN p_step bigint,
+  IN p_recovery_code_hashes text[]
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_success boolean;
+BEGIN
+  UPDATE user_totp
+  SET confirmed = true, last_used_step = p_step
+  WHERE user_id = p_user_id
+    AND NOT confirmed
+  RETURNING true INTO v_success;
+
+  -- Check if there was a pending enrollment
+  IF v_success IS NULL THEN
+    RETURN false;
+  END IF;
+
+  -- Replace any previous recovery codes
+  DELETE FROM user_recovery_code
+  WHERE user_id = p_user_id;
+
+  INSERT INTO user_recovery_code (user_id, code_hash)
+  SELECT p_user_id, unnest(p_recovery_code_hashes);
+
+  RETURN true;
+END;
+$BODY$;
@@ -0,0 +1,23 @@
+-- Count the posts matching a search.
+CREATE FUNCTION count_posts(
+  IN p_include_tags text[],
+  IN p_exclude_tags text[],
+  IN p_options search_options
+)
+RETURNS integer
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_tag_ids integer[];
+  v_exclude_tag_ids integer[];
+  v_valid boolean;
+BEGIN
+  SELECT * INTO v_tag_ids, v_exclude_tag_ids, v_valid FROM resolve_search_tags(p_include_tags, p_exclude_tags, p_options.ratings);
+  IF NOT v_valid THEN
+    RETURN 0;
+  END IF;
+
+  RETURN (SELECT count(*) FROM search_posts(v_tag_ids, v_exclude_tag_ids, p_options));
+END;
+$BODY$ STABLE;
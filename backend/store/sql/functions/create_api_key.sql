@@ -0,0 +1,30 @@
+CREATE FUNCTION create_api_key(
+  IN p_api_key new_api_key,
+  IN p_user_id integer
+)
+RETURNS api_key
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_api_key api_key;
+BEGIN
+  -- Insert API key
+  INSERT INTO api_key (
+    user_id,
+    name,
+    key_hash,
+    key_prefix,
+    scopes
+  )
+  SELECT
+    p_user_id, -- user_id
+    p_api_key.name, -- name
+    p_api_key.key_hash, -- key_hash
+    p_api_key.key_prefix, -- key_prefix
+    p_api_key.scopes -- scopes
+  RETURNING * INTO v_api_key;
+
+  RETURN v_api_key;
+END;
+$BODY$;
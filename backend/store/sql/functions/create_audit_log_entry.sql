@@ -0,0 +1,13 @@
+CREATE FUNCTION create_audit_log_entry(
+  IN p_entry new_audit_log_entry,
+  IN p_user_id integer
+)
+RETURNS VOID
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  INSERT INTO audit_log (user_id, action, target_type, target_id, details)
+  VALUES (p_user_id, p_entry.action, p_entry.target_type, p_entry.target_id, p_entry.details);
+END;
+$BODY$;
@@ -0,0 +1,55 @@
+-- Notify users mentioned in a comment, the author of the comment it is a reply to,
+-- and the uploader of the post.
+-- Each user is only notified once per comment, and never of their own comments.
+CREATE FUNCTION create_comment_notifications(
+  IN p_comment_id integer
+)
+RETURNS VOID
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  -- Notify mentioned users
+  INSERT INTO notification (user_id, kind, actor_user_id, post_id, comment_id)
+  SELECT cm.user_id, 'mention', pc.user_id, pc.post_id, pc.id
+  FROM post_comment AS pc
+  JOIN comment_mention AS cm ON cm.comment_id = pc.id
+  WHERE pc.id = p_comment_id
+    AND NOT EXISTS (
+      SELECT 1
+      FROM notification AS n
+      WHERE n.comment_id = pc.id
+        AND n.user_id = cm.user_id
+    );
+
+  -- Notify the author of the comment replied to
+  INSERT INTO notification (user_id, kind, actor_user_id, post_id, comment_id)
+  SELECT rc.user_id, 'reply', pc.user_id, pc.post_id, pc.id
+  FROM post_comment AS pc
+  JOIN post_comment AS rc ON rc.id = pc.reply_to_id
+  WHERE pc.id = p_comment_id
+    AND rc.user_id IS NOT NULL
+    AND NOT rc.is_deleted
+    AND rc.user_id IS DISTINCT FROM pc.user_id
+    AND NOT EXISTS (
+      SELECT 1
+      FROM notification AS n
+      WHERE n.comment_id = pc.id
+        AND n.user_id = rc.user_id
+    );
+
+  -- Notify the uploader of the post
+  INSERT INTO notification (user_id, kind, actor_user_id, post_id, comment_id)
+  SELECT p.user_id, 'comment', pc.user_id, pc.post_id, pc.id
+  FROM post_comment AS pc
+  JOIN post AS p ON p.id = pc.post_id
+  WHERE pc.id = p_comment_id
+    AND p.user_id IS DISTINCT FROM pc.user_id
+    AND NOT EXISTS (
+      SELECT 1
+      FROM notification AS n
+      WHERE n.comment_id = pc.id
+        AND n.user_id = p.user_id
+    );
+END;
+$BODY$;
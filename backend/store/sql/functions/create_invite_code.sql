@@ -0,0 +1,28 @@
+CREATE FUNCTION create_invite_code(
+  IN p_invite_code new_invite_code,
+  IN p_user_id integer
+)
+RETURNS invite_code
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_invite_code invite_code;
+BEGIN
+  -- Insert invite code
+  INSERT INTO invite_code (
+    user_id,
+    code,
+    max_uses,
+    expires_at
+  )
+  SELECT
+    p_user_id, -- user_id
+    p_invite_code.code, -- code
+    p_invite_code.max_uses, -- max_uses
+    p_invite_code.expires_at -- expires_at
+  RETURNING * INTO v_invite_code;
+
+  RETURN v_invite_code;
+END;
+$BODY$;
@@ -0,0 +1,27 @@
+-- Returns NULL if the name is already taken, or the identity has already been linked to a user,
+-- which can happen if another login created a user at the same time.
+CREATE FUNCTION create_oidc_user(
+  IN p_user new_user,
+  IN p_issuer text,
+  IN p_subject text
+)
+RETURNS "user"
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_user "user";
+BEGIN
+  -- Users of the provider are not invited
+  v_user := create_user(p_user, false);
+
+  -- Link user to the provider identity
+  INSERT INTO user_oidc_identity (issuer, subject, user_id)
+  VALUES (p_issuer, p_subject, v_user.id);
+
+  RETURN v_user;
+EXCEPTION
+  WHEN unique_violation THEN
+    RETURN NULL;
+END;
+$BODY$;
@@ -0,0 +1,19 @@
+CREATE FUNCTION create_pool(
+  IN p_pool new_pool
+)
+RETURNS integer
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_pool_id integer;
+BEGIN
+  INSERT INTO pool (user_id, name, description, created_at)
+  VALUES (p_pool.user_id, p_pool.name, p_pool.description, COALESCE(p_pool.created_at, CURRENT_TIMESTAMP))
+  RETURNING id INTO v_pool_id;
+
+  PERFORM set_pool_posts(v_pool_id, p_pool.post_ids);
+
+  RETURN v_pool_id;
+END;
+$BODY$;
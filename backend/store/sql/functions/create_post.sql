@@ -1,3 +1,4 @@
+-- Returns NULL if the parent post is not valid.
 CREATE FUNCTION create_post(
   IN p_post new_post,
   IN p_tags text[]
@@ -9,6 +10,10 @@ AS $BODY$
 DECLARE
   v_post_id integer;
 BEGIN
+  IF NOT is_valid_post_parent(NULL, p_post.parent_id) THEN
+    RETURN NULL;
+  END IF;
+
   -- Insert post
   INSERT INTO post (
     user_id,
@@ -21,7 +26,8 @@ BEGIN
     height,
     hash,
     ext,
-    tn_ext
+    tn_ext,
+    parent_id
   )
   SELECT
     p_post.user_id, -- user_id
@@ -34,9 +40,17 @@ BEGIN
     p_post.height, -- height
     p_post.hash, -- hash
     p_post.ext, -- ext
-    p_post.tn_ext -- tn_ext
+    p_post.tn_ext, -- tn_ext
+    p_post.parent_id -- parent_id
   RETURNING id INTO v_post_id;
 
+  -- If no rating is specified, the column default is used
+  IF p_post.rating IS NOT NULL THEN
+    UPDATE post
+    SET rating = p_post.rating
+    WHERE id = v_post_id;
+  END IF;
+
   -- Create post_tag_id_cache
   INSERT INTO post_tag_id_cache (post_id) VALUES (v_post_id);
 
@@ -1,6 +1,10 @@
+-- Replies must be to a visible comment on the same post.
+-- Mentioned users that exist are recorded, except for the author.
+-- Users are notified of the comment, unless it is held.
 CREATE FUNCTION create_post_comment(
   IN p_comment new_post_comment,
-  IN p_user_id integer
+  IN p_user_id integer,
+  IN p_mentions text[]
 )
 RETURNS post_comment
 LANGUAGE plpgsql
@@ -8,21 +12,51 @@ LANGUAGE plpgsql
 AS $BODY$
 DECLARE
   v_comment post_comment;
+  v_depth integer;
 BEGIN
-  -- Insert post
+  IF p_comment.reply_to_id IS NOT NULL THEN
+    SELECT depth + 1 INTO v_depth
+    FROM post_comment
+    WHERE id = p_comment.reply_to_id
+      AND post_id = p_comment.post_id
+      AND NOT is_held;
+
+    IF NOT FOUND THEN
+      RAISE EXCEPTION 'Invalid reply comment';
+    END IF;
+  END IF;
+
+  -- Insert comment
   INSERT INTO post_comment (
     user_id,
     user_name,
     post_id,
-    comment
+    comment,
+    reply_to_id,
+    depth,
+    is_held
   )
   SELECT
     p_user_id, -- user_id
     (SELECT name FROM "user" WHERE id = p_user_id), -- user_name
     p_comment.post_id, -- post_id
-    p_comment.comment -- comment
+    p_comment.comment, -- comment
+    p_comment.reply_to_id, -- reply_to_id
+    COALESCE(v_depth, 0), -- depth
+    COALESCE(p_comment.is_held, false) -- is_held
   RETURNING * INTO v_comment;
 
+  -- Record mentioned users
+  INSERT INTO comment_mention (comment_id, user_id)
+  SELECT v_comment.id, u.id
+  FROM "user" AS u
+  WHERE u.name = ANY(p_mentions)
+    AND u.id IS DISTINCT FROM p_user_id;
+
+  IF NOT v_comment.is_held THEN
+    PERFORM create_comment_notifications(v_comment.id);
+  END IF;
+
   RETURN v_comment;
 END;
 $BODY$;
@@ -0,0 +1,23 @@
+-- Reporting a post that the user already has an open report on does nothing.
+-- Returns false if the post does not exist.
+CREATE FUNCTION create_post_report(
+  IN p_report new_post_report,
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  IF NOT EXISTS (SELECT 1 FROM post WHERE id = p_report.post_id AND NOT is_deleted) THEN
+    RETURN false;
+  END IF;
+
+  INSERT INTO post_report (post_id, user_id, reason, comment)
+  VALUES (p_report.post_id, p_user_id, p_report.reason, p_report.comment)
+  ON CONFLICT (post_id, user_id) WHERE status = 'open'
+  DO NOTHING;
+
+  RETURN true;
+END;
+$BODY$;
@@ -1,6 +1,7 @@
 CREATE FUNCTION create_refresh_token(
   IN p_user_id integer,
-  IN p_ip inet
+  IN p_ip inet,
+  IN p_user_agent text
 )
 RETURNS create_refresh_token_result
 LANGUAGE plpgsql
@@ -13,8 +14,8 @@ BEGIN
   v_session := nextval('refresh_token_session_seq');
 
   -- Generate new refresh token with new session
-  INSERT INTO refresh_token (session, user_id, created_ip)
-  VALUES (v_session, p_user_id, p_ip)
+  INSERT INTO refresh_token (session, user_id, created_ip, user_agent)
+  VALUES (v_session, p_user_id, p_ip, p_user_agent)
   RETURNING token INTO v_new_token;
 
   -- Return new token
@@ -0,0 +1,24 @@
+-- Posts that already exist when the search is saved are considered seen.
+CREATE FUNCTION create_saved_search(
+  IN p_saved_search new_saved_search
+)
+RETURNS integer
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_saved_search_id integer;
+BEGIN
+  INSERT INTO saved_search (user_id, name, include_tags, exclude_tags, last_seen_post_id)
+  VALUES (
+    p_saved_search.user_id,
+    p_saved_search.name,
+    p_saved_search.include_tags,
+    p_saved_search.exclude_tags,
+    (SELECT COALESCE(max(id), 0) FROM post)
+  )
+  RETURNING id INTO v_saved_search_id;
+
+  RETURN v_saved_search_id;
+END;
+$BODY$;
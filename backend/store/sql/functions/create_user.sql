@@ -1,5 +1,10 @@
+-- Returns NULL if the invite code is not valid (anymore),
+-- which can happen if it was used up by another registration,
+-- or if an invite code is required but none was specified.
+-- The first user needs no invite code, as there is no one to invite them.
 CREATE FUNCTION create_user(
-  IN p_user new_user
+  IN p_user new_user,
+  IN p_require_invite_code boolean
 )
 RETURNS "user"
 LANGUAGE plpgsql
@@ -7,26 +12,53 @@ LANGUAGE plpgsql
 AS $BODY$
 DECLARE
   v_user "user";
+  v_is_first_user boolean;
   v_rank smallint;
+  v_invited_by_user_id integer;
 BEGIN
+  -- Register one user at a time, so that only one user can be the first
+  PERFORM pg_advisory_xact_lock(hashtext('create_user'));
+
+  v_is_first_user := NOT EXISTS(SELECT * FROM "user");
+
   -- If there are no existing users,
   -- make the new one a giga-admin.
-  IF NOT EXISTS(SELECT * FROM "user") THEN
+  IF v_is_first_user THEN
     v_rank := 9001;
   ELSE
     v_rank := 0;
   END IF;
 
+  IF p_require_invite_code AND p_user.invite_code IS NULL AND NOT v_is_first_user THEN
+    RETURN NULL;
+  END IF;
+
+  -- If an invite code was specified, use it
+  IF p_user.invite_code IS NOT NULL THEN
+    UPDATE invite_code
+    SET uses = uses + 1
+    WHERE code = p_user.invite_code
+      AND uses < max_uses
+      AND (expires_at IS NULL OR expires_at > CURRENT_TIMESTAMP)
+    RETURNING user_id INTO v_invited_by_user_id;
+
+    IF NOT FOUND THEN
+      RETURN NULL;
+    END IF;
+  END IF;
+
   -- Insert user
   INSERT INTO "user" (
     name,
     password_hash,
-    rank
+    rank,
+    invited_by_user_id
   )
   SELECT
     p_user.name, -- name
     p_user.password_hash, -- password_hash
-    v_rank
+    v_rank,
+    v_invited_by_user_id
   RETURNING * INTO v_user;
 
   RETURN v_user;
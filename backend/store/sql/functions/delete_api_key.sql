@@ -0,0 +1,20 @@
+CREATE FUNCTION delete_api_key(
+  IN p_api_key_id integer,
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_success boolean;
+BEGIN
+  -- Only allow users to delete their own API keys
+  DELETE FROM api_key
+  WHERE id = p_api_key_id
+    AND user_id = p_user_id
+  RETURNING true INTO v_success;
+
+  RETURN COALESCE(v_success, false);
+END;
+$BODY$;
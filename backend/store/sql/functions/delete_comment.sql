@@ -0,0 +1,33 @@
+-- Users can only delete their own comments, and only if they were created after the specified time.
+-- Admins can delete any comment, which is recorded in the audit log unless it is their own,
+-- including anonymous comments.
+CREATE FUNCTION delete_comment(
+  IN p_comment_id integer,
+  IN p_user_id integer,
+  IN p_created_after timestamp with time zone
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_is_admin boolean;
+  v_success boolean;
+  v_comment_user_id integer;
+BEGIN
+  v_is_admin := COALESCE((SELECT rank FROM "user" WHERE id = p_user_id) > 0, false);
+
+  UPDATE comment
+  SET is_deleted = true
+  WHERE id = p_comment_id
+    AND NOT is_deleted
+    AND (v_is_admin OR (user_id = p_user_id AND created_at > p_created_after))
+  RETURNING true, user_id INTO v_success, v_comment_user_id;
+
+  IF v_success AND v_comment_user_id IS DISTINCT FROM p_user_id THEN
+    PERFORM create_audit_log_entry(ROW('delete_comment', 'comment', p_comment_id, NULL)::new_audit_log_entry, p_user_id);
+  END IF;
+
+  RETURN COALESCE(v_success, false);
+END;
+$BODY$;
@@ -0,0 +1,21 @@
+CREATE FUNCTION delete_invite_code(
+  IN p_invite_code_id integer,
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_success boolean;
+BEGIN
+  -- Users can delete their own invite codes,
+  -- and admins can delete any invite code.
+  DELETE FROM invite_code
+  WHERE id = p_invite_code_id
+    AND (user_id = p_user_id OR (SELECT rank FROM "user" WHERE id = p_user_id) > 0)
+  RETURNING true INTO v_success;
+
+  RETURN COALESCE(v_success, false);
+END;
+$BODY$;
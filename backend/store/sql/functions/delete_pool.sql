@@ -0,0 +1,20 @@
+-- Returns false if the pool does not exist.
+CREATE FUNCTION delete_pool(
+  IN p_pool_id integer,
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  IF NOT can_user_edit_pool(p_pool_id, p_user_id) THEN
+    RETURN false;
+  END IF;
+
+  DELETE FROM pool
+  WHERE id = p_pool_id;
+
+  RETURN FOUND;
+END;
+$BODY$;
@@ -1,24 +1,32 @@
+-- Users can delete their own posts, and admins can delete any post.
+-- Deleting the post of another user is recorded in the audit log with the specified details.
 CREATE FUNCTION delete_post(
   IN p_post_id integer,
-  IN p_user_id integer
+  IN p_user_id integer,
+  IN p_audit_details text
 )
 RETURNS boolean
 LANGUAGE plpgsql
 
 AS $BODY$
 DECLARE
+  v_is_admin boolean;
   v_success boolean;
+  v_post_user_id integer;
   v_tag_ids integer[];
 BEGIN
+  v_is_admin := COALESCE((SELECT rank FROM "user" WHERE id = p_user_id) > 0, false);
+
   -- Update post
   UPDATE post
   SET is_deleted = true
   WHERE id = p_post_id
-    AND user_id = p_user_id
-  RETURNING true INTO v_success;
+    AND NOT is_deleted
+    AND (v_is_admin OR user_id = p_user_id)
+  RETURNING true, user_id INTO v_success, v_post_user_id;
 
-  IF NOT v_success THEN
-    RETURN v_success;
+  IF NOT COALESCE(v_success, false) THEN
+    RETURN false;
   END IF;
 
   -- Get post tag IDs for later use
@@ -46,6 +54,10 @@ BEGIN
   WHERE v_tag_ids @> tag_ids
     AND NOT v_tag_ids && exclude_tag_ids;
 
+  IF v_post_user_id <> p_user_id THEN
+    PERFORM create_audit_log_entry(ROW('delete_post', 'post', p_post_id, p_audit_details)::new_audit_log_entry, p_user_id);
+  END IF;
+
   RETURN v_success;
 END;
 $BODY$;
@@ -0,0 +1,19 @@
+CREATE FUNCTION delete_saved_search(
+  IN p_saved_search_id integer,
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_success boolean;
+BEGIN
+  DELETE FROM saved_search
+  WHERE id = p_saved_search_id
+    AND user_id = p_user_id
+  RETURNING true INTO v_success;
+
+  RETURN COALESCE(v_success, false);
+END;
+$BODY$;
@@ -0,0 +1,20 @@
+CREATE FUNCTION disable_user_totp(
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_success boolean;
+BEGIN
+  DELETE FROM user_totp
+  WHERE user_id = p_user_id
+  RETURNING true INTO v_success;
+
+  DELETE FROM user_recovery_code
+  WHERE user_id = p_user_id;
+
+  RETURN COALESCE(v_success, false);
+END;
+$BODY$;
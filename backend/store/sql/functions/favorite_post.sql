@@ -0,0 +1,30 @@
+CREATE FUNCTION favorite_post(
+  IN p_post_id integer,
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_favorited boolean;
+BEGIN
+  IF NOT EXISTS (SELECT 1 FROM post WHERE id = p_post_id AND NOT is_deleted) THEN
+    RETURN false;
+  END IF;
+
+  INSERT INTO post_favorite (user_id, post_id)
+  VALUES (p_user_id, p_post_id)
+  ON CONFLICT DO NOTHING
+  RETURNING true INTO v_favorited;
+
+  -- Only count the favorite if the post was not already favorited
+  IF v_favorited THEN
+    UPDATE post
+    SET fav_count = fav_count + 1
+    WHERE id = p_post_id;
+  END IF;
+
+  RETURN true;
+END;
+$BODY$;
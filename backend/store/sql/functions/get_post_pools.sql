@@ -0,0 +1,44 @@
+-- Get the pools a post is in, along with the previous and next posts in each pool.
+CREATE FUNCTION get_post_pools(
+  IN p_post_id integer
+)
+RETURNS TABLE (
+  id integer,
+  name text,
+  previous_post_id integer,
+  next_post_id integer
+)
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  SELECT
+    pl.id,
+    pl.name,
+    (
+      SELECT ppp.post_id
+      FROM pool_post AS ppp
+      JOIN post AS p ON p.id = ppp.post_id
+      WHERE ppp.pool_id = pl.id
+        AND ppp.position < pp.position
+        AND NOT p.is_deleted
+      ORDER BY ppp.position DESC
+      LIMIT 1
+    ) AS previous_post_id,
+    (
+      SELECT npp.post_id
+      FROM pool_post AS npp
+      JOIN post AS p ON p.id = npp.post_id
+      WHERE npp.pool_id = pl.id
+        AND npp.position > pp.position
+        AND NOT p.is_deleted
+      ORDER BY npp.position ASC
+      LIMIT 1
+    ) AS next_post_id
+  FROM pool_post AS pp
+  JOIN pool AS pl ON pl.id = pp.pool_id
+  WHERE pp.post_id = p_post_id
+  ORDER BY pl.id ASC;
+END;
+$BODY$ STABLE;
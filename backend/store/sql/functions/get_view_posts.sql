@@ -1,6 +1,7 @@
 CREATE FUNCTION get_view_posts(
   IN p_include_tags text[],
   IN p_exclude_tags text[],
+  IN p_options search_options,
   IN p_start_id integer,
   IN p_limit integer
 )
@@ -13,11 +14,25 @@ DECLARE
   v_exclude_tag_ids integer[];
   v_valid boolean;
 BEGIN
-  SELECT * INTO v_tag_ids, v_exclude_tag_ids, v_valid FROM resolve_search_tags(p_include_tags, p_exclude_tags);
+  SELECT * INTO v_tag_ids, v_exclude_tag_ids, v_valid FROM resolve_search_tags(p_include_tags, p_exclude_tags, p_options.ratings);
   IF NOT v_valid THEN
     RETURN;
   END IF;
 
+  IF NOT is_cached_search(p_options) THEN
+    RETURN QUERY
+    SELECT p.*
+    FROM search_posts(v_tag_ids, v_exclude_tag_ids, p_options) AS s
+    JOIN view_post AS p ON p.id = s.post_id
+    WHERE
+      -- Only scan forward from the origin
+      (s.sort_key, s.post_id) <= (search_sort_key(p_start_id, p_options.sort), p_start_id)
+    ORDER BY s.sort_key DESC, s.post_id DESC
+    LIMIT p_limit;
+
+    RETURN;
+  END IF;
+
   RETURN QUERY
   SELECT p.*
   FROM post_tag_id_cache AS ptic
@@ -6,8 +6,15 @@ LANGUAGE plpgsql
 
 AS $BODY$
 BEGIN
-    UPDATE refresh_token
-    SET used = true
-    WHERE session = p_session;
+  UPDATE refresh_token
+  SET used = true
+  WHERE session = p_session;
+
+  -- Record revocation, so that access tokens
+  -- issued for the session can be rejected
+  INSERT INTO revoked_session (session)
+  VALUES (p_session)
+  ON CONFLICT (session)
+  DO NOTHING;
 END;
 $BODY$;
@@ -0,0 +1,19 @@
+CREATE FUNCTION invalidate_user_session(
+  IN p_user_id integer,
+  IN p_session bigint
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  -- Only allow users to invalidate their own sessions
+  IF NOT EXISTS(SELECT * FROM view_session WHERE session = p_session AND user_id = p_user_id) THEN
+    RETURN false;
+  END IF;
+
+  PERFORM invalidate_session(p_session);
+
+  RETURN true;
+END;
+$BODY$;
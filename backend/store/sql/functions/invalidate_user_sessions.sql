@@ -0,0 +1,17 @@
+CREATE FUNCTION invalidate_user_sessions(
+  IN p_user_id integer
+)
+RETURNS bigint[]
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_sessions bigint[];
+BEGIN
+  v_sessions := array(SELECT session FROM view_session WHERE user_id = p_user_id);
+
+  PERFORM invalidate_session(s) FROM unnest(v_sessions) AS s;
+
+  RETURN v_sessions;
+END;
+$BODY$;
@@ -0,0 +1,22 @@
+-- Searches without metatags use the search cache.
+-- Ratings are searched by their tag IDs, so they do not prevent caching.
+CREATE FUNCTION is_cached_search(
+  IN p_options search_options
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN p_options.fav_user_name IS NULL
+    AND p_options.min_score IS NULL
+    AND p_options.max_score IS NULL
+    AND p_options.pool_id IS NULL
+    AND p_options.parent_id IS NULL
+    AND p_options.has_parent IS NULL
+    AND p_options.child_id IS NULL
+    AND p_options.has_children IS NULL
+    AND p_options.after_id IS NULL
+    AND COALESCE(p_options.sort, 'id') = 'id';
+END;
+$BODY$ IMMUTABLE;
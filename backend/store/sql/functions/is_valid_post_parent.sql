@@ -0,0 +1,42 @@
+-- Check whether a post can be made the parent of another post.
+-- The parent must exist, and must not be the post itself or one of its descendants.
+-- The ancestors of the parent are locked, so that concurrent parent changes
+-- can not create a cycle between them.
+CREATE FUNCTION is_valid_post_parent(
+  IN p_post_id integer,
+  IN p_parent_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_ancestor_id integer := p_parent_id;
+  v_visited_ids integer[] := '{}';
+BEGIN
+  -- Having no parent is always valid
+  IF p_parent_id IS NULL THEN
+    RETURN true;
+  END IF;
+
+  IF NOT EXISTS (SELECT 1 FROM post WHERE id = p_parent_id AND NOT is_deleted FOR UPDATE) THEN
+    RETURN false;
+  END IF;
+
+  -- Walk up from the new parent, to make sure the post is not one of its ancestors
+  WHILE v_ancestor_id IS NOT NULL AND NOT v_ancestor_id = ANY(v_visited_ids) LOOP
+    IF v_ancestor_id = p_post_id THEN
+      RETURN false;
+    END IF;
+
+    v_visited_ids := v_visited_ids || v_ancestor_id;
+
+    SELECT parent_id INTO v_ancestor_id
+    FROM post
+    WHERE id = v_ancestor_id
+    FOR UPDATE;
+  END LOOP;
+
+  RETURN true;
+END;
+$BODY$;
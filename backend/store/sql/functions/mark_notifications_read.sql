@@ -0,0 +1,17 @@
+-- Marks all the notifications of the user as read if no IDs are specified.
+CREATE FUNCTION mark_notifications_read(
+  IN p_user_id integer,
+  IN p_ids integer[]
+)
+RETURNS VOID
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  UPDATE notification
+  SET is_read = true
+  WHERE user_id = p_user_id
+    AND NOT is_read
+    AND (p_ids IS NULL OR id = ANY(p_ids));
+END;
+$BODY$;
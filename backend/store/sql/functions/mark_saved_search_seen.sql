@@ -0,0 +1,23 @@
+-- Mark posts up to the specified post as seen.
+-- The last seen post never moves backwards, in case of requests arriving out of order.
+CREATE FUNCTION mark_saved_search_seen(
+  IN p_saved_search_id integer,
+  IN p_last_seen_post_id integer,
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_success boolean;
+BEGIN
+  UPDATE saved_search
+  SET last_seen_post_id = GREATEST(last_seen_post_id, p_last_seen_post_id)
+  WHERE id = p_saved_search_id
+    AND user_id = p_user_id
+  RETURNING true INTO v_success;
+
+  RETURN COALESCE(v_success, false);
+END;
+$BODY$;
@@ -0,0 +1,21 @@
+-- Announce comments on the blazebooru_event channel once they are visible,
+-- either when created or when approved.
+CREATE FUNCTION notify_post_comment_event()
+RETURNS trigger
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  IF NEW.is_held OR (TG_OP = 'UPDATE' AND NOT OLD.is_held) THEN
+    RETURN NULL;
+  END IF;
+
+  PERFORM pg_notify('blazebooru_event', json_build_object(
+    'kind', 'comment',
+    'post_id', NEW.post_id,
+    'comment_id', NEW.id
+  )::text);
+
+  RETURN NULL;
+END;
+$BODY$;
@@ -0,0 +1,15 @@
+-- Announce new posts on the blazebooru_event channel.
+CREATE FUNCTION notify_post_event()
+RETURNS trigger
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  PERFORM pg_notify('blazebooru_event', json_build_object(
+    'kind', 'post',
+    'post_id', NEW.id
+  )::text);
+
+  RETURN NULL;
+END;
+$BODY$;
@@ -0,0 +1,16 @@
+-- Announce revoked sessions on the blazebooru_event channel,
+-- so that every server instance rejects their access tokens.
+CREATE FUNCTION notify_revoked_session_event()
+RETURNS trigger
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  PERFORM pg_notify('blazebooru_event', json_build_object(
+    'kind', 'session_revoked',
+    'session', NEW.session
+  )::text);
+
+  RETURN NULL;
+END;
+$BODY$;
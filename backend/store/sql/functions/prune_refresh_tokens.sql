@@ -0,0 +1,24 @@
+CREATE FUNCTION prune_refresh_tokens()
+RETURNS integer
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_count integer;
+BEGIN
+  -- Delete expired tokens, as well as all tokens belonging
+  -- to sessions that no longer have a usable token.
+  -- Used tokens in active sessions are kept, so that
+  -- reuse of a stolen token can still be detected.
+  DELETE FROM refresh_token AS rt
+  WHERE rt.expires_at < CURRENT_TIMESTAMP
+     OR NOT EXISTS(SELECT * FROM refresh_token AS urt
+                   WHERE urt.session = rt.session
+                     AND NOT urt.used
+                     AND urt.expires_at > CURRENT_TIMESTAMP);
+
+  GET DIAGNOSTICS v_count = ROW_COUNT;
+
+  RETURN v_count;
+END;
+$BODY$;
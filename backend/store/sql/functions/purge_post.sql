@@ -0,0 +1,43 @@
+-- Permanently remove a deleted post.
+-- Only admins can purge posts.
+-- Returns the files of the post, and whether another post still uses them.
+CREATE FUNCTION purge_post(
+  IN p_post_id integer,
+  IN p_user_id integer
+)
+RETURNS SETOF purge_post_result
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_post post;
+BEGIN
+  IF NOT COALESCE((SELECT rank FROM "user" WHERE id = p_user_id) > 0, false) THEN
+    RETURN;
+  END IF;
+
+  DELETE FROM post
+  WHERE id = p_post_id
+    AND is_deleted
+  RETURNING * INTO v_post;
+
+  IF v_post.id IS NULL THEN
+    RETURN;
+  END IF;
+
+  -- Tag changes are not linked to the post, so they are removed explicitly
+  DELETE FROM post_tag_change
+  WHERE post_id = p_post_id;
+
+  -- The hash identifies the files of the post, which may still exist elsewhere
+  PERFORM create_audit_log_entry(ROW('purge_post', 'post', p_post_id, v_post.hash)::new_audit_log_entry, p_user_id);
+
+  -- Deleted posts still count as using the files, as they can be undeleted
+  RETURN QUERY
+  SELECT
+    v_post.hash,
+    v_post.ext,
+    v_post.tn_ext,
+    EXISTS (SELECT 1 FROM post WHERE hash = v_post.hash);
+END;
+$BODY$;
@@ -0,0 +1,17 @@
+-- Ratings are cached as negative IDs along with the tag IDs of posts,
+-- so that searches by rating can use the search cache.
+CREATE FUNCTION rating_tag_id(
+  IN p_rating text
+)
+RETURNS integer
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN (CASE p_rating
+    WHEN 'safe' THEN -1
+    WHEN 'questionable' THEN -2
+    WHEN 'explicit' THEN -3
+    END);
+END;
+$BODY$ IMMUTABLE;
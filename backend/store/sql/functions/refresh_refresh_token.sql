@@ -1,6 +1,10 @@
+-- Reusing a token invalidates its session, which is returned without a new token
+-- so that its access tokens can be revoked as well.
+-- Banned users cannot refresh their tokens.
 CREATE FUNCTION refresh_refresh_token(
   IN p_token uuid,
-  IN p_ip inet
+  IN p_ip inet,
+  IN p_user_agent text
 )
 RETURNS refresh_refresh_token_result
 LANGUAGE plpgsql
@@ -22,7 +26,7 @@ BEGIN
   -- Check if already used
   IF v_refresh_token.used THEN
     PERFORM invalidate_session(v_refresh_token.session);
-    RETURN NULL;
+    RETURN ROW(NULL::uuid, v_refresh_token.session, v_refresh_token.user_id);
   END IF;
 
   -- Check if expired
@@ -30,14 +34,19 @@ BEGIN
     RETURN NULL;
   END IF;
 
+  -- Check if banned
+  IF (SELECT is_banned FROM "user" WHERE id = v_refresh_token.user_id) THEN
+    RETURN NULL;
+  END IF;
+
   -- Mark token as used
   UPDATE refresh_token
   SET used = true, used_ip = p_ip
   WHERE id = v_refresh_token.id;
 
   -- Generate new refresh token
-  INSERT INTO refresh_token (session, user_id, created_ip)
-  VALUES (v_refresh_token.session, v_refresh_token.user_id, p_ip)
+  INSERT INTO refresh_token (session, user_id, created_ip, user_agent)
+  VALUES (v_refresh_token.session, v_refresh_token.user_id, p_ip, p_user_agent)
   RETURNING token, session, user_id INTO v_result;
 
   -- Return result
@@ -0,0 +1,72 @@
+-- Only admins can resolve reports.
+-- Dismissing only resolves the report itself, while deleting the post
+-- or banning its uploader resolves all open reports on the post.
+-- Admins and users who are already banned are not banned again,
+-- in which case the reports are recorded as only having deleted the post.
+-- Banned users are logged out everywhere in the same transaction.
+-- The actions actually taken are recorded in the audit log.
+-- Returns the status the reports were resolved with and the sessions that were invalidated,
+-- or NULL if the report could not be resolved.
+CREATE FUNCTION resolve_post_report(
+  IN p_report_id integer,
+  IN p_status text,
+  IN p_user_id integer
+)
+RETURNS resolve_post_report_result
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_post_id integer;
+  v_status text := p_status;
+  v_post_user_id integer;
+  v_details text := 'report ' || p_report_id;
+  v_sessions bigint[] := '{}';
+BEGIN
+  IF NOT COALESCE((SELECT rank FROM "user" WHERE id = p_user_id) > 0, false) THEN
+    RETURN NULL;
+  END IF;
+
+  SELECT post_id INTO v_post_id
+  FROM post_report
+  WHERE id = p_report_id
+    AND status = 'open';
+
+  IF NOT FOUND THEN
+    RETURN NULL;
+  END IF;
+
+  IF p_status = 'dismissed' THEN
+    PERFORM create_audit_log_entry(ROW('dismiss_report', 'report', p_report_id, NULL)::new_audit_log_entry, p_user_id);
+  END IF;
+
+  IF p_status IN ('deleted', 'banned') THEN
+    PERFORM delete_post(v_post_id, p_user_id, v_details);
+  END IF;
+
+  IF p_status = 'banned' THEN
+    UPDATE "user"
+    SET is_banned = true
+    WHERE id = (SELECT user_id FROM post WHERE id = v_post_id)
+      AND rank = 0
+      AND NOT is_banned
+    RETURNING id INTO v_post_user_id;
+
+    IF FOUND THEN
+      PERFORM create_audit_log_entry(ROW('ban_user', 'user', v_post_user_id, v_details)::new_audit_log_entry, p_user_id);
+      v_sessions := invalidate_user_sessions(v_post_user_id);
+    ELSE
+      v_status := 'deleted';
+    END IF;
+  END IF;
+
+  UPDATE post_report
+  SET status = v_status,
+      resolved_at = CURRENT_TIMESTAMP,
+      resolved_by_user_id = p_user_id
+  WHERE status = 'open'
+    AND (id = p_report_id OR (p_status <> 'dismissed' AND post_id = v_post_id));
+
+  RETURN ROW(v_status, v_sessions);
+END;
+$BODY$;
@@ -1,6 +1,8 @@
+-- Posts without one of the ratings are excluded by their rating tag IDs, if ratings are specified.
 CREATE FUNCTION resolve_search_tags(
   IN p_include_tags text[],
   IN p_exclude_tags text[],
+  IN p_ratings text[],
   OUT p_include_tag_ids integer[],
   OUT p_exclude_tag_ids integer[],
   OUT p_valid boolean
@@ -16,5 +18,13 @@ BEGIN
 
   p_include_tag_ids := compute_search_tag_ids(p_include_tag_ids);
   p_exclude_tag_ids := compute_search_tag_ids(p_exclude_tag_ids);
+
+  IF p_ratings IS NOT NULL THEN
+    p_exclude_tag_ids := p_exclude_tag_ids | array(
+      SELECT rating_tag_id(r)
+      FROM unnest(ARRAY['safe', 'questionable', 'explicit']) AS r
+      WHERE r <> ALL(p_ratings)
+    );
+  END IF;
 END;
 $BODY$ STABLE;
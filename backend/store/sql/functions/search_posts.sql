@@ -0,0 +1,65 @@
+-- Returns the posts matching a search, along with the key they are sorted by.
+-- Used for searches with metatags, which are not cached.
+CREATE FUNCTION search_posts(
+  IN p_tag_ids integer[],
+  IN p_exclude_tag_ids integer[],
+  IN p_options search_options
+)
+RETURNS TABLE (
+  post_id integer,
+  sort_key integer
+)
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  SELECT
+    ptic.post_id,
+    (CASE p_options.sort
+      WHEN 'fav_count' THEN p.fav_count
+      WHEN 'score' THEN p.score
+      ELSE 0
+      END) AS sort_key
+  FROM post_tag_id_cache AS ptic
+  JOIN post AS p ON p.id = ptic.post_id
+  WHERE
+    -- Only include posts after the ID, if specified
+    (p_options.after_id IS NULL OR ptic.post_id > p_options.after_id)
+    -- Posts with fewer tags than the required tags cannot qualify
+    AND icount(ptic.tag_ids) >= icount(p_tag_ids)
+    -- Post must have all the included tags
+    AND ptic.tag_ids @> p_tag_ids
+    -- Post must not have any of the excluded tags
+    AND NOT ptic.tag_ids && p_exclude_tag_ids
+    -- Post must be favorited by the user, if specified
+    AND (p_options.fav_user_name IS NULL OR EXISTS (
+      SELECT 1
+      FROM post_favorite AS pf
+      JOIN "user" AS u ON u.id = pf.user_id
+      WHERE pf.post_id = ptic.post_id
+        AND u.name = p_options.fav_user_name
+    ))
+    -- Post score must be within the range, if specified
+    AND (p_options.min_score IS NULL OR p.score >= p_options.min_score)
+    AND (p_options.max_score IS NULL OR p.score <= p_options.max_score)
+    -- Post must be in the pool, if specified
+    AND (p_options.pool_id IS NULL OR EXISTS (
+      SELECT 1
+      FROM pool_post AS pp
+      WHERE pp.post_id = ptic.post_id
+        AND pp.pool_id = p_options.pool_id
+    ))
+    -- Post must be a child of the post, if specified
+    AND (p_options.parent_id IS NULL OR p.parent_id = p_options.parent_id)
+    AND (p_options.has_parent IS NULL OR (p.parent_id IS NOT NULL) = p_options.has_parent)
+    -- Post must be the parent of the post, if specified
+    AND (p_options.child_id IS NULL OR p.id = (SELECT c.parent_id FROM post AS c WHERE c.id = p_options.child_id))
+    AND (p_options.has_children IS NULL OR EXISTS (
+      SELECT 1
+      FROM post AS c
+      WHERE c.parent_id = p.id
+        AND NOT c.is_deleted
+    ) = p_options.has_children);
+END;
+$BODY$ STABLE;
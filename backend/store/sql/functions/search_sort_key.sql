@@ -0,0 +1,22 @@
+-- Get the sort key of the post a search page starts at.
+-- If the post does not exist, the search starts from the beginning.
+CREATE FUNCTION search_sort_key(
+  IN p_post_id integer,
+  IN p_sort text
+)
+RETURNS integer
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  IF p_sort = 'fav_count' THEN
+    RETURN COALESCE((SELECT fav_count FROM post WHERE id = p_post_id), 2147483647);
+  END IF;
+
+  IF p_sort = 'score' THEN
+    RETURN COALESCE((SELECT score FROM post WHERE id = p_post_id), 2147483647);
+  END IF;
+
+  RETURN 0;
+END;
+$BODY$ STABLE;
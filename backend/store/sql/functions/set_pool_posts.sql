@@ -0,0 +1,24 @@
+-- Replace the posts in a pool, keeping the order they are specified in.
+-- Duplicate and non-existing posts are ignored.
+CREATE FUNCTION set_pool_posts(
+  IN p_pool_id integer,
+  IN p_post_ids integer[]
+)
+RETURNS VOID
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  DELETE FROM pool_post
+  WHERE pool_id = p_pool_id;
+
+  INSERT INTO pool_post (pool_id, post_id, position)
+  SELECT p_pool_id, x.post_id, ROW_NUMBER() OVER (ORDER BY x.position)
+  FROM (
+    SELECT DISTINCT ON (pid.post_id) pid.post_id, pid.position
+    FROM unnest(p_post_ids) WITH ORDINALITY AS pid(post_id, position)
+    ORDER BY pid.post_id, pid.position
+  ) AS x
+  WHERE EXISTS (SELECT 1 FROM post WHERE id = x.post_id AND NOT is_deleted);
+END;
+$BODY$;
@@ -0,0 +1,20 @@
+-- Replace the blacklisted tags of a user.
+-- Blank and duplicate tags are ignored.
+CREATE FUNCTION set_user_tag_blacklist(
+  IN p_user_id integer,
+  IN p_tags text[]
+)
+RETURNS VOID
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  DELETE FROM user_tag_blacklist
+  WHERE user_id = p_user_id;
+
+  INSERT INTO user_tag_blacklist (user_id, tag)
+  SELECT DISTINCT p_user_id, trim(t.tag)
+  FROM unnest(p_tags) AS t(tag)
+  WHERE trim(t.tag) <> '';
+END;
+$BODY$;
@@ -0,0 +1,29 @@
+-- Only admins can unban users.
+-- Returns false if the user does not exist or is not banned.
+CREATE FUNCTION unban_user(
+  IN p_user_id integer,
+  IN p_admin_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  IF NOT COALESCE((SELECT rank FROM "user" WHERE id = p_admin_user_id) > 0, false) THEN
+    RETURN false;
+  END IF;
+
+  UPDATE "user"
+  SET is_banned = false
+  WHERE id = p_user_id
+    AND is_banned;
+
+  IF NOT FOUND THEN
+    RETURN false;
+  END IF;
+
+  PERFORM create_audit_log_entry(ROW('unban_user', 'user', p_user_id, NULL)::new_audit_log_entry, p_admin_user_id);
+
+  RETURN true;
+END;
+$BODY$;
@@ -0,0 +1,51 @@
+-- Only admins can undelete posts.
+CREATE FUNCTION undelete_post(
+  IN p_post_id integer,
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_success boolean;
+  v_tag_ids integer[];
+BEGIN
+  IF NOT COALESCE((SELECT rank FROM "user" WHERE id = p_user_id) > 0, false) THEN
+    RETURN false;
+  END IF;
+
+  -- Update post
+  UPDATE post
+  SET is_deleted = false
+  WHERE id = p_post_id
+    AND is_deleted
+  RETURNING true INTO v_success;
+
+  IF NOT COALESCE(v_success, false) THEN
+    RETURN false;
+  END IF;
+
+  v_tag_ids := compute_post_tag_ids(array(SELECT tag_id FROM post_tag AS pt WHERE pt.post_id = p_post_id ORDER BY tag_id ASC))
+    | (SELECT rating_tag_id(rating) FROM post WHERE id = p_post_id);
+
+  -- Recreate post_tag_id_cache so that the post
+  -- will be scanned for tag matches again
+  INSERT INTO post_tag_id_cache (post_id, tag_ids) VALUES (p_post_id, v_tag_ids);
+
+  -- Update search cache to reflect restored post
+  UPDATE search_cache
+  SET post_count = post_count + 1,
+      first_post_id = (CASE WHEN p_post_id > first_post_id THEN p_post_id ELSE first_post_id END),
+      last_page_post_ids = (CASE WHEN p_post_id < (SELECT MAX(id) FROM unnest(last_page_post_ids) AS id)
+                            THEN last_page_post_ids | p_post_id
+                            ELSE last_page_post_ids
+                            END)
+  WHERE v_tag_ids @> tag_ids
+    AND NOT v_tag_ids && exclude_tag_ids;
+
+  PERFORM create_audit_log_entry(ROW('undelete_post', 'post', p_post_id, NULL)::new_audit_log_entry, p_user_id);
+
+  RETURN v_success;
+END;
+$BODY$;
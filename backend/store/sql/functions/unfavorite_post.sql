@@ -0,0 +1,29 @@
+CREATE FUNCTION unfavorite_post(
+  IN p_post_id integer,
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_unfavorited boolean;
+BEGIN
+  IF NOT EXISTS (SELECT 1 FROM post WHERE id = p_post_id AND NOT is_deleted) THEN
+    RETURN false;
+  END IF;
+
+  DELETE FROM post_favorite
+  WHERE user_id = p_user_id
+    AND post_id = p_post_id
+  RETURNING true INTO v_unfavorited;
+
+  IF v_unfavorited THEN
+    UPDATE post
+    SET fav_count = fav_count - 1
+    WHERE id = p_post_id;
+  END IF;
+
+  RETURN true;
+END;
+$BODY$;
@@ -0,0 +1,31 @@
+CREATE FUNCTION unvote_post(
+  IN p_post_id integer,
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_old_score integer;
+BEGIN
+  -- Lock post, so that concurrent votes are counted correctly
+  PERFORM 1 FROM post WHERE id = p_post_id AND NOT is_deleted FOR UPDATE;
+  IF NOT FOUND THEN
+    RETURN false;
+  END IF;
+
+  DELETE FROM post_vote
+  WHERE user_id = p_user_id
+    AND post_id = p_post_id
+  RETURNING score INTO v_old_score;
+
+  IF v_old_score IS NOT NULL THEN
+    UPDATE post
+    SET score = score - v_old_score
+    WHERE id = p_post_id;
+  END IF;
+
+  RETURN true;
+END;
+$BODY$;
@@ -0,0 +1,50 @@
+-- Users can only edit their own comments, and only if they were created after the specified time.
+-- If the edited comment matches the filter, it is held until approved again.
+-- The mentioned users are replaced, and newly mentioned users are notified unless the comment is held.
+CREATE FUNCTION update_comment(
+  IN p_comment_id integer,
+  IN p_comment text,
+  IN p_user_id integer,
+  IN p_created_after timestamp with time zone,
+  IN p_mentions text[],
+  IN p_is_held boolean
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_is_held boolean;
+BEGIN
+  UPDATE comment
+  SET comment = p_comment,
+      updated_at = CURRENT_TIMESTAMP,
+      is_held = is_held OR p_is_held
+  WHERE id = p_comment_id
+    AND user_id = p_user_id
+    AND created_at > p_created_after
+    AND NOT is_deleted
+  RETURNING is_held INTO v_is_held;
+
+  IF NOT FOUND THEN
+    RETURN false;
+  END IF;
+
+  -- Replace mentioned users
+  DELETE FROM comment_mention
+  WHERE comment_id = p_comment_id;
+
+  INSERT INTO comment_mention (comment_id, user_id)
+  SELECT p_comment_id, u.id
+  FROM "user" AS u
+  WHERE u.name = ANY(p_mentions)
+    AND u.id IS DISTINCT FROM p_user_id;
+
+  -- Users that have already been notified of the comment are not notified again
+  IF NOT v_is_held THEN
+    PERFORM create_comment_notifications(p_comment_id);
+  END IF;
+
+  RETURN true;
+END;
+$BODY$;
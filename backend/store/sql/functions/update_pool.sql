@@ -0,0 +1,30 @@
+-- Returns false if the pool does not exist,
+-- as admins are allowed to edit any pool.
+CREATE FUNCTION update_pool(
+  IN p_pool_id integer,
+  IN p_update_pool update_pool,
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  IF NOT can_user_edit_pool(p_pool_id, p_user_id) THEN
+    RETURN false;
+  END IF;
+
+  UPDATE pool
+  SET name = p_update_pool.name,
+      description = p_update_pool.description
+  WHERE id = p_pool_id;
+
+  IF NOT FOUND THEN
+    RETURN false;
+  END IF;
+
+  PERFORM set_pool_posts(p_pool_id, p_update_pool.post_ids);
+
+  RETURN true;
+END;
+$BODY$;
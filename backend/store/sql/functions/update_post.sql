@@ -1,3 +1,5 @@
+-- Returns false if the post does not exist or belongs to another user,
+-- and NULL if the parent post is not valid.
 CREATE FUNCTION update_post(
   IN p_update_post update_post,
   IN p_user_id integer
@@ -6,24 +8,35 @@ RETURNS boolean
 LANGUAGE plpgsql
 
 AS $BODY$
-DECLARE
-  v_success boolean;
 BEGIN
+  -- Lock the post, so that its parent can not be changed concurrently
+  PERFORM 1
+  FROM post
+  WHERE id = p_update_post.id
+    AND user_id = p_user_id
+  FOR UPDATE;
+
+  IF NOT FOUND THEN
+    RETURN false;
+  END IF;
+
+  IF p_update_post.update_parent AND NOT is_valid_post_parent(p_update_post.id, p_update_post.parent_id) THEN
+    RETURN NULL;
+  END IF;
+
   -- Update post
   UPDATE post
   SET
     title = p_update_post.title,
     description = p_update_post.description,
-    source = p_update_post.source
-  WHERE id = p_update_post.id
-    AND user_id = p_user_id
-  RETURNING true INTO v_success;
+    source = p_update_post.source,
+    parent_id = (CASE WHEN p_update_post.update_parent THEN p_update_post.parent_id ELSE parent_id END),
+    rating = COALESCE(p_update_post.rating, rating)
+  WHERE id = p_update_post.id;
 
-  IF v_success THEN
-    -- Update post tags
-    PERFORM update_post_tags(p_update_post.id, p_update_post.add_tags, p_update_post.remove_tags, p_user_id, false);
-  END IF;
+  -- Update post tags
+  PERFORM update_post_tags(p_update_post.id, p_update_post.add_tags, p_update_post.remove_tags, p_user_id, false);
 
-  RETURN COALESCE(v_success, false);
+  RETURN true;
 END;
 $BODY$;
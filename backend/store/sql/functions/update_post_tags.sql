@@ -1,3 +1,5 @@
+-- Tag changes are announced on the blazebooru_event channel, except for the tags
+-- set when a post is created, which are covered by the post event.
 CREATE FUNCTION update_post_tags(
   IN p_post_id integer,
   IN p_add_tags text[],
@@ -14,6 +16,7 @@ DECLARE
   v_remove_tag_ids integer[];
   v_old_tag_ids integer[];
   v_new_tag_ids integer[];
+  v_changed boolean;
 BEGIN
   -- Create missing tags
   PERFORM create_missing_tags(p_add_tags);
@@ -26,6 +29,7 @@ BEGIN
 
   -- Compute new tags
   v_new_tag_ids := (v_old_tag_ids | v_add_tag_ids) - v_remove_tag_ids;
+  v_changed := v_new_tag_ids <> v_old_tag_ids;
 
   -- Add links for added tags to post
   INSERT INTO post_tag (post_id, tag_id)
@@ -47,8 +51,9 @@ BEGIN
                    ORDER BY tag ASC)
   WHERE id = p_post_id;
 
-  v_old_tag_ids := compute_post_tag_ids(v_old_tag_ids);
-  v_new_tag_ids := compute_post_tag_ids(v_new_tag_ids);
+  -- The cached tag IDs include the rating of the post, which may have changed as well
+  v_old_tag_ids := (SELECT tag_ids FROM post_tag_id_cache WHERE post_id = p_post_id);
+  v_new_tag_ids := compute_post_tag_ids(v_new_tag_ids) | (SELECT rating_tag_id(rating) FROM post WHERE id = p_post_id);
 
   -- Update post_tag_id_cache
   UPDATE post_tag_id_cache
@@ -98,5 +103,22 @@ BEGIN
     v_add_tag_ids,
     v_remove_tag_ids
   );
+
+  -- Notify the uploader of changes made by others
+  IF NOT p_new_post AND v_changed THEN
+    INSERT INTO notification (user_id, kind, actor_user_id, post_id)
+    SELECT p.user_id, 'tag_change', p_user_id, p.id
+    FROM post AS p
+    WHERE p.id = p_post_id
+      AND p.user_id <> p_user_id;
+  END IF;
+
+  -- Announce the change to connected clients
+  IF NOT p_new_post THEN
+    PERFORM pg_notify('blazebooru_event', json_build_object(
+      'kind', 'tag_change',
+      'post_id', p_post_id
+    )::text);
+  END IF;
 END;
 $BODY$;
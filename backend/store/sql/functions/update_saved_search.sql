@@ -0,0 +1,23 @@
+CREATE FUNCTION update_saved_search(
+  IN p_saved_search_id integer,
+  IN p_update_saved_search update_saved_search,
+  IN p_user_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_success boolean;
+BEGIN
+  UPDATE saved_search
+  SET name = p_update_saved_search.name,
+      include_tags = p_update_saved_search.include_tags,
+      exclude_tags = p_update_saved_search.exclude_tags
+  WHERE id = p_saved_search_id
+    AND user_id = p_user_id
+  RETURNING true INTO v_success;
+
+  RETURN COALESCE(v_success, false);
+END;
+$BODY$;
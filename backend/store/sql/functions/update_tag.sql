@@ -1,3 +1,4 @@
+-- The requested changes are recorded in the audit log.
 CREATE FUNCTION update_tag(
   IN p_tag_id integer,
   IN p_update_tag update_tag,
@@ -90,6 +91,7 @@ BEGIN
     -- Update pre-calculated post tag ID cache
     UPDATE post_tag_id_cache AS ptic
     SET tag_ids = compute_post_tag_ids(array(SELECT tag_id FROM post_tag AS pt WHERE pt.post_id = ptic.post_id))
+      | (SELECT rating_tag_id(p.rating) FROM post AS p WHERE p.id = ptic.post_id)
     WHERE tag_ids && v_affected_tag_ids;
 
     -- Delete cached searches affected by alias change
@@ -98,6 +100,8 @@ BEGIN
        OR exclude_tag_ids && v_affected_tag_ids;
   END IF;
 
+  PERFORM create_audit_log_entry(ROW('update_tag', 'tag', p_tag_id, row_to_json(p_update_tag)::text)::new_audit_log_entry, p_user_id);
+
   RETURN true;
 END;
 $BODY$;
@@ -0,0 +1,32 @@
+-- Get the API key with the specified hash, unless its user is banned.
+-- The last used time is only updated once a minute,
+-- so that every request made with the key does not write to the database.
+CREATE FUNCTION use_api_key(
+  IN p_key_hash text
+)
+RETURNS SETOF api_key
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_api_key api_key;
+BEGIN
+  SELECT * INTO v_api_key
+  FROM api_key
+  WHERE key_hash = p_key_hash
+    AND NOT (SELECT is_banned FROM "user" WHERE id = user_id);
+
+  IF v_api_key.id IS NULL THEN
+    RETURN;
+  END IF;
+
+  IF v_api_key.last_used_at IS NULL OR v_api_key.last_used_at < CURRENT_TIMESTAMP - interval '1 minute' THEN
+    UPDATE api_key
+    SET last_used_at = CURRENT_TIMESTAMP
+    WHERE id = v_api_key.id
+    RETURNING * INTO v_api_key;
+  END IF;
+
+  RETURN NEXT v_api_key;
+END;
+$BODY$;
@@ -0,0 +1,36 @@
+CREATE FUNCTION vote_post(
+  IN p_post_id integer,
+  IN p_user_id integer,
+  IN p_score integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_old_score integer;
+BEGIN
+  -- Lock post, so that concurrent votes are counted correctly
+  PERFORM 1 FROM post WHERE id = p_post_id AND NOT is_deleted FOR UPDATE;
+  IF NOT FOUND THEN
+    RETURN false;
+  END IF;
+
+  SELECT score INTO v_old_score
+  FROM post_vote
+  WHERE user_id = p_user_id
+    AND post_id = p_post_id;
+
+  INSERT INTO post_vote (user_id, post_id, score)
+  VALUES (p_user_id, p_post_id, p_score)
+  ON CONFLICT (user_id, post_id) DO UPDATE
+  SET score = EXCLUDED.score;
+
+  -- Replace any previous vote by the user
+  UPDATE post
+  SET score = score + p_score - COALESCE(v_old_score, 0)
+  WHERE id = p_post_id;
+
+  RETURN true;
+END;
+$BODY$;
@@ -0,0 +1,28 @@
+CREATE TABLE api_key
+(
+  id serial NOT NULL,
+  created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+  user_id integer NOT NULL,
+  name text NOT NULL,
+  key_hash text NOT NULL,
+  key_prefix text NOT NULL,
+  scopes text[] NOT NULL DEFAULT '{}',
+  last_used_at timestamp with time zone,
+
+  PRIMARY KEY (id),
+  UNIQUE (key_hash),
+
+  FOREIGN KEY (user_id)
+    REFERENCES "user" (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID
+);
+
+SELECT manage_updated_at('api_key'); -- Automatically manage updated_at
+
+CREATE INDEX api_key_user_id_idx ON api_key
+  USING btree
+  (user_id ASC NULLS LAST);
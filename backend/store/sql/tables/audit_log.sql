@@ -0,0 +1,32 @@
+-- The target is not a foreign key, so that entries are kept
+-- when what they are about is removed.
+CREATE TABLE audit_log
+(
+  id serial NOT NULL,
+  created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  user_id integer,
+  action text NOT NULL,
+  target_type text NOT NULL,
+  target_id integer NOT NULL,
+  details text,
+
+  PRIMARY KEY (id),
+
+  FOREIGN KEY (user_id)
+    REFERENCES "user" (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE SET NULL
+    NOT VALID
+);
+
+CREATE INDEX audit_log_user_id_idx ON audit_log
+  USING btree
+  (user_id ASC NULLS LAST);
+
+CREATE INDEX audit_log_action_idx ON audit_log
+  USING btree
+  (action ASC NULLS LAST);
+
+CREATE INDEX audit_log_target_idx ON audit_log
+  USING btree
+  (target_type ASC NULLS LAST, target_id ASC NULLS LAST);
@@ -7,6 +7,10 @@ CREATE TABLE comment
   user_id integer,
   user_name text,
   comment text NOT NULL,
+  is_deleted boolean NOT NULL DEFAULT false,
+  reply_to_id integer,
+  depth integer NOT NULL DEFAULT 0,
+  is_held boolean NOT NULL DEFAULT false,
 
   PRIMARY KEY (id),
 
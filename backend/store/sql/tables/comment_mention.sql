@@ -0,0 +1,14 @@
+-- Users mentioned in a comment, so that they can be notified of it.
+CREATE TABLE comment_mention
+(
+  comment_id integer NOT NULL,
+  user_id integer NOT NULL,
+
+  PRIMARY KEY (comment_id, user_id),
+
+  FOREIGN KEY (user_id)
+    REFERENCES "user" (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID
+);
@@ -0,0 +1,35 @@
+CREATE TABLE notification
+(
+  id serial NOT NULL,
+  created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  user_id integer NOT NULL,
+  kind text NOT NULL,
+  actor_user_id integer,
+  post_id integer,
+  comment_id integer,
+  is_read boolean NOT NULL DEFAULT false,
+
+  PRIMARY KEY (id),
+
+  FOREIGN KEY (user_id)
+    REFERENCES "user" (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID,
+
+  FOREIGN KEY (actor_user_id)
+    REFERENCES "user" (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE SET NULL
+    NOT VALID,
+
+  FOREIGN KEY (post_id)
+    REFERENCES post (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID
+);
+
+CREATE INDEX notification_user_id_idx ON notification
+  USING btree
+  (user_id ASC NULLS LAST);
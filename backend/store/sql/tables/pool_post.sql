@@ -0,0 +1,20 @@
+CREATE TABLE pool_post
+(
+  pool_id integer NOT NULL,
+  post_id integer NOT NULL,
+  position integer NOT NULL,
+
+  PRIMARY KEY (pool_id, post_id),
+
+  FOREIGN KEY (pool_id)
+    REFERENCES pool (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID,
+
+  FOREIGN KEY (post_id)
+    REFERENCES post (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID
+);
@@ -16,13 +16,24 @@ CREATE TABLE post
   tn_ext text NOT NULL,
   tags text[] NOT NULL DEFAULT '{}',
   is_deleted boolean NOT NULL DEFAULT false,
+  fav_count integer NOT NULL DEFAULT 0,
+  score integer NOT NULL DEFAULT 0,
+  parent_id integer,
+  rating text NOT NULL DEFAULT 'questionable',
 
   PRIMARY KEY (id),
+  CHECK (rating IN ('safe', 'questionable', 'explicit')),
 
   FOREIGN KEY (user_id)
     REFERENCES "user" (id) MATCH SIMPLE
     ON UPDATE NO ACTION
     ON DELETE CASCADE
+    NOT VALID,
+
+  FOREIGN KEY (parent_id)
+    REFERENCES post (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE SET NULL
     NOT VALID
 );
 
@@ -31,3 +42,18 @@ SELECT manage_updated_at('post'); -- Automatically manage updated_at
 CREATE INDEX post_is_deleted_idx ON post
   USING btree
   (is_deleted ASC NULLS LAST);
+
+CREATE INDEX post_fav_count_idx ON post
+  USING btree
+  (fav_count DESC NULLS LAST, id DESC NULLS LAST);
+
+CREATE INDEX post_score_idx ON post
+  USING btree
+  (score DESC NULLS LAST, id DESC NULLS LAST);
+
+CREATE INDEX post_parent_id_idx ON post
+  USING btree
+  (parent_id ASC NULLS LAST);
+
+CREATE TRIGGER notify_event AFTER INSERT ON post
+  FOR EACH ROW EXECUTE FUNCTION notify_post_event();
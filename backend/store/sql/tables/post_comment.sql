@@ -7,3 +7,6 @@ CREATE TABLE post_comment (
     ON DELETE CASCADE
     NOT VALID
 ) INHERITS (comment);
+
+CREATE TRIGGER notify_event AFTER INSERT OR UPDATE OF is_held ON post_comment
+  FOR EACH ROW EXECUTE FUNCTION notify_post_comment_event();
@@ -0,0 +1,24 @@
+CREATE TABLE post_favorite
+(
+  user_id integer NOT NULL,
+  post_id integer NOT NULL,
+  created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+  PRIMARY KEY (user_id, post_id),
+
+  FOREIGN KEY (user_id)
+    REFERENCES "user" (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID,
+
+  FOREIGN KEY (post_id)
+    REFERENCES post (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID
+);
+
+CREATE INDEX post_favorite_post_id_idx ON post_favorite
+  USING btree
+  (post_id ASC NULLS LAST);
@@ -0,0 +1,41 @@
+-- Resolved reports are kept, as a record of the decisions made.
+CREATE TABLE post_report
+(
+  id serial NOT NULL,
+  created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  post_id integer NOT NULL,
+  user_id integer NOT NULL,
+  reason text NOT NULL,
+  comment text,
+  status text NOT NULL DEFAULT 'open',
+  resolved_at timestamp with time zone,
+  resolved_by_user_id integer,
+
+  PRIMARY KEY (id),
+  CHECK (reason IN ('spam', 'illegal', 'offensive', 'wrong_rating', 'duplicate', 'other')),
+  CHECK (status IN ('open', 'dismissed', 'deleted', 'banned')),
+
+  FOREIGN KEY (post_id)
+    REFERENCES post (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID,
+
+  FOREIGN KEY (user_id)
+    REFERENCES "user" (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID,
+
+  FOREIGN KEY (resolved_by_user_id)
+    REFERENCES "user" (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE SET NULL
+    NOT VALID
+);
+
+-- Users can only have one open report per post
+CREATE UNIQUE INDEX post_report_open_idx ON post_report
+  USING btree
+  (post_id ASC NULLS LAST, user_id ASC NULLS LAST)
+  WHERE status = 'open';
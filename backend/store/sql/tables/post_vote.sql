@@ -0,0 +1,30 @@
+CREATE TABLE post_vote
+(
+  user_id integer NOT NULL,
+  post_id integer NOT NULL,
+  created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  score smallint NOT NULL,
+
+  PRIMARY KEY (user_id, post_id),
+
+  CHECK (score IN (-1, 1)),
+
+  FOREIGN KEY (user_id)
+    REFERENCES "user" (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID,
+
+  FOREIGN KEY (post_id)
+    REFERENCES post (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID
+);
+
+SELECT manage_updated_at('post_vote'); -- Automatically manage updated_at
+
+CREATE INDEX post_vote_post_id_idx ON post_vote
+  USING btree
+  (post_id ASC NULLS LAST);
@@ -12,9 +12,14 @@ CREATE TABLE refresh_token
 
   used boolean NOT NULL DEFAULT false,
   used_ip inet,
+  user_agent text,
 
   PRIMARY KEY (id),
   UNIQUE (token)
 );
 
 SELECT manage_updated_at('refresh_token'); -- Automatically manage updated_at
+
+CREATE INDEX refresh_token_user_id_idx ON refresh_token
+  USING btree
+  (user_id ASC NULLS LAST);
@@ -0,0 +1,14 @@
+CREATE TABLE revoked_session
+(
+  session bigint NOT NULL,
+  revoked_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+  PRIMARY KEY (session)
+);
+
+CREATE INDEX revoked_session_revoked_at_idx ON revoked_session
+  USING btree
+  (revoked_at ASC NULLS LAST);
+
+CREATE TRIGGER notify_event AFTER INSERT ON revoked_session
+  FOR EACH ROW EXECUTE FUNCTION notify_revoked_session_event();
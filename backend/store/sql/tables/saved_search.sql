@@ -0,0 +1,25 @@
+CREATE TABLE saved_search
+(
+  id serial NOT NULL,
+  created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  user_id integer NOT NULL,
+  name text NOT NULL,
+  include_tags text[] NOT NULL DEFAULT '{}',
+  exclude_tags text[] NOT NULL DEFAULT '{}',
+  last_seen_post_id integer NOT NULL DEFAULT 0,
+
+  PRIMARY KEY (id),
+
+  FOREIGN KEY (user_id)
+    REFERENCES "user" (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID
+);
+
+SELECT manage_updated_at('saved_search'); -- Automatically manage updated_at
+
+CREATE INDEX saved_search_user_id_idx ON saved_search
+  USING btree
+  (user_id ASC NULLS LAST);
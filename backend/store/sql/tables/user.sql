@@ -4,11 +4,21 @@ CREATE TABLE "user"
   created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
   updated_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
   name text NOT NULL,
-  password_hash text NOT NULL,
+  password_hash text,
   rank smallint NOT NULL DEFAULT 0,
+  invited_by_user_id integer,
+  max_rating text,
+  is_banned boolean NOT NULL DEFAULT false,
 
   PRIMARY KEY (id),
-  UNIQUE (name)
+  UNIQUE (name),
+  CHECK (max_rating IN ('safe', 'questionable', 'explicit')),
+
+  FOREIGN KEY (invited_by_user_id)
+    REFERENCES "user" (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE SET NULL
+    NOT VALID
 );
 
 SELECT manage_updated_at('user'); -- Automatically manage updated_at
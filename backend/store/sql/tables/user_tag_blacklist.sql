@@ -0,0 +1,13 @@
+CREATE TABLE user_tag_blacklist
+(
+  user_id integer NOT NULL,
+  tag text NOT NULL,
+
+  PRIMARY KEY (user_id, tag),
+
+  FOREIGN KEY (user_id)
+    REFERENCES "user" (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID
+);
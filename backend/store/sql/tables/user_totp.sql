@@ -0,0 +1,20 @@
+CREATE TABLE user_totp
+(
+  user_id integer NOT NULL,
+  created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+  secret bytea NOT NULL,
+  confirmed boolean NOT NULL DEFAULT false,
+  last_used_step bigint,
+
+  PRIMARY KEY (user_id),
+
+  FOREIGN KEY (user_id)
+    REFERENCES "user" (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID
+);
+
+SELECT manage_updated_at('user_totp'); -- Automatically manage updated_at
@@ -0,0 +1,6 @@
+CREATE TYPE new_api_key AS (
+  name text,
+  key_hash text,
+  key_prefix text,
+  scopes text[]
+);
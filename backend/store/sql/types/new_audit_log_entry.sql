@@ -0,0 +1,6 @@
+CREATE TYPE new_audit_log_entry AS (
+  action text,
+  target_type text,
+  target_id integer,
+  details text
+);
@@ -0,0 +1,5 @@
+CREATE TYPE new_invite_code AS (
+  code text,
+  max_uses integer,
+  expires_at timestamp with time zone
+);
@@ -0,0 +1,7 @@
+CREATE TYPE new_pool AS (
+  user_id integer,
+  name text,
+  description text,
+  post_ids integer[],
+  created_at timestamp with time zone
+);
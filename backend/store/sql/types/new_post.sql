@@ -9,5 +9,7 @@ CREATE TYPE new_post AS (
   height integer,
   hash text,
   ext text,
-  tn_ext text
+  tn_ext text,
+  parent_id integer,
+  rating text
 );
@@ -1,4 +1,6 @@
 CREATE TYPE new_post_comment AS (
   post_id integer,
-  comment text
+  comment text,
+  reply_to_id integer,
+  is_held boolean
 );
@@ -0,0 +1,5 @@
+CREATE TYPE new_post_report AS (
+  post_id integer,
+  reason text,
+  comment text
+);
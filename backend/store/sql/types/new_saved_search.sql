@@ -0,0 +1,6 @@
+CREATE TYPE new_saved_search AS (
+  user_id integer,
+  name text,
+  include_tags text[],
+  exclude_tags text[]
+);
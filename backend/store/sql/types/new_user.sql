@@ -1,4 +1,5 @@
 CREATE TYPE new_user AS (
   name text,
-  password_hash text
+  password_hash text,
+  invite_code text
 );
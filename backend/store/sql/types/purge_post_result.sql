@@ -0,0 +1,6 @@
+CREATE TYPE purge_post_result AS (
+  hash text,
+  ext text,
+  tn_ext text,
+  is_file_shared boolean
+);
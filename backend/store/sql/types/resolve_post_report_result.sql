@@ -0,0 +1 @@
+CREATE TYPE resolve_post_report_result AS (status text, sessions bigint[]);
@@ -0,0 +1,15 @@
+-- Options for searches using metatags
+CREATE TYPE search_options AS
+(
+  fav_user_name text,
+  min_score integer,
+  max_score integer,
+  sort text,
+  pool_id integer,
+  parent_id integer,
+  has_parent boolean,
+  child_id integer,
+  has_children boolean,
+  ratings text[],
+  after_id integer
+);
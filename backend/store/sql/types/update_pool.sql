@@ -0,0 +1,5 @@
+CREATE TYPE update_pool AS (
+  name text,
+  description text,
+  post_ids integer[]
+);
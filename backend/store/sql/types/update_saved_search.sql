@@ -0,0 +1,5 @@
+CREATE TYPE update_saved_search AS (
+  name text,
+  include_tags text[],
+  exclude_tags text[]
+);
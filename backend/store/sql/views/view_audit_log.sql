@@ -0,0 +1,13 @@
+CREATE VIEW view_audit_log
+AS
+SELECT
+  a.id,
+  a.created_at,
+  a.user_id,
+  u.name AS user_name,
+  a.action,
+  a.target_type,
+  a.target_id,
+  a.details
+FROM audit_log AS a
+LEFT JOIN "user" AS u ON u.id = a.user_id;
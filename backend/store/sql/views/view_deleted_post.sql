@@ -0,0 +1,26 @@
+-- Deleted posts, in the same shape as view_post.
+CREATE VIEW view_deleted_post
+AS
+SELECT
+  p.id,
+  p.created_at,
+  p.user_id,
+  u.name AS user_name,
+  p.title,
+  p.description,
+  p.source,
+  p.filename,
+  p.size,
+  p.width,
+  p.height,
+  p.hash,
+  p.ext,
+  p.tn_ext,
+  p.tags,
+  p.fav_count,
+  p.score,
+  p.parent_id,
+  p.rating
+FROM post AS p
+JOIN "user" AS u ON u.id = p.user_id
+WHERE is_deleted;
@@ -0,0 +1,16 @@
+-- Posts are identified by their hash, as post IDs are not preserved by export and import.
+CREATE VIEW view_export_pool
+AS
+SELECT
+  vp.id,
+  vp.created_at,
+  vp.user_name,
+  vp.name,
+  vp.description,
+  array(
+    SELECT p.hash
+    FROM unnest(vp.post_ids) WITH ORDINALITY AS pid(post_id, position)
+    JOIN post AS p ON p.id = pid.post_id
+    ORDER BY pid.position ASC
+  ) AS post_hashes
+FROM view_pool AS vp;
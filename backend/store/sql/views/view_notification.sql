@@ -0,0 +1,14 @@
+CREATE VIEW view_notification
+AS
+SELECT
+  n.id,
+  n.created_at,
+  n.user_id,
+  n.kind,
+  n.actor_user_id,
+  u.name AS actor_user_name,
+  n.post_id,
+  n.comment_id,
+  n.is_read
+FROM notification AS n
+LEFT JOIN "user" AS u ON u.id = n.actor_user_id;
@@ -0,0 +1,20 @@
+CREATE VIEW view_pool
+AS
+SELECT
+  pl.id,
+  pl.created_at,
+  pl.updated_at,
+  pl.user_id,
+  u.name AS user_name,
+  pl.name,
+  pl.description,
+  array(
+    SELECT pp.post_id
+    FROM pool_post AS pp
+    JOIN post AS p ON p.id = pp.post_id
+    WHERE pp.pool_id = pl.id
+      AND NOT p.is_deleted
+    ORDER BY pp.position ASC
+  ) AS post_ids
+FROM pool AS pl
+JOIN "user" AS u ON u.id = pl.user_id;
@@ -15,7 +15,11 @@ SELECT
   p.hash,
   p.ext,
   p.tn_ext,
-  p.tags
+  p.tags,
+  p.fav_count,
+  p.score,
+  p.parent_id,
+  p.rating
 FROM post AS p
 JOIN "user" AS u ON u.id = p.user_id
 WHERE NOT is_deleted;
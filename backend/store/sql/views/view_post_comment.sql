@@ -0,0 +1,18 @@
+-- The content and author of deleted comments are hidden.
+-- Held comments are not included.
+CREATE VIEW view_post_comment
+AS
+SELECT
+  pc.id,
+  pc.created_at,
+  pc.updated_at,
+  (CASE WHEN pc.is_deleted THEN NULL ELSE pc.user_id END) AS user_id,
+  (CASE WHEN pc.is_deleted THEN NULL ELSE pc.user_name END) AS user_name,
+  pc.post_id,
+  (CASE WHEN pc.is_deleted THEN '[removed]' ELSE pc.comment END) AS comment,
+  pc.is_deleted,
+  pc.updated_at > pc.created_at AS is_edited,
+  pc.reply_to_id,
+  pc.depth
+FROM post_comment AS pc
+WHERE NOT pc.is_held;
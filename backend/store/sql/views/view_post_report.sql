@@ -0,0 +1,19 @@
+CREATE VIEW view_post_report
+AS
+SELECT
+  r.id,
+  r.created_at,
+  r.post_id,
+  p.user_id AS post_user_id,
+  r.user_id,
+  u.name AS user_name,
+  r.reason,
+  r.comment,
+  r.status,
+  r.resolved_at,
+  r.resolved_by_user_id,
+  ru.name AS resolved_by_user_name
+FROM post_report AS r
+JOIN post AS p ON p.id = r.post_id
+JOIN "user" AS u ON u.id = r.user_id
+LEFT JOIN "user" AS ru ON ru.id = r.resolved_by_user_id;
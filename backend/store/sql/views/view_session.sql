@@ -0,0 +1,14 @@
+CREATE VIEW view_session
+AS
+SELECT
+  rt.session,
+  rt.user_id,
+  MIN(rt.created_at) AS created_at,
+  (array_agg(rt.created_ip ORDER BY rt.id ASC))[1] AS created_ip,
+  MAX(rt.created_at) AS last_seen_at,
+  (array_agg(rt.created_ip ORDER BY rt.id DESC))[1] AS last_seen_ip,
+  (array_agg(rt.user_agent ORDER BY rt.id DESC))[1] AS user_agent
+FROM refresh_token AS rt
+GROUP BY rt.session, rt.user_id
+-- Only sessions that still have a usable refresh token are active
+HAVING bool_or(NOT rt.used AND rt.expires_at > CURRENT_TIMESTAMP);
@@ -1,3 +1,5 @@
+use std::net::IpAddr;
+
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
@@ -7,8 +9,46 @@ pub struct User {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub name: String,
-    pub password_hash: String,
+    pub password_hash: Option<String>,
     pub rank: i16,
+    pub invited_by_user_id: Option<i32>,
+    pub max_rating: Option<String>,
+    pub is_banned: bool,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct InviteCode {
+    pub id: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub user_id: i32,
+    pub code: String,
+    pub max_uses: i32,
+    pub uses: i32,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct UserTotp {
+    pub user_id: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub secret: Vec<u8>,
+    pub confirmed: bool,
+    pub last_used_step: Option<i64>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct ApiKey {
+    pub id: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub user_id: i32,
+    pub name: String,
+    pub key_hash: String,
+    pub key_prefix: String,
+    pub scopes: Vec<String>,
+    pub last_used_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, sqlx::FromRow)]
@@ -29,6 +69,10 @@ pub struct Post {
     pub tn_ext: String,
     pub tags: Vec<String>,
     pub is_deleted: bool,
+    pub fav_count: i32,
+    pub score: i32,
+    pub parent_id: Option<i32>,
+    pub rating: String,
 }
 
 #[derive(Debug, sqlx::FromRow)]
@@ -48,6 +92,51 @@ pub struct ViewPost {
     pub ext: Option<String>,
     pub tn_ext: Option<String>,
     pub tags: Option<Vec<String>>,
+    pub fav_count: Option<i32>,
+    pub score: Option<i32>,
+    pub parent_id: Option<i32>,
+    pub rating: Option<String>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct ViewPool {
+    pub id: Option<i32>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub user_id: Option<i32>,
+    pub user_name: Option<String>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub post_ids: Option<Vec<i32>>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct PostPool {
+    pub id: Option<i32>,
+    pub name: Option<String>,
+    pub previous_post_id: Option<i32>,
+    pub next_post_id: Option<i32>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct ExportPool {
+    pub created_at: Option<DateTime<Utc>>,
+    pub user_name: Option<String>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub post_hashes: Option<Vec<String>>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct SavedSearch {
+    pub id: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub user_id: i32,
+    pub name: String,
+    pub include_tags: Vec<String>,
+    pub exclude_tags: Vec<String>,
+    pub last_seen_post_id: i32,
 }
 
 #[derive(Debug, sqlx::FromRow)]
@@ -59,6 +148,66 @@ pub struct PostComment {
     pub user_name: Option<String>,
     pub post_id: i32,
     pub comment: String,
+    pub is_deleted: bool,
+    pub reply_to_id: Option<i32>,
+    pub depth: i32,
+    pub is_held: bool,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct ViewPostComment {
+    pub id: Option<i32>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub user_id: Option<i32>,
+    pub user_name: Option<String>,
+    pub post_id: Option<i32>,
+    pub comment: Option<String>,
+    pub is_deleted: Option<bool>,
+    pub is_edited: Option<bool>,
+    pub reply_to_id: Option<i32>,
+    pub depth: Option<i32>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct ViewNotification {
+    pub id: Option<i32>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub user_id: Option<i32>,
+    pub kind: Option<String>,
+    pub actor_user_id: Option<i32>,
+    pub actor_user_name: Option<String>,
+    pub post_id: Option<i32>,
+    pub comment_id: Option<i32>,
+    pub is_read: Option<bool>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct ViewPostReport {
+    pub id: Option<i32>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub post_id: Option<i32>,
+    pub post_user_id: Option<i32>,
+    pub user_id: Option<i32>,
+    pub user_name: Option<String>,
+    pub reason: Option<String>,
+    pub comment: Option<String>,
+    pub status: Option<String>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub resolved_by_user_id: Option<i32>,
+    pub resolved_by_user_name: Option<String>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct ViewAuditLogEntry {
+    pub id: Option<i32>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub user_id: Option<i32>,
+    pub user_name: Option<String>,
+    pub action: Option<String>,
+    pub target_type: Option<String>,
+    pub target_id: Option<i32>,
+    pub details: Option<String>,
 }
 
 #[derive(Debug, sqlx::FromRow)]
@@ -70,6 +219,17 @@ pub struct ViewTag {
     pub implied_tags: Option<Vec<String>>,
 }
 
+#[derive(Debug, sqlx::FromRow)]
+pub struct ViewSession {
+    pub session: Option<i64>,
+    pub user_id: Option<i32>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub created_ip: Option<IpAddr>,
+    pub last_seen_at: Option<DateTime<Utc>>,
+    pub last_seen_ip: Option<IpAddr>,
+    pub user_agent: Option<String>,
+}
+
 #[derive(Debug, sqlx::Type)]
 #[sqlx(type_name = "new_post")]
 pub struct NewPost {
@@ -84,6 +244,24 @@ pub struct NewPost {
     pub hash: Option<String>,
     pub ext: Option<String>,
     pub tn_ext: Option<String>,
+    pub parent_id: Option<i32>,
+    pub rating: Option<String>,
+}
+
+#[derive(Debug, sqlx::Type)]
+#[sqlx(type_name = "search_options")]
+pub struct SearchOptions {
+    pub fav_user_name: Option<String>,
+    pub min_score: Option<i32>,
+    pub max_score: Option<i32>,
+    pub sort: Option<String>,
+    pub pool_id: Option<i32>,
+    pub parent_id: Option<i32>,
+    pub has_parent: Option<bool>,
+    pub child_id: Option<i32>,
+    pub has_children: Option<bool>,
+    pub ratings: Option<Vec<String>>,
+    pub after_id: Option<i32>,
 }
 
 #[derive(Debug, sqlx::Type)]
@@ -97,6 +275,46 @@ pub struct UpdatePost {
 
     pub add_tags: Vec<String>,
     pub remove_tags: Vec<String>,
+
+    pub parent_id: Option<i32>,
+    pub update_parent: Option<bool>,
+
+    pub rating: Option<String>,
+}
+
+#[derive(Debug, sqlx::Type)]
+#[sqlx(type_name = "new_pool")]
+pub struct NewPool {
+    pub user_id: Option<i32>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub post_ids: Vec<i32>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, sqlx::Type)]
+#[sqlx(type_name = "update_pool")]
+pub struct UpdatePool {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub post_ids: Vec<i32>,
+}
+
+#[derive(Debug, sqlx::Type)]
+#[sqlx(type_name = "new_saved_search")]
+pub struct NewSavedSearch {
+    pub user_id: Option<i32>,
+    pub name: Option<String>,
+    pub include_tags: Vec<String>,
+    pub exclude_tags: Vec<String>,
+}
+
+#[derive(Debug, sqlx::Type)]
+#[sqlx(type_name = "update_saved_search")]
+pub struct UpdateSavedSearch {
+    pub name: Option<String>,
+    pub include_tags: Vec<String>,
+    pub exclude_tags: Vec<String>,
 }
 
 #[derive(Debug, sqlx::Type)]
@@ -113,6 +331,24 @@ pub struct UpdateTag {
 pub struct NewUser {
     pub name: Option<String>,
     pub password_hash: Option<String>,
+    pub invite_code: Option<String>,
+}
+
+#[derive(Debug, sqlx::Type)]
+#[sqlx(type_name = "new_invite_code")]
+pub struct NewInviteCode {
+    pub code: Option<String>,
+    pub max_uses: Option<i32>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, sqlx::Type)]
+#[sqlx(type_name = "new_api_key")]
+pub struct NewApiKey {
+    pub name: Option<String>,
+    pub key_hash: Option<String>,
+    pub key_prefix: Option<String>,
+    pub scopes: Vec<String>,
 }
 
 #[derive(Debug, sqlx::Type)]
@@ -120,6 +356,25 @@ pub struct NewUser {
 pub struct NewPostComment {
     pub post_id: i32,
     pub comment: String,
+    pub reply_to_id: Option<i32>,
+    pub is_held: bool,
+}
+
+#[derive(Debug, sqlx::Type)]
+#[sqlx(type_name = "new_post_report")]
+pub struct NewPostReport {
+    pub post_id: i32,
+    pub reason: String,
+    pub comment: Option<String>,
+}
+
+/// Criteria that audit log entries must all match
+#[derive(Debug, Default)]
+pub struct AuditLogFilter {
+    pub user_id: Option<i32>,
+    pub action: Option<String>,
+    pub target_type: Option<String>,
+    pub target_id: Option<i32>,
 }
 
 #[derive(Debug, sqlx::Type)]
@@ -129,6 +384,15 @@ pub struct PageInfo {
     pub start_id: Option<i32>,
 }
 
+#[derive(Debug, sqlx::Type)]
+#[sqlx(type_name = "purge_post_result")]
+pub struct PurgePostResult {
+    pub hash: Option<String>,
+    pub ext: Option<String>,
+    pub tn_ext: Option<String>,
+    pub is_file_shared: Option<bool>,
+}
+
 #[derive(Debug, sqlx::Type)]
 #[sqlx(type_name = "refresh_refresh_token_result")]
 pub struct CreateRefreshTokenResult {
@@ -143,3 +407,10 @@ pub struct RefreshRefreshTokenResult {
     pub session: Option<i64>,
     pub user_id: Option<i32>,
 }
+
+#[derive(Debug, sqlx::Type)]
+#[sqlx(type_name = "resolve_post_report_result")]
+pub struct ResolvePostReportResult {
+    pub status: Option<String>,
+    pub sessions: Option<Vec<i64>>,
+}
@@ -0,0 +1,83 @@
+use anyhow::Context;
+
+use crate::{models as dbm, PgStore, StoreError};
+
+impl PgStore {
+    pub async fn create_api_key(&self, api_key: &dbm::NewApiKey, user_id: i32) -> Result<dbm::ApiKey, StoreError> {
+        let api_key = sqlx::query_as_unchecked!(
+            dbm::ApiKey,
+            r#"SELECT * FROM create_api_key($1, $2);"#,
+            api_key,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("Error creating API key in database")?;
+
+        Ok(api_key)
+    }
+
+    pub async fn delete_api_key(&self, id: i32, user_id: i32) -> Result<bool, StoreError> {
+        let success = sqlx::query_scalar_unchecked!(r#"SELECT delete_api_key($1, $2);"#, id, user_id)
+            .fetch_one(&self.pool)
+            .await
+            .context("Error deleting API key in database")?;
+
+        Ok(success.unwrap())
+    }
+
+    pub async fn get_user_api_keys(&self, user_id: i32) -> Result<Vec<dbm::ApiKey>, StoreError> {
+        let api_keys = sqlx::query_as!(
+            dbm::ApiKey,
+            r#"SELECT * FROM api_key WHERE user_id = $1 ORDER BY id ASC;"#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Error getting API keys from database")?;
+
+        Ok(api_keys)
+    }
+
+    /// Get the API key with the specified hash, updating its last used time.
+    pub async fn use_api_key(&self, key_hash: &str) -> Result<Option<dbm::ApiKey>, StoreError> {
+        let api_key = sqlx::query_as_unchecked!(dbm::ApiKey, r#"SELECT * FROM use_api_key($1);"#, key_hash)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Error using API key in database")?;
+
+        Ok(api_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::test_db;
+
+    #[tokio::test]
+    async fn last_used_time_is_throttled() {
+        let Some(store) = test_db::create().await else {
+            return;
+        };
+
+        let user = test_db::create_user(&store, "user").await;
+
+        let api_key = dbm::NewApiKey {
+            name: Some("key".to_string()),
+            key_hash: Some("hash".to_string()),
+            key_prefix: Some("prefix".to_string()),
+            scopes: vec![],
+        };
+        store.create_api_key(&api_key, user.id).await.unwrap();
+
+        assert!(store.use_api_key("unknown").await.unwrap().is_none());
+
+        let first = store.use_api_key("hash").await.unwrap().unwrap();
+        assert!(first.last_used_at.is_some());
+
+        // Using the key again right away does not update the last used time
+        let second = store.use_api_key("hash").await.unwrap().unwrap();
+        assert_eq!(second.last_used_at, first.last_used_at);
+    }
+}
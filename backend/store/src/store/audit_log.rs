@@ -0,0 +1,35 @@
+use anyhow::Context;
+
+use crate::{models as dbm, PgStore, StoreError};
+
+impl PgStore {
+    pub async fn get_audit_log(
+        &self,
+        filter: &dbm::AuditLogFilter,
+        start_id: i32,
+        limit: i32,
+    ) -> Result<Vec<dbm::ViewAuditLogEntry>, StoreError> {
+        let entries = sqlx::query_as!(
+            dbm::ViewAuditLogEntry,
+            r#"SELECT * FROM view_audit_log
+            WHERE ($1::integer IS NULL OR user_id = $1)
+              AND ($2::text IS NULL OR action = $2)
+              AND ($3::text IS NULL OR target_type = $3)
+              AND ($4::integer IS NULL OR target_id = $4)
+              AND id <= $5
+            ORDER BY id DESC
+            LIMIT $6;"#,
+            filter.user_id,
+            filter.action,
+            filter.target_type,
+            filter.target_id,
+            Some(start_id),
+            i64::from(limit)
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Error getting audit log from database")?;
+
+        Ok(entries)
+    }
+}
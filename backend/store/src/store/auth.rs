@@ -1,10 +1,11 @@
 use std::net::IpAddr;
 
 use anyhow::Context;
+use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
 use crate::{
-    models::{CreateRefreshTokenResult, RefreshRefreshTokenResult},
+    models::{CreateRefreshTokenResult, RefreshRefreshTokenResult, ViewSession},
     PgStore,
 };
 
@@ -13,12 +14,14 @@ impl PgStore {
         &self,
         user_id: i32,
         ip: IpAddr,
+        user_agent: Option<&str>,
     ) -> Result<CreateRefreshTokenResult, anyhow::Error> {
         let token = sqlx::query_as_unchecked!(
             CreateRefreshTokenResult,
-            r#"SELECT * FROM create_refresh_token($1, $2);"#,
+            r#"SELECT * FROM create_refresh_token($1, $2, $3);"#,
             user_id,
-            ip
+            ip,
+            user_agent
         )
         .fetch_one(&self.pool)
         .await
@@ -40,16 +43,76 @@ impl PgStore {
         Ok(())
     }
 
+    pub async fn invalidate_user_session(&self, user_id: i32, session: i64) -> Result<bool, anyhow::Error> {
+        let success = sqlx::query_scalar_unchecked!(r#"SELECT invalidate_user_session($1, $2);"#, user_id, session)
+            .fetch_one(&self.pool)
+            .await
+            .context("Error invalidating user session")?;
+
+        Ok(success.unwrap())
+    }
+
+    pub async fn invalidate_user_sessions(&self, user_id: i32) -> Result<Vec<i64>, anyhow::Error> {
+        let sessions = sqlx::query_scalar_unchecked!(r#"SELECT invalidate_user_sessions($1);"#, user_id)
+            .fetch_one(&self.pool)
+            .await
+            .context("Error invalidating user sessions")?;
+
+        Ok(sessions.unwrap_or_default())
+    }
+
+    pub async fn get_revoked_sessions(&self, since: DateTime<Utc>) -> Result<Vec<i64>, anyhow::Error> {
+        let sessions = sqlx::query_scalar!(r#"SELECT session FROM revoked_session WHERE revoked_at > $1;"#, since)
+            .fetch_all(&self.pool)
+            .await
+            .context("Error getting revoked sessions from database")?;
+
+        Ok(sessions)
+    }
+
+    pub async fn prune_revoked_sessions(&self, before: DateTime<Utc>) -> Result<u64, anyhow::Error> {
+        let result = sqlx::query!(r#"DELETE FROM revoked_session WHERE revoked_at < $1;"#, before)
+            .execute(&self.pool)
+            .await
+            .context("Error pruning revoked sessions")?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn get_user_sessions(&self, user_id: i32) -> Result<Vec<ViewSession>, anyhow::Error> {
+        let sessions = sqlx::query_as_unchecked!(
+            ViewSession,
+            r#"SELECT * FROM view_session WHERE user_id = $1 ORDER BY last_seen_at DESC;"#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Error getting user sessions from database")?;
+
+        Ok(sessions)
+    }
+
+    pub async fn prune_refresh_tokens(&self) -> Result<i32, anyhow::Error> {
+        let count = sqlx::query_scalar_unchecked!(r#"SELECT prune_refresh_tokens();"#)
+            .fetch_one(&self.pool)
+            .await
+            .context("Error pruning refresh tokens")?;
+
+        Ok(count.unwrap())
+    }
+
     pub async fn refresh_refresh_token(
         &self,
         token: Uuid,
         ip: IpAddr,
+        user_agent: Option<&str>,
     ) -> Result<RefreshRefreshTokenResult, anyhow::Error> {
         let result = sqlx::query_as_unchecked!(
             RefreshRefreshTokenResult,
-            r#"SELECT * FROM refresh_refresh_token($1, $2);"#,
+            r#"SELECT * FROM refresh_refresh_token($1, $2, $3);"#,
             token,
-            ip
+            ip,
+            user_agent
         )
         .fetch_optional(&self.pool)
         .await?
@@ -1,4 +1,5 @@
 use anyhow::Context;
+use chrono::{DateTime, Utc};
 
 use crate::{models as dbm, PgStore, StoreError};
 
@@ -7,12 +8,14 @@ impl PgStore {
         &self,
         comment: dbm::NewPostComment,
         user_id: Option<i32>,
+        mentions: &[String],
     ) -> Result<dbm::PostComment, StoreError> {
         let comment = sqlx::query_as_unchecked!(
             dbm::PostComment,
-            r#"SELECT * FROM create_post_comment($1, $2);"#,
+            r#"SELECT * FROM create_post_comment($1, $2, $3);"#,
             comment,
-            user_id
+            user_id,
+            mentions
         )
         .fetch_one(&self.pool)
         .await
@@ -21,10 +24,10 @@ impl PgStore {
         Ok(comment)
     }
 
-    pub async fn get_post_comments(&self, post_id: i32) -> Result<Vec<dbm::PostComment>, StoreError> {
+    pub async fn get_post_comments(&self, post_id: i32) -> Result<Vec<dbm::ViewPostComment>, StoreError> {
         let comments = sqlx::query_as!(
-            dbm::PostComment,
-            r#"SELECT * FROM post_comment WHERE post_id = $1 ORDER BY id ASC;"#,
+            dbm::ViewPostComment,
+            r#"SELECT * FROM view_post_comment WHERE post_id = $1 ORDER BY id ASC;"#,
             Some(post_id)
         )
         .fetch_all(&self.pool)
@@ -33,4 +36,101 @@ impl PgStore {
 
         Ok(comments)
     }
+
+    /// Get held comments that have not been deleted, oldest first.
+    pub async fn get_held_comments(&self) -> Result<Vec<dbm::PostComment>, StoreError> {
+        let comments = sqlx::query_as!(
+            dbm::PostComment,
+            r#"SELECT * FROM post_comment WHERE is_held AND NOT is_deleted ORDER BY id ASC;"#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Error getting held comments from database")?;
+
+        Ok(comments)
+    }
+
+    pub async fn approve_comment(&self, id: i32, user_id: i32) -> Result<bool, StoreError> {
+        let success = sqlx::query_scalar_unchecked!(r#"SELECT approve_comment($1, $2);"#, id, user_id)
+            .fetch_one(&self.pool)
+            .await
+            .context("Error approving comment in database")?;
+
+        Ok(success.unwrap())
+    }
+
+    pub async fn update_comment(
+        &self,
+        id: i32,
+        comment: &str,
+        user_id: i32,
+        created_after: DateTime<Utc>,
+        mentions: &[String],
+        is_held: bool,
+    ) -> Result<bool, StoreError> {
+        let success = sqlx::query_scalar_unchecked!(
+            r#"SELECT update_comment($1, $2, $3, $4, $5, $6);"#,
+            id,
+            comment,
+            user_id,
+            created_after,
+            mentions,
+            is_held
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("Error updating comment in database")?;
+
+        Ok(success.unwrap())
+    }
+
+    pub async fn delete_comment(
+        &self,
+        id: i32,
+        user_id: i32,
+        created_after: DateTime<Utc>,
+    ) -> Result<bool, StoreError> {
+        let success =
+            sqlx::query_scalar_unchecked!(r#"SELECT delete_comment($1, $2, $3);"#, id, user_id, created_after)
+                .fetch_one(&self.pool)
+                .await
+                .context("Error deleting comment in database")?;
+
+        Ok(success.unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::test_db;
+
+    #[tokio::test]
+    async fn deleting_anonymous_comments_is_audited() {
+        let Some(store) = test_db::create().await else {
+            return;
+        };
+
+        let admin = test_db::create_user(&store, "admin").await;
+        let post_id = test_db::create_post(&store, admin.id, "hash").await;
+
+        let comment = dbm::NewPostComment {
+            post_id,
+            comment: "comment".to_string(),
+            reply_to_id: None,
+            is_held: false,
+        };
+        let comment_id = store.create_post_comment(comment, None, &[]).await.unwrap().id;
+
+        assert!(store.delete_comment(comment_id, admin.id, Utc::now()).await.unwrap());
+
+        let filter = dbm::AuditLogFilter {
+            user_id: Some(admin.id),
+            action: Some("delete_comment".to_string()),
+            target_type: None,
+            target_id: Some(comment_id),
+        };
+        let entries = store.get_audit_log(&filter, i32::MAX, 10).await.unwrap();
+        assert_eq!(entries.len(), 1);
+    }
 }
@@ -0,0 +1,40 @@
+use anyhow::Context;
+use sqlx::postgres::PgListener;
+
+use crate::{PgStore, StoreError};
+
+/// Channel that events are sent on by triggers in the database
+const EVENT_CHANNEL: &str = "blazebooru_event";
+
+pub struct EventListener {
+    listener: PgListener,
+}
+
+impl PgStore {
+    pub async fn listen_events(&self) -> Result<EventListener, StoreError> {
+        let mut listener = PgListener::connect_with(&self.pool)
+            .await
+            .context("Error connecting event listener to database")?;
+
+        listener
+            .listen(EVENT_CHANNEL)
+            .await
+            .context("Error listening for events in database")?;
+
+        Ok(EventListener { listener })
+    }
+}
+
+impl EventListener {
+    /// Wait for the next event, returning its JSON payload.
+    /// Returns None if the connection was lost, in which case events may have been missed.
+    pub async fn recv(&mut self) -> Result<Option<String>, StoreError> {
+        let notification = self
+            .listener
+            .try_recv()
+            .await
+            .context("Error receiving event from database")?;
+
+        Ok(notification.map(|n| n.payload().to_string()))
+    }
+}
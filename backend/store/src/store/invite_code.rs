@@ -0,0 +1,59 @@
+use anyhow::Context;
+
+use crate::{models as dbm, PgStore, StoreError};
+
+impl PgStore {
+    pub async fn create_invite_code(
+        &self,
+        invite_code: &dbm::NewInviteCode,
+        user_id: i32,
+    ) -> Result<dbm::InviteCode, StoreError> {
+        let invite_code = sqlx::query_as_unchecked!(
+            dbm::InviteCode,
+            r#"SELECT * FROM create_invite_code($1, $2);"#,
+            invite_code,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("Error creating invite code in database")?;
+
+        Ok(invite_code)
+    }
+
+    pub async fn delete_invite_code(&self, id: i32, user_id: i32) -> Result<bool, StoreError> {
+        let success = sqlx::query_scalar_unchecked!(r#"SELECT delete_invite_code($1, $2);"#, id, user_id)
+            .fetch_one(&self.pool)
+            .await
+            .context("Error deleting invite code in database")?;
+
+        Ok(success.unwrap())
+    }
+
+    pub async fn get_user_invite_codes(&self, user_id: i32) -> Result<Vec<dbm::InviteCode>, StoreError> {
+        let invite_codes = sqlx::query_as!(
+            dbm::InviteCode,
+            r#"SELECT * FROM invite_code WHERE user_id = $1 ORDER BY id DESC;"#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Error getting invite codes from database")?;
+
+        Ok(invite_codes)
+    }
+
+    /// Check whether an invite code exists and can still be used.
+    pub async fn is_invite_code_valid(&self, code: &str) -> Result<bool, StoreError> {
+        let valid = sqlx::query_scalar!(
+            r#"SELECT EXISTS(SELECT * FROM invite_code
+            WHERE code = $1 AND uses < max_uses AND (expires_at IS NULL OR expires_at > CURRENT_TIMESTAMP));"#,
+            code
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("Error checking invite code in database")?;
+
+        Ok(valid.unwrap_or(false))
+    }
+}
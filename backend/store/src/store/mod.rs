@@ -1,9 +1,23 @@
+mod api_key;
+mod audit_log;
 mod auth;
 mod comment;
+mod event;
+mod invite_code;
+mod notification;
+mod pool;
 mod post;
+mod report;
+mod saved_search;
 mod tag;
+#[cfg(test)]
+mod test_db;
+mod totp;
 mod user;
 
+pub use event::EventListener;
+pub use post::PostFilesLock;
+
 use anyhow::Context;
 use thiserror::Error;
 
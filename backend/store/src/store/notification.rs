@@ -0,0 +1,46 @@
+use anyhow::Context;
+
+use crate::{models as dbm, PgStore, StoreError};
+
+impl PgStore {
+    pub async fn get_notifications(
+        &self,
+        user_id: i32,
+        start_id: i32,
+        limit: i32,
+    ) -> Result<Vec<dbm::ViewNotification>, StoreError> {
+        let notifications = sqlx::query_as!(
+            dbm::ViewNotification,
+            r#"SELECT * FROM view_notification WHERE user_id = $1 AND id <= $2 ORDER BY id DESC LIMIT $3;"#,
+            Some(user_id),
+            Some(start_id),
+            i64::from(limit)
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Error getting notifications from database")?;
+
+        Ok(notifications)
+    }
+
+    pub async fn get_unread_notification_count(&self, user_id: i32) -> Result<i64, StoreError> {
+        let count = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) FROM notification WHERE user_id = $1 AND NOT is_read;"#,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("Error getting unread notification count from database")?;
+
+        Ok(count.unwrap())
+    }
+
+    pub async fn mark_notifications_read(&self, user_id: i32, ids: Option<&[i32]>) -> Result<(), StoreError> {
+        sqlx::query!(r#"SELECT mark_notifications_read($1, $2);"#, user_id, ids)
+            .execute(&self.pool)
+            .await
+            .context("Error marking notifications as read in database")?;
+
+        Ok(())
+    }
+}
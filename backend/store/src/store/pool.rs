@@ -0,0 +1,71 @@
+use anyhow::Context;
+
+use crate::{models as dbm, PgStore, StoreError};
+
+impl PgStore {
+    pub async fn get_view_pool(&self, id: i32) -> Result<Option<dbm::ViewPool>, StoreError> {
+        let pool = sqlx::query_as!(dbm::ViewPool, r#"SELECT * FROM view_pool WHERE id = $1;"#, id)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Error getting view pool from database")?;
+
+        Ok(pool)
+    }
+
+    pub async fn get_view_pools(&self) -> Result<Vec<dbm::ViewPool>, StoreError> {
+        let pools = sqlx::query_as!(dbm::ViewPool, r#"SELECT * FROM view_pool ORDER BY id DESC;"#)
+            .fetch_all(&self.pool)
+            .await
+            .context("Error getting view pools from database")?;
+
+        Ok(pools)
+    }
+
+    pub async fn get_post_pools(&self, post_id: i32) -> Result<Vec<dbm::PostPool>, StoreError> {
+        let pools = sqlx::query_as!(dbm::PostPool, r#"SELECT * FROM get_post_pools($1);"#, post_id)
+            .fetch_all(&self.pool)
+            .await
+            .context("Error getting post pools from database")?;
+
+        Ok(pools)
+    }
+
+    pub async fn get_export_pools(&self) -> Result<Vec<dbm::ExportPool>, StoreError> {
+        let pools = sqlx::query_as!(
+            dbm::ExportPool,
+            r#"SELECT created_at, user_name, name, description, post_hashes FROM view_export_pool ORDER BY id ASC;"#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Error getting export pools from database")?;
+
+        Ok(pools)
+    }
+
+    pub async fn create_pool(&self, pool: &dbm::NewPool) -> Result<i32, StoreError> {
+        let new_pool_id = sqlx::query_scalar_unchecked!(r#"SELECT create_pool($1);"#, pool)
+            .fetch_one(&self.pool)
+            .await
+            .context("Error creating pool in database")?;
+
+        Ok(new_pool_id.unwrap())
+    }
+
+    pub async fn update_pool(&self, id: i32, pool: &dbm::UpdatePool, user_id: i32) -> Result<bool, StoreError> {
+        let success = sqlx::query_scalar_unchecked!(r#"SELECT update_pool($1, $2, $3);"#, id, pool, user_id)
+            .fetch_one(&self.pool)
+            .await
+            .context("Error updating pool in database")?;
+
+        Ok(success.unwrap())
+    }
+
+    pub async fn delete_pool(&self, id: i32, user_id: i32) -> Result<bool, StoreError> {
+        let success = sqlx::query_scalar_unchecked!(r#"SELECT delete_pool($1, $2);"#, id, user_id)
+            .fetch_one(&self.pool)
+            .await
+            .context("Error deleting pool in database")?;
+
+        Ok(success.unwrap())
+    }
+}
@@ -1,5 +1,9 @@
 use anyhow::Context;
+use sqlx::{Postgres, Transaction};
 
+use blazebooru_models::local as lm;
+
+use crate::transform::dbm_search_options_from_lm;
 use crate::{models as dbm, PgStore, StoreError};
 
 impl PgStore {
@@ -12,26 +16,28 @@ impl PgStore {
         Ok(post)
     }
 
-    pub async fn create_post(&self, post: &dbm::NewPost, tags: &[&str]) -> Result<i32, StoreError> {
+    /// Returns None if the parent post is not valid.
+    pub async fn create_post(&self, post: &dbm::NewPost, tags: &[&str]) -> Result<Option<i32>, StoreError> {
         let new_post_id = sqlx::query_scalar_unchecked!(r#"SELECT create_post($1, $2);"#, post, tags)
             .fetch_one(&self.pool)
             .await
             .context("Error creating post in database")?;
 
-        Ok(new_post_id.unwrap())
+        Ok(new_post_id)
     }
 
-    pub async fn update_post(&self, post: &dbm::UpdatePost, user_id: i32) -> Result<bool, StoreError> {
+    /// Returns None if the parent post is not valid.
+    pub async fn update_post(&self, post: &dbm::UpdatePost, user_id: i32) -> Result<Option<bool>, StoreError> {
         let success = sqlx::query_scalar_unchecked!(r#"SELECT update_post($1, $2);"#, post, user_id)
             .fetch_one(&self.pool)
             .await
             .context("Error updating post in database")?;
 
-        Ok(success.unwrap())
+        Ok(success)
     }
 
     pub async fn delete_post(&self, post_id: i32, user_id: i32) -> Result<bool, StoreError> {
-        let success = sqlx::query_scalar_unchecked!(r#"SELECT delete_post($1, $2);"#, post_id, user_id)
+        let success = sqlx::query_scalar_unchecked!(r#"SELECT delete_post($1, $2, NULL);"#, post_id, user_id)
             .fetch_one(&self.pool)
             .await
             .context("Error deleting post in database")?;
@@ -39,6 +45,88 @@ impl PgStore {
         Ok(success.unwrap())
     }
 
+    pub async fn get_deleted_posts(&self, start_id: i32, limit: i32) -> Result<Vec<dbm::ViewPost>, StoreError> {
+        let posts = sqlx::query_as!(
+            dbm::ViewPost,
+            r#"SELECT * FROM view_deleted_post WHERE id <= $1 ORDER BY id DESC LIMIT $2;"#,
+            Some(start_id),
+            i64::from(limit)
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Error getting deleted posts from database")?;
+
+        Ok(posts)
+    }
+
+    pub async fn undelete_post(&self, post_id: i32, user_id: i32) -> Result<bool, StoreError> {
+        let success = sqlx::query_scalar_unchecked!(r#"SELECT undelete_post($1, $2);"#, post_id, user_id)
+            .fetch_one(&self.pool)
+            .await
+            .context("Error undeleting post in database")?;
+
+        Ok(success.unwrap())
+    }
+
+    pub async fn purge_post(&self, post_id: i32, user_id: i32) -> Result<Option<dbm::PurgePostResult>, StoreError> {
+        let result = sqlx::query_as_unchecked!(
+            dbm::PurgePostResult,
+            r#"SELECT * FROM purge_post($1, $2);"#,
+            post_id,
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Error purging post in database")?;
+
+        Ok(result)
+    }
+
+    /// Lock the files of posts with a hash, which is held until the lock is released or dropped.
+    /// The files are only written and removed under the lock, so that they are not removed
+    /// between being written and used by a new post.
+    pub async fn lock_post_files(&self, hash: &str) -> Result<PostFilesLock, StoreError> {
+        let mut transaction = self.pool.begin().await.context("Error beginning transaction")?;
+
+        sqlx::query!(r#"SELECT pg_advisory_xact_lock(hashtext($1));"#, hash)
+            .execute(&mut *transaction)
+            .await
+            .context("Error locking post files in database")?;
+
+        Ok(PostFilesLock { transaction })
+    }
+
+    /// Check whether any post uses the files with a hash, including deleted posts.
+    pub async fn is_post_file_used(&self, hash: &str) -> Result<bool, StoreError> {
+        let used = sqlx::query_scalar!(r#"SELECT EXISTS (SELECT 1 FROM post WHERE hash = $1);"#, hash)
+            .fetch_one(&self.pool)
+            .await
+            .context("Error checking post file usage in database")?;
+
+        Ok(used.unwrap())
+    }
+
+    pub async fn get_post_child_ids(&self, id: i32) -> Result<Vec<i32>, StoreError> {
+        let child_ids = sqlx::query_scalar!(
+            r#"SELECT id FROM post WHERE parent_id = $1 AND NOT is_deleted ORDER BY id ASC;"#,
+            id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Error getting post children from database")?;
+
+        Ok(child_ids)
+    }
+
+    pub async fn is_valid_post_parent(&self, post_id: Option<i32>, parent_id: i32) -> Result<bool, StoreError> {
+        let valid = sqlx::query_scalar_unchecked!(r#"SELECT is_valid_post_parent($1, $2);"#, post_id, parent_id)
+            .fetch_one(&self.pool)
+            .await
+            .context("Error validating post parent in database")?;
+
+        Ok(valid.unwrap())
+    }
+
     pub async fn get_view_post(&self, id: i32) -> Result<Option<dbm::ViewPost>, StoreError> {
         let post = sqlx::query_as!(dbm::ViewPost, r#"SELECT * FROM view_post WHERE id = $1;"#, id)
             .fetch_optional(&self.pool)
@@ -59,16 +147,16 @@ impl PgStore {
 
     pub async fn get_view_posts(
         &self,
-        include_tags: &[String],
-        exclude_tags: &[String],
+        search: &lm::PostSearch<'_>,
         start_id: i32,
         limit: i32,
     ) -> Result<Vec<dbm::ViewPost>, StoreError> {
-        let posts = sqlx::query_as!(
+        let posts = sqlx::query_as_unchecked!(
             dbm::ViewPost,
-            r#"SELECT * FROM get_view_posts($1, $2, $3, $4);"#,
-            include_tags,
-            exclude_tags,
+            r#"SELECT * FROM get_view_posts($1, $2, $3, $4, $5);"#,
+            search.include_tags,
+            search.exclude_tags,
+            dbm_search_options_from_lm(search),
             start_id,
             limit
         )
@@ -81,8 +169,7 @@ impl PgStore {
 
     pub async fn calculate_pages(
         &self,
-        include_tags: &[&str],
-        exclude_tags: &[&str],
+        search: &lm::PostSearch<'_>,
         posts_per_page: i32,
         page_count: i32,
         origin_page: Option<dbm::PageInfo>,
@@ -90,9 +177,10 @@ impl PgStore {
         let pages = if page_count < 0 {
             sqlx::query_as_unchecked!(
                 dbm::PageInfo,
-                r#"SELECT * FROM unnest(calculate_pages_reverse($1, $2, $3, $4, $5));"#,
-                include_tags,
-                exclude_tags,
+                r#"SELECT * FROM unnest(calculate_pages_reverse($1, $2, $3, $4, $5, $6));"#,
+                search.include_tags,
+                search.exclude_tags,
+                dbm_search_options_from_lm(search),
                 posts_per_page,
                 -page_count,
                 origin_page
@@ -103,9 +191,10 @@ impl PgStore {
         } else {
             sqlx::query_as_unchecked!(
                 dbm::PageInfo,
-                r#"SELECT * FROM unnest(calculate_pages($1, $2, $3, $4, $5));"#,
-                include_tags,
-                exclude_tags,
+                r#"SELECT * FROM unnest(calculate_pages($1, $2, $3, $4, $5, $6));"#,
+                search.include_tags,
+                search.exclude_tags,
+                dbm_search_options_from_lm(search),
                 posts_per_page,
                 page_count,
                 origin_page
@@ -120,15 +209,15 @@ impl PgStore {
 
     pub async fn calculate_last_page(
         &self,
-        include_tags: &[&str],
-        exclude_tags: &[&str],
+        search: &lm::PostSearch<'_>,
         posts_per_page: i32,
     ) -> Result<dbm::PageInfo, StoreError> {
         let page = sqlx::query_as_unchecked!(
             dbm::PageInfo,
-            r#"SELECT * FROM calculate_last_page($1, $2, $3);"#,
-            include_tags,
-            exclude_tags,
+            r#"SELECT * FROM calculate_last_page($1, $2, $3, $4);"#,
+            search.include_tags,
+            search.exclude_tags,
+            dbm_search_options_from_lm(search),
             posts_per_page
         )
         .fetch_one(&self.pool)
@@ -137,4 +226,142 @@ impl PgStore {
 
         Ok(page)
     }
+
+    pub async fn count_posts(&self, search: &lm::PostSearch<'_>) -> Result<i32, StoreError> {
+        let count = sqlx::query_scalar_unchecked!(
+            r#"SELECT count_posts($1, $2, $3);"#,
+            search.include_tags,
+            search.exclude_tags,
+            dbm_search_options_from_lm(search)
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("Error counting posts in database")?;
+
+        Ok(count.unwrap())
+    }
+
+    pub async fn favorite_post(&self, post_id: i32, user_id: i32) -> Result<bool, StoreError> {
+        let success = sqlx::query_scalar_unchecked!(r#"SELECT favorite_post($1, $2);"#, post_id, user_id)
+            .fetch_one(&self.pool)
+            .await
+            .context("Error favoriting post in database")?;
+
+        Ok(success.unwrap())
+    }
+
+    pub async fn unfavorite_post(&self, post_id: i32, user_id: i32) -> Result<bool, StoreError> {
+        let success = sqlx::query_scalar_unchecked!(r#"SELECT unfavorite_post($1, $2);"#, post_id, user_id)
+            .fetch_one(&self.pool)
+            .await
+            .context("Error unfavoriting post in database")?;
+
+        Ok(success.unwrap())
+    }
+
+    pub async fn vote_post(&self, post_id: i32, user_id: i32, score: i32) -> Result<bool, StoreError> {
+        let success = sqlx::query_scalar_unchecked!(r#"SELECT vote_post($1, $2, $3);"#, post_id, user_id, score)
+            .fetch_one(&self.pool)
+            .await
+            .context("Error voting on post in database")?;
+
+        Ok(success.unwrap())
+    }
+
+    pub async fn unvote_post(&self, post_id: i32, user_id: i32) -> Result<bool, StoreError> {
+        let success = sqlx::query_scalar_unchecked!(r#"SELECT unvote_post($1, $2);"#, post_id, user_id)
+            .fetch_one(&self.pool)
+            .await
+            .context("Error retracting post vote in database")?;
+
+        Ok(success.unwrap())
+    }
+}
+
+/// Lock on the files of posts with a hash.
+pub struct PostFilesLock {
+    transaction: Transaction<'static, Postgres>,
+}
+
+impl PostFilesLock {
+    pub async fn release(self) -> Result<(), StoreError> {
+        self.transaction
+            .commit()
+            .await
+            .context("Error unlocking post files in database")?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use blazebooru_models::view as vm;
+
+    use super::*;
+    use crate::store::test_db;
+
+    fn search_ratings(ratings: &[vm::PostRating]) -> lm::PostSearch<'static> {
+        lm::PostSearch {
+            include_tags: Vec::new(),
+            exclude_tags: Vec::new(),
+            fav_user_name: None,
+            min_score: None,
+            max_score: None,
+            pool_id: None,
+            parent: None,
+            child: None,
+            ratings: Some(ratings.to_vec()),
+            exclude_ratings: Vec::new(),
+            after_id: None,
+            sort: lm::PostSort::Id,
+        }
+    }
+
+    #[tokio::test]
+    async fn rating_searches_are_cached() {
+        let Some(store) = test_db::create().await else {
+            return;
+        };
+
+        let user = test_db::create_user(&store, "admin").await;
+
+        let mut post_ids = Vec::new();
+        for (hash, rating) in [("a", "safe"), ("b", "questionable"), ("c", "explicit")] {
+            let post = dbm::NewPost {
+                rating: Some(rating.to_string()),
+                ..test_db::new_post(user.id, hash)
+            };
+            post_ids.push(store.create_post(&post, &[]).await.unwrap().unwrap());
+        }
+
+        let search = search_ratings(&[vm::PostRating::Safe, vm::PostRating::Questionable]);
+
+        let posts = store.get_view_posts(&search, i32::MAX, 10).await.unwrap();
+        assert_eq!(
+            posts.iter().map(|p| p.id).collect::<Vec<_>>(),
+            vec![Some(post_ids[1]), Some(post_ids[0])]
+        );
+
+        // The last page is taken from the search cache, which is created by the first calculation
+        let page = store.calculate_last_page(&search, 1).await.unwrap();
+        assert_eq!(page.no, Some(2));
+
+        // Changing the rating of a post updates the cached search
+        let update = dbm::UpdatePost {
+            id: Some(post_ids[1]),
+            title: None,
+            description: None,
+            source: None,
+            add_tags: Vec::new(),
+            remove_tags: Vec::new(),
+            parent_id: None,
+            update_parent: Some(false),
+            rating: Some("explicit".to_string()),
+        };
+        assert_eq!(store.update_post(&update, user.id).await.unwrap(), Some(true));
+
+        let page = store.calculate_last_page(&search, 1).await.unwrap();
+        assert_eq!(page.no, Some(1));
+    }
 }
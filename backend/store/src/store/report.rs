@@ -0,0 +1,95 @@
+use anyhow::Context;
+
+use crate::{models as dbm, PgStore, StoreError};
+
+impl PgStore {
+    pub async fn create_post_report(&self, report: &dbm::NewPostReport, user_id: i32) -> Result<bool, StoreError> {
+        let success = sqlx::query_scalar_unchecked!(r#"SELECT create_post_report($1, $2);"#, report, user_id)
+            .fetch_one(&self.pool)
+            .await
+            .context("Error creating post report in database")?;
+
+        Ok(success.unwrap())
+    }
+
+    pub async fn get_post_reports(&self, status: &str) -> Result<Vec<dbm::ViewPostReport>, StoreError> {
+        let reports = sqlx::query_as!(
+            dbm::ViewPostReport,
+            r#"SELECT * FROM view_post_report WHERE status = $1 ORDER BY id ASC;"#,
+            Some(status)
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Error getting post reports from database")?;
+
+        Ok(reports)
+    }
+
+    pub async fn resolve_post_report(
+        &self,
+        id: i32,
+        status: &str,
+        user_id: i32,
+    ) -> Result<Option<dbm::ResolvePostReportResult>, StoreError> {
+        let result = sqlx::query_as_unchecked!(
+            dbm::ResolvePostReportResult,
+            r#"SELECT * FROM resolve_post_report($1, $2, $3) WHERE status IS NOT NULL;"#,
+            id,
+            status,
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Error resolving post report in database")?;
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+
+    use super::*;
+    use crate::store::test_db;
+
+    const IP: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+    #[tokio::test]
+    async fn banning_invalidates_sessions() {
+        let Some(store) = test_db::create().await else {
+            return;
+        };
+
+        let admin = test_db::create_user(&store, "admin").await;
+        let user = test_db::create_user(&store, "alice").await;
+        let post_id = test_db::create_post(&store, user.id, "hash").await;
+
+        let refresh_token = store.create_refresh_token(user.id, IP, None).await.unwrap();
+
+        let report = dbm::NewPostReport {
+            post_id,
+            reason: "spam".to_string(),
+            comment: None,
+        };
+        assert!(store.create_post_report(&report, admin.id).await.unwrap());
+
+        let report_id = store.get_post_reports("open").await.unwrap()[0].id.unwrap();
+        let result = store
+            .resolve_post_report(report_id, "banned", admin.id)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(result.status.as_deref(), Some("banned"));
+        assert_eq!(result.sessions, Some(vec![refresh_token.session.unwrap()]));
+
+        // The banned user cannot log back in with a new token either
+        let refresh_token = store.create_refresh_token(user.id, IP, None).await.unwrap();
+        let result = store
+            .refresh_refresh_token(refresh_token.token.unwrap(), IP, None)
+            .await
+            .unwrap();
+        assert!(result.token.is_none() && result.session.is_none());
+    }
+}
@@ -0,0 +1,84 @@
+use anyhow::Context;
+
+use crate::{models as dbm, PgStore, StoreError};
+
+impl PgStore {
+    pub async fn get_saved_search(&self, id: i32, user_id: i32) -> Result<Option<dbm::SavedSearch>, StoreError> {
+        let saved_search = sqlx::query_as!(
+            dbm::SavedSearch,
+            r#"SELECT * FROM saved_search WHERE id = $1 AND user_id = $2;"#,
+            id,
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Error getting saved search from database")?;
+
+        Ok(saved_search)
+    }
+
+    pub async fn get_saved_searches(&self, user_id: i32) -> Result<Vec<dbm::SavedSearch>, StoreError> {
+        let saved_searches = sqlx::query_as!(
+            dbm::SavedSearch,
+            r#"SELECT * FROM saved_search WHERE user_id = $1 ORDER BY name ASC;"#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Error getting saved searches from database")?;
+
+        Ok(saved_searches)
+    }
+
+    pub async fn create_saved_search(&self, saved_search: &dbm::NewSavedSearch) -> Result<i32, StoreError> {
+        let new_saved_search_id = sqlx::query_scalar_unchecked!(r#"SELECT create_saved_search($1);"#, saved_search)
+            .fetch_one(&self.pool)
+            .await
+            .context("Error creating saved search in database")?;
+
+        Ok(new_saved_search_id.unwrap())
+    }
+
+    pub async fn update_saved_search(
+        &self,
+        id: i32,
+        saved_search: &dbm::UpdateSavedSearch,
+        user_id: i32,
+    ) -> Result<bool, StoreError> {
+        let success =
+            sqlx::query_scalar_unchecked!(r#"SELECT update_saved_search($1, $2, $3);"#, id, saved_search, user_id)
+                .fetch_one(&self.pool)
+                .await
+                .context("Error updating saved search in database")?;
+
+        Ok(success.unwrap())
+    }
+
+    pub async fn delete_saved_search(&self, id: i32, user_id: i32) -> Result<bool, StoreError> {
+        let success = sqlx::query_scalar_unchecked!(r#"SELECT delete_saved_search($1, $2);"#, id, user_id)
+            .fetch_one(&self.pool)
+            .await
+            .context("Error deleting saved search in database")?;
+
+        Ok(success.unwrap())
+    }
+
+    pub async fn mark_saved_search_seen(
+        &self,
+        id: i32,
+        last_seen_post_id: i32,
+        user_id: i32,
+    ) -> Result<bool, StoreError> {
+        let success = sqlx::query_scalar_unchecked!(
+            r#"SELECT mark_saved_search_seen($1, $2, $3);"#,
+            id,
+            last_seen_post_id,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("Error marking saved search as seen in database")?;
+
+        Ok(success.unwrap())
+    }
+}
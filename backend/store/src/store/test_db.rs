@@ -0,0 +1,85 @@
+//! Databases for tests of the database functions.
+//! Each test gets a new database on the server specified by `BLAZEBOORU_TEST_DATABASE_URL`,
+//! and is skipped if it is not set.
+//! The databases are kept after the tests, so that failures can be inspected.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use sqlx::postgres::{PgConnectOptions, PgPool};
+use sqlx::{Connection, Executor, PgConnection};
+
+use super::PgStore;
+use crate::models as dbm;
+
+const TEST_DATABASE_URL_VAR: &str = "BLAZEBOORU_TEST_DATABASE_URL";
+
+static NEXT_DATABASE: AtomicUsize = AtomicUsize::new(0);
+
+/// Create a migrated database for a test.
+/// Returns None if no database server is configured for tests.
+pub async fn create() -> Option<PgStore> {
+    let Ok(url) = std::env::var(TEST_DATABASE_URL_VAR) else {
+        eprintln!("{TEST_DATABASE_URL_VAR} is not set, skipping test");
+        return None;
+    };
+
+    let name = format!(
+        "blazebooru_test_{}_{}",
+        std::process::id(),
+        NEXT_DATABASE.fetch_add(1, Ordering::Relaxed)
+    );
+
+    let mut connection = PgConnection::connect(&url).await.unwrap();
+    connection
+        .execute(format!(r#"DROP DATABASE IF EXISTS "{name}";"#).as_str())
+        .await
+        .unwrap();
+    connection
+        .execute(format!(r#"CREATE DATABASE "{name}";"#).as_str())
+        .await
+        .unwrap();
+
+    let options = url.parse::<PgConnectOptions>().unwrap().database(&name);
+    let store = PgStore {
+        pool: PgPool::connect_lazy_with(options),
+    };
+
+    store.migrate().await.unwrap();
+
+    Some(store)
+}
+
+/// Create a user without an invite code.
+pub async fn create_user(store: &PgStore, name: &str) -> dbm::User {
+    let user = dbm::NewUser {
+        name: Some(name.to_string()),
+        password_hash: Some("hash".to_string()),
+        invite_code: None,
+    };
+
+    store.create_user(&user, false).await.unwrap().unwrap()
+}
+
+/// A post uploaded by a user, with files identified by the hash.
+pub fn new_post(user_id: i32, hash: &str) -> dbm::NewPost {
+    dbm::NewPost {
+        user_id: Some(user_id),
+        title: None,
+        description: None,
+        source: None,
+        filename: Some(format!("{hash}.png")),
+        size: Some(1),
+        width: Some(1),
+        height: Some(1),
+        hash: Some(hash.to_string()),
+        ext: Some("png".to_string()),
+        tn_ext: Some("webp".to_string()),
+        parent_id: None,
+        rating: None,
+    }
+}
+
+/// Create a post uploaded by a user.
+pub async fn create_post(store: &PgStore, user_id: i32, hash: &str) -> i32 {
+    store.create_post(&new_post(user_id, hash), &[]).await.unwrap().unwrap()
+}
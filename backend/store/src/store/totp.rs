@@ -0,0 +1,93 @@
+use anyhow::Context;
+
+use crate::{models as dbm, PgStore, StoreError};
+
+impl PgStore {
+    pub async fn get_user_totp(&self, user_id: i32) -> Result<Option<dbm::UserTotp>, StoreError> {
+        let totp = sqlx::query_as!(dbm::UserTotp, r#"SELECT * FROM user_totp WHERE user_id = $1;"#, user_id)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Error getting user TOTP from database")?;
+
+        Ok(totp)
+    }
+
+    /// Start a new TOTP enrollment, replacing any unconfirmed one.
+    /// Returns false if TOTP is already enabled for the user.
+    pub async fn enroll_user_totp(&self, user_id: i32, secret: &[u8]) -> Result<bool, StoreError> {
+        let result = sqlx::query_scalar!(
+            r#"INSERT INTO user_totp (user_id, secret) VALUES ($1, $2)
+            ON CONFLICT (user_id) DO UPDATE SET secret = EXCLUDED.secret WHERE NOT user_totp.confirmed
+            RETURNING user_id;"#,
+            user_id,
+            secret
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Error enrolling user TOTP in database")?;
+
+        Ok(result.is_some())
+    }
+
+    pub async fn confirm_user_totp(
+        &self,
+        user_id: i32,
+        step: i64,
+        recovery_code_hashes: &[String],
+    ) -> Result<bool, StoreError> {
+        let success = sqlx::query_scalar_unchecked!(
+            r#"SELECT confirm_user_totp($1, $2, $3);"#,
+            user_id,
+            step,
+            recovery_code_hashes
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("Error confirming user TOTP in database")?;
+
+        Ok(success.unwrap())
+    }
+
+    pub async fn disable_user_totp(&self, user_id: i32) -> Result<bool, StoreError> {
+        let success = sqlx::query_scalar_unchecked!(r#"SELECT disable_user_totp($1);"#, user_id)
+            .fetch_one(&self.pool)
+            .await
+            .context("Error disabling user TOTP in database")?;
+
+        Ok(success.unwrap())
+    }
+
+    /// Record the time step of a used TOTP code.
+    /// Returns false if a code from the same or a later step has already been used.
+    pub async fn use_user_totp_step(&self, user_id: i32, step: i64) -> Result<bool, StoreError> {
+        let result = sqlx::query_scalar!(
+            r#"UPDATE user_totp SET last_used_step = $2
+            WHERE user_id = $1 AND confirmed AND (last_used_step IS NULL OR last_used_step < $2)
+            RETURNING user_id;"#,
+            user_id,
+            step
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Error using user TOTP step in database")?;
+
+        Ok(result.is_some())
+    }
+
+    /// Mark a recovery code as used.
+    /// Returns false if the code does not exist or has already been used.
+    pub async fn use_user_recovery_code(&self, user_id: i32, code_hash: &str) -> Result<bool, StoreError> {
+        let result = sqlx::query_scalar!(
+            r#"UPDATE user_recovery_code SET used_at = CURRENT_TIMESTAMP
+            WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL
+            RETURNING id;"#,
+            user_id,
+            code_hash
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Error using user recovery code in database")?;
+
+        Ok(result.is_some())
+    }
+}
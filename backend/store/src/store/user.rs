@@ -3,13 +3,24 @@ use anyhow::Context;
 use crate::{models as dbm, PgStore, StoreError};
 
 impl PgStore {
-    pub async fn create_user(&self, user: &dbm::NewUser) -> Result<dbm::User, StoreError> {
-        let post = sqlx::query_as_unchecked!(dbm::User, r#"SELECT * FROM create_user($1);"#, user)
-            .fetch_one(&self.pool)
-            .await
-            .context("Error creating user in database")?;
+    /// Create a user.
+    /// Returns None if the invite code is not valid.
+    pub async fn create_user(
+        &self,
+        user: &dbm::NewUser,
+        require_invite_code: bool,
+    ) -> Result<Option<dbm::User>, StoreError> {
+        let user = sqlx::query_as_unchecked!(
+            dbm::User,
+            r#"SELECT * FROM create_user($1, $2) WHERE id IS NOT NULL;"#,
+            user,
+            require_invite_code
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Error creating user in database")?;
 
-        Ok(post)
+        Ok(user)
     }
 
     pub async fn get_user(&self, id: i32) -> Result<Option<dbm::User>, StoreError> {
@@ -29,4 +40,132 @@ impl PgStore {
 
         Ok(user)
     }
+
+    pub async fn update_user_max_rating(&self, id: i32, max_rating: Option<String>) -> Result<(), StoreError> {
+        sqlx::query!(r#"UPDATE "user" SET max_rating = $2 WHERE id = $1;"#, id, max_rating)
+            .execute(&self.pool)
+            .await
+            .context("Error updating user max rating in database")?;
+
+        Ok(())
+    }
+
+    pub async fn unban_user(&self, id: i32, admin_user_id: i32) -> Result<bool, StoreError> {
+        let success = sqlx::query_scalar_unchecked!(r#"SELECT unban_user($1, $2);"#, id, admin_user_id)
+            .fetch_one(&self.pool)
+            .await
+            .context("Error unbanning user in database")?;
+
+        Ok(success.unwrap())
+    }
+
+    pub async fn get_user_tag_blacklist(&self, id: i32) -> Result<Vec<String>, StoreError> {
+        let tags = sqlx::query_scalar!(
+            r#"SELECT tag FROM user_tag_blacklist WHERE user_id = $1 ORDER BY tag ASC;"#,
+            id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Error getting user tag blacklist from database")?;
+
+        Ok(tags)
+    }
+
+    pub async fn set_user_tag_blacklist(&self, id: i32, tags: &[String]) -> Result<(), StoreError> {
+        sqlx::query!(r#"SELECT set_user_tag_blacklist($1, $2);"#, id, tags)
+            .execute(&self.pool)
+            .await
+            .context("Error setting user tag blacklist in database")?;
+
+        Ok(())
+    }
+
+    pub async fn get_oidc_user(&self, issuer: &str, subject: &str) -> Result<Option<dbm::User>, StoreError> {
+        let user = sqlx::query_as!(
+            dbm::User,
+            r#"SELECT * FROM "user" WHERE id = (SELECT user_id FROM user_oidc_identity WHERE issuer = $1 AND subject = $2);"#,
+            issuer,
+            subject
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Error getting OIDC user from database")?;
+
+        Ok(user)
+    }
+
+    pub async fn create_oidc_user(
+        &self,
+        user: &dbm::NewUser,
+        issuer: &str,
+        subject: &str,
+    ) -> Result<Option<dbm::User>, StoreError> {
+        let user = sqlx::query_as_unchecked!(
+            dbm::User,
+            r#"SELECT * FROM create_oidc_user($1, $2, $3) WHERE id IS NOT NULL;"#,
+            user,
+            issuer,
+            subject
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Error creating OIDC user in database")?;
+
+        Ok(user)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::test_db;
+
+    fn new_user(name: &str, invite_code: Option<&str>) -> dbm::NewUser {
+        dbm::NewUser {
+            name: Some(name.to_string()),
+            password_hash: Some("hash".to_string()),
+            invite_code: invite_code.map(str::to_string),
+        }
+    }
+
+    #[tokio::test]
+    async fn first_user_needs_no_invite_code() {
+        let Some(store) = test_db::create().await else {
+            return;
+        };
+
+        let admin = store.create_user(&new_user("admin", None), true).await.unwrap();
+        assert_eq!(admin.map(|u| u.rank), Some(9001));
+
+        let user = store.create_user(&new_user("alice", None), true).await.unwrap();
+        assert!(user.is_none());
+    }
+
+    #[tokio::test]
+    async fn invite_codes_are_used_up() {
+        let Some(store) = test_db::create().await else {
+            return;
+        };
+
+        let admin = store
+            .create_user(&new_user("admin", None), true)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let invite_code = dbm::NewInviteCode {
+            code: Some("code".to_string()),
+            max_uses: Some(1),
+            expires_at: None,
+        };
+        store.create_invite_code(&invite_code, admin.id).await.unwrap();
+
+        let user = store.create_user(&new_user("alice", Some("code")), true).await.unwrap();
+        assert_eq!(user.and_then(|u| u.invited_by_user_id), Some(admin.id));
+        assert!(!store.is_invite_code_valid("code").await.unwrap());
+
+        // The code cannot be reused once it has been used up
+        let user = store.create_user(&new_user("bob", Some("code")), true).await.unwrap();
+        assert!(user.is_none());
+    }
 }
@@ -13,6 +13,29 @@ impl From<dbm::CreateRefreshTokenResult> for lm::CreateRefreshTokenResult {
     }
 }
 
+impl From<dbm::ApiKey> for lm::ApiKey {
+    fn from(k: dbm::ApiKey) -> Self {
+        lm::ApiKey {
+            id: k.id,
+            user_id: k.user_id,
+            scopes: api_key_scopes_from_dbm(&k.scopes),
+        }
+    }
+}
+
+impl From<dbm::ApiKey> for vm::ApiKey {
+    fn from(k: dbm::ApiKey) -> Self {
+        vm::ApiKey {
+            id: k.id,
+            created_at: k.created_at,
+            name: k.name,
+            key_prefix: k.key_prefix,
+            scopes: api_key_scopes_from_dbm(&k.scopes),
+            last_used_at: k.last_used_at,
+        }
+    }
+}
+
 impl From<dbm::User> for lm::User {
     fn from(u: dbm::User) -> Self {
         lm::User {
@@ -35,6 +58,41 @@ impl From<dbm::User> for vm::User {
     }
 }
 
+impl From<dbm::User> for vm::UserSettings {
+    fn from(u: dbm::User) -> Self {
+        vm::UserSettings {
+            max_rating: u.max_rating.as_deref().map(rating_from_dbm),
+        }
+    }
+}
+
+impl From<dbm::InviteCode> for vm::InviteCode {
+    fn from(i: dbm::InviteCode) -> Self {
+        vm::InviteCode {
+            id: i.id,
+            created_at: i.created_at,
+            code: i.code,
+            max_uses: i.max_uses,
+            uses: i.uses,
+            expires_at: i.expires_at,
+        }
+    }
+}
+
+impl From<dbm::ViewSession> for vm::Session {
+    fn from(s: dbm::ViewSession) -> Self {
+        vm::Session {
+            session: s.session.unwrap(),
+            created_at: s.created_at.unwrap(),
+            created_ip: s.created_ip.unwrap(),
+            last_seen_at: s.last_seen_at.unwrap(),
+            last_seen_ip: s.last_seen_ip.unwrap(),
+            user_agent: s.user_agent,
+            is_current: false,
+        }
+    }
+}
+
 impl From<dbm::ViewPost> for em::Post {
     fn from(p: dbm::ViewPost) -> Self {
         em::Post {
@@ -51,6 +109,7 @@ impl From<dbm::ViewPost> for em::Post {
             ext: p.ext.unwrap(),
             tn_ext: p.tn_ext.unwrap(),
             tags: p.tags.unwrap(),
+            rating: p.rating,
         }
     }
 }
@@ -73,6 +132,12 @@ impl From<dbm::ViewPost> for vm::Post {
             ext: p.ext.unwrap(),
             tn_ext: p.tn_ext.unwrap(),
             tags: p.tags.unwrap(),
+            fav_count: p.fav_count.unwrap(),
+            score: p.score.unwrap(),
+            parent_id: p.parent_id,
+            rating: rating_from_dbm(&p.rating.unwrap()),
+            child_ids: None,
+            pools: None,
         }
     }
 }
@@ -85,7 +150,94 @@ impl From<dbm::PostComment> for vm::Comment {
             updated_at: p.updated_at,
             user_id: p.user_id,
             user_name: p.user_name,
+            post_id: p.post_id,
             comment: p.comment,
+            is_edited: p.updated_at > p.created_at,
+            is_deleted: p.is_deleted,
+            is_held: p.is_held,
+            reply_to_id: p.reply_to_id,
+            depth: p.depth,
+        }
+    }
+}
+
+impl From<dbm::ViewPostComment> for vm::Comment {
+    fn from(p: dbm::ViewPostComment) -> Self {
+        vm::Comment {
+            id: p.id.unwrap(),
+            created_at: p.created_at.unwrap(),
+            updated_at: p.updated_at.unwrap(),
+            user_id: p.user_id,
+            user_name: p.user_name,
+            post_id: p.post_id.unwrap(),
+            comment: p.comment.unwrap(),
+            is_edited: p.is_edited.unwrap(),
+            is_deleted: p.is_deleted.unwrap(),
+            // Held comments are not included in the view
+            is_held: false,
+            reply_to_id: p.reply_to_id,
+            depth: p.depth.unwrap(),
+        }
+    }
+}
+
+impl From<dbm::ViewNotification> for vm::Notification {
+    fn from(n: dbm::ViewNotification) -> Self {
+        vm::Notification {
+            id: n.id.unwrap(),
+            created_at: n.created_at.unwrap(),
+            kind: n.kind.unwrap(),
+            actor_user_id: n.actor_user_id,
+            actor_user_name: n.actor_user_name,
+            post_id: n.post_id,
+            comment_id: n.comment_id,
+            is_read: n.is_read.unwrap(),
+        }
+    }
+}
+
+impl From<dbm::ViewAuditLogEntry> for vm::AuditLogEntry {
+    fn from(e: dbm::ViewAuditLogEntry) -> Self {
+        vm::AuditLogEntry {
+            id: e.id.unwrap(),
+            created_at: e.created_at.unwrap(),
+            user_id: e.user_id,
+            user_name: e.user_name,
+            action: e.action.unwrap(),
+            target_type: e.target_type.unwrap(),
+            target_id: e.target_id.unwrap(),
+            details: e.details,
+        }
+    }
+}
+
+impl From<vm::AuditLogFilter> for dbm::AuditLogFilter {
+    fn from(f: vm::AuditLogFilter) -> Self {
+        dbm::AuditLogFilter {
+            user_id: f.user_id,
+            action: f.action.map(dbm_audit_action_from_vm),
+            target_type: f.target_type.map(dbm_audit_target_type_from_vm),
+            target_id: f.target_id,
+        }
+    }
+}
+
+impl From<dbm::ViewPostReport> for vm::PostReport {
+    fn from(r: dbm::ViewPostReport) -> Self {
+        vm::PostReport {
+            id: r.id.unwrap(),
+            created_at: r.created_at.unwrap(),
+            post_id: r.post_id.unwrap(),
+            post_user_id: r.post_user_id.unwrap(),
+            user_id: r.user_id.unwrap(),
+            user_name: r.user_name.unwrap(),
+            reason: report_reason_from_dbm(&r.reason.unwrap()),
+            comment: r.comment,
+            status: report_status_from_dbm(&r.status.unwrap()),
+            resolved_at: r.resolved_at,
+            resolved_by_user_id: r.resolved_by_user_id,
+            resolved_by_user_name: r.resolved_by_user_name,
+            post: None,
         }
     }
 }
@@ -120,6 +272,79 @@ impl From<dbm::ViewTag> for vm::Tag {
     }
 }
 
+impl From<dbm::ViewPool> for vm::Pool {
+    fn from(p: dbm::ViewPool) -> Self {
+        vm::Pool {
+            id: p.id.unwrap(),
+            created_at: p.created_at.unwrap(),
+            updated_at: p.updated_at.unwrap(),
+            user_id: p.user_id.unwrap(),
+            user_name: p.user_name.unwrap(),
+            name: p.name.unwrap(),
+            description: p.description,
+            post_ids: p.post_ids.unwrap(),
+        }
+    }
+}
+
+impl From<dbm::PostPool> for vm::PostPool {
+    fn from(p: dbm::PostPool) -> Self {
+        vm::PostPool {
+            id: p.id.unwrap(),
+            name: p.name.unwrap(),
+            previous_post_id: p.previous_post_id,
+            next_post_id: p.next_post_id,
+        }
+    }
+}
+
+impl From<dbm::ExportPool> for em::Pool {
+    fn from(p: dbm::ExportPool) -> Self {
+        em::Pool {
+            created_at: p.created_at.unwrap(),
+            user_name: p.user_name.unwrap(),
+            name: p.name.unwrap(),
+            description: p.description,
+            post_hashes: p.post_hashes.unwrap(),
+        }
+    }
+}
+
+impl From<vm::UpdatePool> for dbm::UpdatePool {
+    fn from(p: vm::UpdatePool) -> Self {
+        dbm::UpdatePool {
+            name: Some(p.name),
+            description: p.description.filter(|v| !v.is_empty()),
+            post_ids: p.post_ids,
+        }
+    }
+}
+
+impl From<dbm::SavedSearch> for vm::SavedSearch {
+    fn from(s: dbm::SavedSearch) -> Self {
+        vm::SavedSearch {
+            id: s.id,
+            created_at: s.created_at,
+            updated_at: s.updated_at,
+            name: s.name,
+            include_tags: s.include_tags,
+            exclude_tags: s.exclude_tags,
+            last_seen_post_id: s.last_seen_post_id,
+            new_count: None,
+        }
+    }
+}
+
+impl From<vm::UpdateSavedSearch> for dbm::UpdateSavedSearch {
+    fn from(s: vm::UpdateSavedSearch) -> Self {
+        dbm::UpdateSavedSearch {
+            name: Some(s.name),
+            include_tags: s.include_tags,
+            exclude_tags: s.exclude_tags,
+        }
+    }
+}
+
 impl From<vm::UpdateTag> for dbm::UpdateTag {
     fn from(t: vm::UpdateTag) -> Self {
         dbm::UpdateTag {
@@ -139,5 +364,205 @@ pub fn dbm_update_post_from_vm(id: i32, p: vm::UpdatePost) -> dbm::UpdatePost {
         source: p.source.filter(|v| !v.is_empty()),
         add_tags: p.add_tags,
         remove_tags: p.remove_tags,
+        parent_id: p.parent_id.flatten(),
+        update_parent: Some(p.parent_id.is_some()),
+        rating: p.rating.map(dbm_rating_from_vm),
+    }
+}
+
+pub fn dbm_search_options_from_lm(s: &lm::PostSearch) -> dbm::SearchOptions {
+    let sort = match s.sort {
+        lm::PostSort::Id => "id",
+        lm::PostSort::FavCount => "fav_count",
+        lm::PostSort::Score => "score",
+    };
+
+    dbm::SearchOptions {
+        fav_user_name: s.fav_user_name.map(|n| n.to_string()),
+        min_score: s.min_score,
+        max_score: s.max_score,
+        sort: Some(sort.to_string()),
+        pool_id: s.pool_id,
+        parent_id: relation_post_id(s.parent),
+        has_parent: relation_exists(s.parent),
+        child_id: relation_post_id(s.child),
+        has_children: relation_exists(s.child),
+        ratings: search_ratings(s).map(|ratings| ratings.into_iter().map(dbm_rating_from_vm).collect()),
+        after_id: s.after_id,
+    }
+}
+
+/// Get the ratings included in a search, without the excluded ratings.
+fn search_ratings(s: &lm::PostSearch) -> Option<Vec<vm::PostRating>> {
+    if s.ratings.is_none() && s.exclude_ratings.is_empty() {
+        return None;
+    }
+
+    let ratings = s.ratings.as_deref().unwrap_or(&vm::PostRating::ALL);
+
+    Some(
+        ratings
+            .iter()
+            .copied()
+            .filter(|r| !s.exclude_ratings.contains(r))
+            .collect(),
+    )
+}
+
+fn relation_post_id(filter: Option<lm::RelationFilter>) -> Option<i32> {
+    match filter {
+        Some(lm::RelationFilter::Post(id)) => Some(id),
+        _ => None,
+    }
+}
+
+fn relation_exists(filter: Option<lm::RelationFilter>) -> Option<bool> {
+    match filter {
+        Some(lm::RelationFilter::Any) => Some(true),
+        Some(lm::RelationFilter::None) => Some(false),
+        _ => None,
+    }
+}
+
+pub fn dbm_rating_from_vm(rating: vm::PostRating) -> String {
+    match rating {
+        vm::PostRating::Safe => "safe",
+        vm::PostRating::Questionable => "questionable",
+        vm::PostRating::Explicit => "explicit",
+    }
+    .to_string()
+}
+
+/// Unknown ratings are treated as explicit, to err on the side of hiding posts.
+fn rating_from_dbm(rating: &str) -> vm::PostRating {
+    match rating {
+        "safe" => vm::PostRating::Safe,
+        "questionable" => vm::PostRating::Questionable,
+        _ => vm::PostRating::Explicit,
+    }
+}
+
+pub fn dbm_api_key_scopes_from_vm(scopes: &[vm::ApiKeyScope]) -> Vec<String> {
+    scopes
+        .iter()
+        .map(|s| match s {
+            vm::ApiKeyScope::Read => "read",
+            vm::ApiKeyScope::Upload => "upload",
+            vm::ApiKeyScope::EditTags => "edit_tags",
+        })
+        .map(|s| s.to_string())
+        .collect()
+}
+
+/// Unknown scopes are ignored, so that removing a scope
+/// in the future does not break existing API keys.
+fn api_key_scopes_from_dbm(scopes: &[String]) -> Vec<vm::ApiKeyScope> {
+    scopes
+        .iter()
+        .filter_map(|s| match s.as_str() {
+            "read" => Some(vm::ApiKeyScope::Read),
+            "upload" => Some(vm::ApiKeyScope::Upload),
+            "edit_tags" => Some(vm::ApiKeyScope::EditTags),
+            _ => None,
+        })
+        .collect()
+}
+
+pub fn dbm_report_reason_from_vm(reason: vm::ReportReason) -> String {
+    match reason {
+        vm::ReportReason::Spam => "spam",
+        vm::ReportReason::Illegal => "illegal",
+        vm::ReportReason::Offensive => "offensive",
+        vm::ReportReason::WrongRating => "wrong_rating",
+        vm::ReportReason::Duplicate => "duplicate",
+        vm::ReportReason::Other => "other",
+    }
+    .to_string()
+}
+
+fn report_reason_from_dbm(reason: &str) -> vm::ReportReason {
+    match reason {
+        "spam" => vm::ReportReason::Spam,
+        "illegal" => vm::ReportReason::Illegal,
+        "offensive" => vm::ReportReason::Offensive,
+        "wrong_rating" => vm::ReportReason::WrongRating,
+        "duplicate" => vm::ReportReason::Duplicate,
+        _ => vm::ReportReason::Other,
+    }
+}
+
+pub fn dbm_report_status_from_vm(status: vm::ReportStatus) -> String {
+    match status {
+        vm::ReportStatus::Open => "open",
+        vm::ReportStatus::Dismissed => "dismissed",
+        vm::ReportStatus::Deleted => "deleted",
+        vm::ReportStatus::Banned => "banned",
+    }
+    .to_string()
+}
+
+/// Unknown statuses are treated as open, so that the reports are not lost.
+pub fn report_status_from_dbm(status: &str) -> vm::ReportStatus {
+    match status {
+        "dismissed" => vm::ReportStatus::Dismissed,
+        "deleted" => vm::ReportStatus::Deleted,
+        "banned" => vm::ReportStatus::Banned,
+        _ => vm::ReportStatus::Open,
+    }
+}
+
+pub fn dbm_audit_action_from_vm(action: vm::AuditAction) -> String {
+    match action {
+        vm::AuditAction::DeletePost => "delete_post",
+        vm::AuditAction::UndeletePost => "undelete_post",
+        vm::AuditAction::PurgePost => "purge_post",
+        vm::AuditAction::DeleteComment => "delete_comment",
+        vm::AuditAction::ApproveComment => "approve_comment",
+        vm::AuditAction::UpdateTag => "update_tag",
+        vm::AuditAction::DismissReport => "dismiss_report",
+        vm::AuditAction::BanUser => "ban_user",
+        vm::AuditAction::UnbanUser => "unban_user",
+    }
+    .to_string()
+}
+
+pub fn dbm_audit_target_type_from_vm(target_type: vm::AuditTargetType) -> String {
+    match target_type {
+        vm::AuditTargetType::Post => "post",
+        vm::AuditTargetType::Comment => "comment",
+        vm::AuditTargetType::Tag => "tag",
+        vm::AuditTargetType::Report => "report",
+        vm::AuditTargetType::User => "user",
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn audit_log_filter_uses_the_names_recorded_by_the_database() {
+        let filter = dbm::AuditLogFilter::from(vm::AuditLogFilter {
+            user_id: Some(1),
+            action: Some(vm::AuditAction::UnbanUser),
+            target_type: Some(vm::AuditTargetType::User),
+            target_id: Some(2),
+        });
+
+        assert_eq!(filter.action.as_deref(), Some("unban_user"));
+        assert_eq!(filter.target_type.as_deref(), Some("user"));
+    }
+
+    #[test]
+    fn report_statuses_round_trip() {
+        for status in [
+            vm::ReportStatus::Open,
+            vm::ReportStatus::Dismissed,
+            vm::ReportStatus::Deleted,
+            vm::ReportStatus::Banned,
+        ] {
+            assert_eq!(report_status_from_dbm(&dbm_report_status_from_vm(status)), status);
+        }
     }
 }